@@ -2,7 +2,7 @@ use crate::domain::RegistryConfig;
 use anyhow::{Context, Result, anyhow, bail};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
 // Response types
@@ -26,6 +26,100 @@ pub struct CratesGcReport {
 pub struct DockerGcReport {
     pub deleted: usize,
     pub kept: usize,
+    /// Unreferenced blobs kept because they are within the grace period
+    #[serde(default)]
+    pub kept_recent: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserInfo {
+    pub login: String,
+    pub admin: bool,
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsersResponse {
+    users: Vec<UserInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub login: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokensResponse {
+    tokens: Vec<TokenInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadSession {
+    pub repository: String,
+    pub uuid: String,
+    pub size_bytes: u64,
+    pub age_seconds: u64,
+    pub idle_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadSessions {
+    /// Idle time after which sessions are reaped; `None` when disabled
+    pub ttl_seconds: Option<u64>,
+    pub sessions: Vec<UploadSession>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: String,
+    pub subject: Option<String>,
+    pub client_ip: String,
+    pub action: String,
+    pub target: String,
+    pub version: Option<String>,
+    pub digest: Option<String>,
+    pub outcome: String,
+    pub status: u16,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditPage {
+    /// Matching entries, newest first
+    pub events: Vec<AuditEvent>,
+    /// Number of matching entries across all pages
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatedToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: TokenInfo,
+}
+
+// ---------------------------------------------------------------------------
+// Request types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+pub struct UserRequest<'a> {
+    pub password: &'a str,
+    pub admin: bool,
+    pub scopes: &'a [String],
+}
+
+#[derive(Serialize)]
+pub struct CreateTokenRequest<'a> {
+    pub name: &'a str,
+    pub login: Option<&'a str>,
+    pub scopes: &'a [String],
+    pub expires_in_days: Option<i64>,
 }
 
 // ---------------------------------------------------------------------------
@@ -126,4 +220,182 @@ impl AdminApi {
 
         Ok(report)
     }
+
+    pub async fn list_users(&self, registry: &RegistryConfig) -> Result<Vec<UserInfo>> {
+        let response = self
+            .send(self.request(registry, reqwest::Method::GET, "/admin/users")?)
+            .await?;
+        let body: UsersResponse = response
+            .json()
+            .await
+            .context("failed to decode users response")?;
+
+        Ok(body.users)
+    }
+
+    pub async fn put_user(
+        &self,
+        registry: &RegistryConfig,
+        login: &str,
+        request: &UserRequest<'_>,
+    ) -> Result<UserInfo> {
+        let endpoint = format!("/admin/users/{login}");
+        let response = self
+            .send(
+                self.request(registry, reqwest::Method::PUT, &endpoint)?
+                    .json(request),
+            )
+            .await?;
+
+        response
+            .json()
+            .await
+            .context("failed to decode user response")
+    }
+
+    pub async fn remove_user(&self, registry: &RegistryConfig, login: &str) -> Result<()> {
+        let endpoint = format!("/admin/users/{login}");
+        self.send(self.request(registry, reqwest::Method::DELETE, &endpoint)?)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_tokens(
+        &self,
+        registry: &RegistryConfig,
+        login: Option<&str>,
+    ) -> Result<Vec<TokenInfo>> {
+        let endpoint = match login {
+            Some(login) => format!("/admin/tokens?login={login}"),
+            None => "/admin/tokens".to_string(),
+        };
+        let request = self.request(registry, reqwest::Method::GET, &endpoint)?;
+        let body: TokensResponse = self
+            .send(request)
+            .await?
+            .json()
+            .await
+            .context("failed to decode tokens response")?;
+
+        Ok(body.tokens)
+    }
+
+    pub async fn create_token(
+        &self,
+        registry: &RegistryConfig,
+        request: &CreateTokenRequest<'_>,
+    ) -> Result<CreatedToken> {
+        let response = self
+            .send(
+                self.request(registry, reqwest::Method::POST, "/admin/tokens")?
+                    .json(request),
+            )
+            .await?;
+
+        response
+            .json()
+            .await
+            .context("failed to decode token response")
+    }
+
+    pub async fn revoke_token(&self, registry: &RegistryConfig, id: &str) -> Result<()> {
+        let endpoint = format!("/admin/tokens/{id}");
+        self.send(self.request(registry, reqwest::Method::DELETE, &endpoint)?)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_upload_sessions(&self, registry: &RegistryConfig) -> Result<UploadSessions> {
+        let request = self.request(registry, reqwest::Method::GET, "/admin/docker/uploads")?;
+
+        self.send(request)
+            .await?
+            .json()
+            .await
+            .context("failed to decode upload sessions response")
+    }
+
+    /// Query the audit log; `filters` are `(parameter, value)` pairs such as
+    /// `("action", "crates.yank")`.
+    pub async fn audit_log(
+        &self,
+        registry: &RegistryConfig,
+        filters: &[(&str, &str)],
+    ) -> Result<AuditPage> {
+        let query: Vec<String> = filters
+            .iter()
+            .map(|(key, value)| format!("{key}={}", encode_query_value(value)))
+            .collect();
+        let endpoint = format!("/admin/audit?{}", query.join("&"));
+        let request = self.request(registry, reqwest::Method::GET, &endpoint)?;
+
+        self.send(request)
+            .await?
+            .json()
+            .await
+            .context("failed to decode audit log response")
+    }
+
+    /// Builds an admin API request. Docker credentials (Basic) are preferred,
+    /// falling back to the crates token (Bearer), which must carry the
+    /// `admin:*:*` scope.
+    fn request(
+        &self,
+        registry: &RegistryConfig,
+        method: reqwest::Method,
+        endpoint: &str,
+    ) -> Result<reqwest::RequestBuilder> {
+        let base = if registry.docker.url.is_empty() {
+            &registry.crates.url
+        } else {
+            &registry.docker.url
+        };
+        let url = format!("{}{}", base.trim_end_matches('/'), endpoint);
+        let mut request = self.client.request(method, url);
+
+        if let (Some(username), Some(password)) =
+            (&registry.docker.username, &registry.docker.password)
+        {
+            request = request.basic_auth(username, Some(password));
+        } else if let Some(token) = &registry.crates.token {
+            request = request.bearer_auth(token);
+        } else {
+            bail!(
+                "no credentials configured; run `warehouse docker login` or `warehouse crates login`"
+            );
+        }
+
+        Ok(request)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .context("failed to send admin request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("request failed: {} {}", status, body);
+        }
+
+        Ok(response)
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, so RFC 3339 offsets
+/// (`+02:00`) survive the query string.
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
 }
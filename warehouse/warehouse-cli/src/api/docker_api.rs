@@ -232,13 +232,9 @@ impl DockerApi {
 
 fn parse_bearer_challenge(headers: &HeaderMap) -> Option<BearerChallenge> {
     let raw = headers.get(WWW_AUTHENTICATE)?.to_str().ok()?.trim();
-    let raw = if let Some(rest) = raw.strip_prefix("Bearer ") {
-        rest
-    } else if let Some(rest) = raw.strip_prefix("bearer ") {
-        rest
-    } else {
-        return None;
-    };
+    let raw = raw
+        .strip_prefix("Bearer ")
+        .or_else(|| raw.strip_prefix("bearer "))?;
 
     let mut realm = None;
     let mut service = None;
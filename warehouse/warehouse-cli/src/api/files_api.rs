@@ -0,0 +1,294 @@
+use crate::domain::RegistryConfig;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub struct FileInfo {
+    pub repository: String,
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub content_type: String,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepositorySummary {
+    pub name: String,
+    pub files: u64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryList {
+    repositories: Vec<RepositorySummary>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileEntry {
+    pub path: String,
+    /// `file` or `directory`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub size_bytes: u64,
+    pub sha256: Option<String>,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DirectoryListing {
+    pub entries: Vec<FileEntry>,
+}
+
+/// Outcome of a download written to disk (or stdout).
+pub struct Downloaded {
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+// ---------------------------------------------------------------------------
+// Client
+// ---------------------------------------------------------------------------
+
+pub struct FilesApi {
+    client: reqwest::Client,
+}
+
+impl FilesApi {
+    pub fn new(reg: &RegistryConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(reg.crates.insecure_tls || reg.docker.insecure_tls)
+            .build()
+            .context("failed to build HTTP client")?;
+
+        Ok(Self { client })
+    }
+
+    pub async fn upload(
+        &self,
+        registry: &RegistryConfig,
+        repository: &str,
+        path: &str,
+        data: Vec<u8>,
+        content_type: Option<&str>,
+        metadata: &[(String, String)],
+    ) -> Result<FileInfo> {
+        let sha256 = format!("{:x}", Sha256::digest(&data));
+        let mut request = self
+            .request(
+                registry,
+                reqwest::Method::PUT,
+                &file_endpoint(repository, path),
+            )?
+            .header("X-Checksum-Sha256", sha256)
+            .header(
+                "Content-Type",
+                content_type.unwrap_or("application/octet-stream"),
+            );
+        for (key, value) in metadata {
+            request = request.header(format!("X-Meta-{key}"), value);
+        }
+
+        self.send(request.body(data))
+            .await?
+            .json()
+            .await
+            .context("failed to decode upload response")
+    }
+
+    /// Streams a file to `output` (stdout when `None`) and verifies it
+    /// against the checksum the server reports.
+    pub async fn download(
+        &self,
+        registry: &RegistryConfig,
+        repository: &str,
+        path: &str,
+        output: Option<&Path>,
+    ) -> Result<Downloaded> {
+        let request = self.request(
+            registry,
+            reqwest::Method::GET,
+            &file_endpoint(repository, path),
+        )?;
+        let mut response = self.send(request).await?;
+        let expected = response
+            .headers()
+            .get("X-Checksum-Sha256")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        if expected.is_none()
+            && response
+                .headers()
+                .get("Content-Type")
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.starts_with("application/json"))
+        {
+            bail!("'{path}' is a directory; use `warehouse files ls`");
+        }
+
+        // Written next to the target and renamed once verified, so a failed
+        // download never leaves a truncated file behind.
+        let partial = output.map(|p| PathBuf::from(format!("{}.part", p.display())));
+        let mut file = match &partial {
+            Some(partial) => Some(
+                tokio::fs::File::create(partial)
+                    .await
+                    .with_context(|| format!("failed to create {}", partial.display()))?,
+            ),
+            None => None,
+        };
+
+        let mut hasher = Sha256::new();
+        let mut bytes = 0u64;
+        while let Some(chunk) = response.chunk().await.context("download failed")? {
+            hasher.update(&chunk);
+            bytes += chunk.len() as u64;
+            match &mut file {
+                Some(file) => file.write_all(&chunk).await,
+                None => std::io::stdout().write_all(&chunk),
+            }
+            .context("failed to write file")?;
+        }
+        match &mut file {
+            Some(file) => file.flush().await,
+            None => std::io::stdout().flush(),
+        }
+        .context("failed to write file")?;
+        drop(file);
+
+        let sha256 = format!("{:x}", hasher.finalize());
+        if let Some(expected) = expected
+            && !expected.eq_ignore_ascii_case(&sha256)
+        {
+            if let Some(partial) = &partial {
+                let _ = tokio::fs::remove_file(partial).await;
+            }
+            bail!("checksum mismatch: expected {expected}, got {sha256}");
+        }
+        if let (Some(partial), Some(output)) = (&partial, output) {
+            tokio::fs::rename(partial, output)
+                .await
+                .with_context(|| format!("failed to write {}", output.display()))?;
+        }
+
+        Ok(Downloaded { bytes, sha256 })
+    }
+
+    pub async fn list_repositories(
+        &self,
+        registry: &RegistryConfig,
+    ) -> Result<Vec<RepositorySummary>> {
+        let request = self.request(registry, reqwest::Method::GET, "/api/v1/files")?;
+        let body: RepositoryList = self
+            .send(request)
+            .await?
+            .json()
+            .await
+            .context("failed to decode repositories response")?;
+
+        Ok(body.repositories)
+    }
+
+    pub async fn list_directory(
+        &self,
+        registry: &RegistryConfig,
+        repository: &str,
+        path: &str,
+        recursive: bool,
+    ) -> Result<DirectoryListing> {
+        let path = path.trim_matches('/');
+        let mut endpoint = file_endpoint(repository, path);
+        if !path.is_empty() {
+            endpoint.push('/');
+        }
+        if recursive {
+            endpoint.push_str("?recursive=true");
+        }
+
+        self.send(self.request(registry, reqwest::Method::GET, &endpoint)?)
+            .await?
+            .json()
+            .await
+            .context("failed to decode listing response")
+    }
+
+    /// Builds a files API request with the same credentials as the admin
+    /// API: docker credentials (Basic), else the crates token (Bearer).
+    /// Anonymous requests are sent when neither is configured, which is
+    /// enough for downloads unless the server requires auth for reads.
+    fn request(
+        &self,
+        registry: &RegistryConfig,
+        method: reqwest::Method,
+        endpoint: &str,
+    ) -> Result<reqwest::RequestBuilder> {
+        let base = if registry.docker.url.is_empty() {
+            &registry.crates.url
+        } else {
+            &registry.docker.url
+        };
+        if base.is_empty() {
+            bail!("registry has no URL configured");
+        }
+        let url = format!("{}{}", base.trim_end_matches('/'), endpoint);
+        let mut request = self.client.request(method, url);
+
+        if let (Some(username), Some(password)) =
+            (&registry.docker.username, &registry.docker.password)
+        {
+            request = request.basic_auth(username, Some(password));
+        } else if let Some(token) = &registry.crates.token {
+            request = request.bearer_auth(token);
+        }
+
+        Ok(request)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .context("failed to send files request")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("request failed: {} {}", status, body);
+        }
+
+        Ok(response)
+    }
+}
+
+/// `/api/v1/files/<repo>/<path>` with every path segment percent-encoded.
+fn file_endpoint(repository: &str, path: &str) -> String {
+    let encoded: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(encode_segment)
+        .collect();
+    format!("/api/v1/files/{repository}/{}", encoded.join("/"))
+}
+
+fn encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char);
+            }
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
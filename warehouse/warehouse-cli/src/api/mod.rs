@@ -1,3 +1,4 @@
 pub mod admin_api;
 pub mod crates_api;
 pub mod docker_api;
+pub mod files_api;
@@ -1,15 +1,20 @@
-use crate::api::admin_api::AdminApi;
+use crate::api::admin_api::{AdminApi, CreateTokenRequest, UserRequest};
 use crate::api::crates_api::CratesApi;
 use crate::api::docker_api::DockerApi;
+use crate::api::files_api::FilesApi;
 use crate::cli::{
-    AdminCommands, AdminGcArgs, CatalogArgs, Cli, Commands, CratesCommands, CratesLoginArgs,
-    CratesRegistryAddArgs, CratesRegistryCommands, CratesRegistryRemoveArgs, CratesRegistryUseArgs,
-    CratesSearchArgs, CratesUnyankArgs, CratesVersionsArgs, CratesYankArgs, DockerCommands,
-    LoginArgs, RegistryAddArgs, RegistryCommands, RegistryRemoveArgs, RegistryUseArgs, TagsArgs,
+    AdminAuditArgs, AdminCommands, AdminGcArgs, AdminListArgs, AdminTokenCreateArgs,
+    AdminTokenListArgs, AdminTokenRevokeArgs, AdminTokensCommands, AdminUserAddArgs,
+    AdminUserRemoveArgs, AdminUsersCommands, CatalogArgs, Cli, Commands, CratesCommands,
+    CratesLoginArgs, CratesRegistryAddArgs, CratesRegistryCommands, CratesRegistryRemoveArgs,
+    CratesRegistryUseArgs, CratesSearchArgs, CratesUnyankArgs, CratesVersionsArgs, CratesYankArgs,
+    DockerCommands, FilesCommands, FilesDownloadArgs, FilesLsArgs, FilesUploadArgs, LoginArgs,
+    RegistryAddArgs, RegistryCommands, RegistryRemoveArgs, RegistryUseArgs, TagsArgs,
 };
 use crate::config::{ConfigScope, ConfigStore, RegistrySource};
 use crate::domain::{RegistryConfig, validate_registry_name};
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use std::path::PathBuf;
 
 pub async fn run(cli: Cli) -> Result<()> {
     let store = ConfigStore::new();
@@ -17,6 +22,7 @@ pub async fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Docker { command } => run_docker(&store, command).await,
         Commands::Crates { command } => run_crates(&store, command).await,
+        Commands::Files { command } => run_files(&store, command).await,
         Commands::Admin { command } => run_admin(&store, command).await,
     }
 }
@@ -470,6 +476,157 @@ async fn cmd_crates_unyank(store: &ConfigStore, args: CratesUnyankArgs) -> Resul
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Files commands
+// ---------------------------------------------------------------------------
+
+async fn run_files(store: &ConfigStore, command: FilesCommands) -> Result<()> {
+    match command {
+        FilesCommands::Upload(args) => cmd_files_upload(store, args).await,
+        FilesCommands::Download(args) => cmd_files_download(store, args).await,
+        FilesCommands::Ls(args) => cmd_files_ls(store, args).await,
+    }
+}
+
+async fn cmd_files_upload(store: &ConfigStore, args: FilesUploadArgs) -> Result<()> {
+    let metadata = args
+        .meta
+        .iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.to_string()))
+            }
+            _ => bail!("invalid --meta '{entry}'; expected key=value"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let data = tokio::fs::read(&args.file)
+        .await
+        .with_context(|| format!("failed to read {}", args.file.display()))?;
+
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let files_api = FilesApi::new(&registry)?;
+
+    let info = files_api
+        .upload(
+            &registry,
+            &args.repository,
+            &args.path,
+            data,
+            args.content_type.as_deref(),
+            &metadata,
+        )
+        .await?;
+
+    println!(
+        "uploaded {}/{} ({} bytes) to registry '{}'",
+        info.repository, info.path, info.size_bytes, registry_name
+    );
+    println!("sha256: {}", info.sha256);
+    println!("content type: {}", info.content_type);
+    for (key, value) in &info.metadata {
+        println!("{key}: {value}");
+    }
+    Ok(())
+}
+
+async fn cmd_files_download(store: &ConfigStore, args: FilesDownloadArgs) -> Result<()> {
+    let output = match args.output {
+        Some(path) if path.as_os_str() == "-" => None,
+        Some(path) => Some(path),
+        None => {
+            let name = args
+                .path
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| anyhow!("invalid path '{}'", args.path))?;
+            Some(PathBuf::from(name))
+        }
+    };
+
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let files_api = FilesApi::new(&registry)?;
+
+    let downloaded = files_api
+        .download(&registry, &args.repository, &args.path, output.as_deref())
+        .await?;
+
+    // Keep stdout clean when it carries the file itself.
+    if let Some(output) = output {
+        println!(
+            "downloaded {}/{} ({} bytes) to {}",
+            args.repository,
+            args.path,
+            downloaded.bytes,
+            output.display()
+        );
+        println!("sha256: {}", downloaded.sha256);
+    }
+    Ok(())
+}
+
+async fn cmd_files_ls(store: &ConfigStore, args: FilesLsArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let files_api = FilesApi::new(&registry)?;
+
+    let Some(repository) = args.repository else {
+        let repositories = files_api.list_repositories(&registry).await?;
+        if repositories.is_empty() {
+            println!("no repositories found");
+            return Ok(());
+        }
+
+        println!("{:<40}  {:>8}  {:>14}", "repository", "files", "bytes");
+        println!("{}", "-".repeat(66));
+        for repository in repositories {
+            println!(
+                "{:<40}  {:>8}  {:>14}",
+                repository.name, repository.files, repository.size_bytes
+            );
+        }
+        return Ok(());
+    };
+
+    let listing = files_api
+        .list_directory(
+            &registry,
+            &repository,
+            args.path.as_deref().unwrap_or_default(),
+            args.recursive,
+        )
+        .await?;
+
+    println!(
+        "{:<48}  {:>14}  {:<19}  sha256",
+        "path", "bytes", "uploaded"
+    );
+    println!("{}", "-".repeat(120));
+    for entry in listing.entries {
+        let path = if entry.kind == "directory" {
+            format!("{}/", entry.path)
+        } else {
+            entry.path
+        };
+        println!(
+            "{:<48}  {:>14}  {:<19}  {}",
+            path,
+            entry.size_bytes,
+            // Seconds are precise enough for a listing.
+            entry
+                .modified_at
+                .as_deref()
+                .map_or("-", |at| at.get(..19).unwrap_or(at)),
+            entry.sha256.as_deref().unwrap_or("-")
+        );
+    }
+
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Admin commands
 // ---------------------------------------------------------------------------
@@ -477,6 +634,18 @@ async fn cmd_crates_unyank(store: &ConfigStore, args: CratesUnyankArgs) -> Resul
 async fn run_admin(store: &ConfigStore, command: AdminCommands) -> Result<()> {
     match command {
         AdminCommands::Gc(args) => cmd_admin_gc(store, args).await,
+        AdminCommands::Users { command } => match command {
+            AdminUsersCommands::Add(args) => cmd_admin_users_add(store, args).await,
+            AdminUsersCommands::List(args) => cmd_admin_users_list(store, args).await,
+            AdminUsersCommands::Remove(args) => cmd_admin_users_remove(store, args).await,
+        },
+        AdminCommands::Tokens { command } => match command {
+            AdminTokensCommands::Create(args) => cmd_admin_tokens_create(store, args).await,
+            AdminTokensCommands::List(args) => cmd_admin_tokens_list(store, args).await,
+            AdminTokensCommands::Revoke(args) => cmd_admin_tokens_revoke(store, args).await,
+        },
+        AdminCommands::Uploads(args) => cmd_admin_uploads(store, args).await,
+        AdminCommands::Audit(args) => cmd_admin_audit(store, args).await,
     }
 }
 
@@ -495,11 +664,15 @@ async fn cmd_admin_gc(store: &ConfigStore, args: AdminGcArgs) -> Result<()> {
     // Run Docker GC if requested or if no specific type was specified
     if args.docker || !args.crates {
         println!("Running Docker garbage collection...");
-        match admin_api.run_docker_gc(&registry, "/admin/docker/gc").await {
+        match admin_api
+            .run_docker_gc(&registry, "/admin/docker/gc?wait=true")
+            .await
+        {
             Ok(report) => {
                 println!("Docker GC completed:");
                 println!("  Deleted: {}", report.deleted);
                 println!("  Kept: {}", report.kept);
+                println!("  Kept (grace period): {}", report.kept_recent);
             }
             Err(e) => {
                 eprintln!("Docker GC failed: {}", e);
@@ -511,7 +684,10 @@ async fn cmd_admin_gc(store: &ConfigStore, args: AdminGcArgs) -> Result<()> {
     // Run Crates GC if requested or if no specific type was specified
     if args.crates || !args.docker {
         println!("Running crates garbage collection...");
-        match admin_api.run_crates_gc(&registry, "/admin/crates/gc").await {
+        match admin_api
+            .run_crates_gc(&registry, "/admin/crates/gc?wait=true")
+            .await
+        {
             Ok(report) => {
                 println!("Crates GC completed:");
                 println!("  Deleted crates: {}", report.deleted_crates);
@@ -529,3 +705,252 @@ async fn cmd_admin_gc(store: &ConfigStore, args: AdminGcArgs) -> Result<()> {
 
     Ok(())
 }
+
+async fn cmd_admin_users_add(store: &ConfigStore, args: AdminUserAddArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let request = UserRequest {
+        password: &args.password,
+        admin: args.admin,
+        scopes: &args.scopes,
+    };
+    let user = admin_api.put_user(&registry, &args.login, &request).await?;
+
+    println!(
+        "user '{}' saved on registry '{}'",
+        user.login, registry_name
+    );
+    Ok(())
+}
+
+async fn cmd_admin_users_list(store: &ConfigStore, args: AdminListArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let users = admin_api.list_users(&registry).await?;
+
+    println!("registry: {}", registry_name);
+    println!();
+
+    if users.is_empty() {
+        println!("no users found");
+        return Ok(());
+    }
+
+    println!("{:<24}  {:<5}  scopes", "login", "admin");
+    println!("{}", "-".repeat(72));
+    for user in users {
+        println!(
+            "{:<24}  {:<5}  {}",
+            user.login,
+            if user.admin { "yes" } else { "no" },
+            user.scopes.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+async fn cmd_admin_users_remove(store: &ConfigStore, args: AdminUserRemoveArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    admin_api.remove_user(&registry, &args.login).await?;
+
+    println!(
+        "user '{}' removed from registry '{}'",
+        args.login, registry_name
+    );
+    Ok(())
+}
+
+async fn cmd_admin_tokens_create(store: &ConfigStore, args: AdminTokenCreateArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let request = CreateTokenRequest {
+        name: &args.name,
+        login: args.login.as_deref(),
+        scopes: &args.scopes,
+        expires_in_days: args.expires_in_days,
+    };
+    let created = admin_api.create_token(&registry, &request).await?;
+
+    println!(
+        "token '{}' ({}) created for '{}'",
+        created.info.name, created.info.id, created.info.login
+    );
+    println!("scopes: {}", created.info.scopes.join(" "));
+    if let Some(expires_at) = &created.info.expires_at {
+        println!("expires: {}", expires_at);
+    }
+    println!();
+    println!("{}", created.token);
+    println!();
+    println!("store it now; it cannot be shown again");
+    Ok(())
+}
+
+async fn cmd_admin_tokens_list(store: &ConfigStore, args: AdminTokenListArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let tokens = admin_api
+        .list_tokens(&registry, args.login.as_deref())
+        .await?;
+
+    println!("registry: {}", registry_name);
+    println!();
+
+    if tokens.is_empty() {
+        println!("no tokens found");
+        return Ok(());
+    }
+
+    println!(
+        "{:<36}  {:<16}  {:<16}  {:<25}  scopes",
+        "id", "name", "login", "expires"
+    );
+    println!("{}", "-".repeat(120));
+    for token in tokens {
+        println!(
+            "{:<36}  {:<16}  {:<16}  {:<25}  {}",
+            token.id,
+            token.name,
+            token.login,
+            token.expires_at.as_deref().unwrap_or("never"),
+            token.scopes.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+async fn cmd_admin_tokens_revoke(store: &ConfigStore, args: AdminTokenRevokeArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    admin_api.revoke_token(&registry, &args.id).await?;
+
+    println!(
+        "token '{}' revoked on registry '{}'",
+        args.id, registry_name
+    );
+    Ok(())
+}
+
+async fn cmd_admin_uploads(store: &ConfigStore, args: AdminListArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let uploads = admin_api.list_upload_sessions(&registry).await?;
+
+    println!("registry: {}", registry_name);
+    match uploads.ttl_seconds {
+        Some(ttl) => println!("idle sessions are reaped after {}", format_duration(ttl)),
+        None => println!("idle sessions are never reaped"),
+    }
+    println!();
+
+    if uploads.sessions.is_empty() {
+        println!("no upload sessions in progress");
+        return Ok(());
+    }
+
+    println!(
+        "{:<40}  {:<36}  {:>12}  {:>8}  {:>8}",
+        "repository", "uuid", "bytes", "age", "idle"
+    );
+    println!("{}", "-".repeat(112));
+    for session in uploads.sessions {
+        println!(
+            "{:<40}  {:<36}  {:>12}  {:>8}  {:>8}",
+            session.repository,
+            session.uuid,
+            session.size_bytes,
+            format_duration(session.age_seconds),
+            format_duration(session.idle_seconds)
+        );
+    }
+
+    Ok(())
+}
+
+async fn cmd_admin_audit(store: &ConfigStore, args: AdminAuditArgs) -> Result<()> {
+    let registry_name = store.resolve_registry_name(args.registry)?;
+    let registry = store.load_effective_registry(&registry_name)?.config;
+    let admin_api = AdminApi::new(&registry)?;
+
+    let page = args.page.to_string();
+    let per_page = args.per_page.to_string();
+    let mut filters = vec![("page", page.as_str()), ("per_page", per_page.as_str())];
+    for (key, value) in [
+        ("subject", &args.subject),
+        ("action", &args.action),
+        ("target", &args.target),
+        ("outcome", &args.outcome),
+        ("since", &args.since),
+        ("until", &args.until),
+    ] {
+        if let Some(value) = value {
+            filters.push((key, value));
+        }
+    }
+
+    let audit = admin_api.audit_log(&registry, &filters).await?;
+
+    println!("registry: {}", registry_name);
+    println!();
+
+    if audit.events.is_empty() {
+        println!("no audit entries found");
+        return Ok(());
+    }
+
+    println!(
+        "{:<19}  {:<16}  {:<15}  {:<28}  {:<12}  target",
+        "time", "subject", "client", "action", "outcome"
+    );
+    println!("{}", "-".repeat(120));
+    for event in &audit.events {
+        let mut target = event.target.clone();
+        if let Some(version) = &event.version {
+            target = format!("{target}:{version}");
+        }
+        if let Some(digest) = &event.digest {
+            target = format!("{target} ({digest})");
+        }
+        println!(
+            "{:<19}  {:<16}  {:<15}  {:<28}  {:<12}  {}",
+            event.timestamp.get(..19).unwrap_or(&event.timestamp),
+            event.subject.as_deref().unwrap_or("-"),
+            event.client_ip,
+            event.action,
+            format!("{} {}", event.outcome, event.status),
+            target
+        );
+    }
+
+    let pages = audit.total.div_ceil(audit.per_page.max(1));
+    println!();
+    println!("page {} of {} ({} entries)", audit.page, pages, audit.total);
+
+    Ok(())
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{s}s"),
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s => format!("{}d", s / (24 * 60 * 60)),
+    }
+}
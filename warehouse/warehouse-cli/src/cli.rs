@@ -19,6 +19,11 @@ pub enum Commands {
         #[command(subcommand)]
         command: CratesCommands,
     },
+    /// Upload, download and list files in generic file repositories
+    Files {
+        #[command(subcommand)]
+        command: FilesCommands,
+    },
     /// Admin operations
     Admin {
         #[command(subcommand)]
@@ -226,7 +231,8 @@ pub struct CratesLoginArgs {
     /// Registry name; defaults to active crates registry from config
     #[arg(long)]
     pub registry: Option<String>,
-    /// API token (equivalent of `cargo login` token)
+    /// API token (equivalent of `cargo login` token), e.g. an offline token from
+    /// `/token?service=<service>&scope=crate:*:*&offline_token=true`
     #[arg(long)]
     pub token: String,
     /// Save token to ~/.config/warehouse instead of .warehouse
@@ -280,6 +286,67 @@ pub struct CratesUnyankArgs {
     pub registry: Option<String>,
 }
 
+// ---------------------------------------------------------------------------
+// Files commands
+// ---------------------------------------------------------------------------
+
+#[derive(Subcommand)]
+pub enum FilesCommands {
+    /// Upload a local file; the server verifies its sha256
+    Upload(FilesUploadArgs),
+    /// Download a file and verify its sha256
+    Download(FilesDownloadArgs),
+    /// List repositories, or the files of a repository directory
+    Ls(FilesLsArgs),
+}
+
+#[derive(Args)]
+pub struct FilesUploadArgs {
+    /// Repository name, e.g. `firmware`
+    pub repository: String,
+    /// Path inside the repository, e.g. `board-a/1.4.0/image.bin`
+    pub path: String,
+    /// Local file to upload
+    pub file: std::path::PathBuf,
+    /// Content type served back on download; defaults to application/octet-stream
+    #[arg(long)]
+    pub content_type: Option<String>,
+    /// Metadata stored with the file as `key=value`; repeatable
+    #[arg(long = "meta")]
+    pub meta: Vec<String>,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct FilesDownloadArgs {
+    /// Repository name
+    pub repository: String,
+    /// Path inside the repository
+    pub path: String,
+    /// Output file; defaults to the file name in the current directory, `-` for stdout
+    #[arg(short, long)]
+    pub output: Option<std::path::PathBuf>,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct FilesLsArgs {
+    /// Repository name; lists repositories when omitted
+    pub repository: Option<String>,
+    /// Directory inside the repository; defaults to its root
+    pub path: Option<String>,
+    /// List every file below the directory
+    #[arg(short, long)]
+    pub recursive: bool,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Admin commands
 // ---------------------------------------------------------------------------
@@ -288,6 +355,40 @@ pub struct CratesUnyankArgs {
 pub enum AdminCommands {
     /// Run garbage collection for both Docker and crates
     Gc(AdminGcArgs),
+    /// Manage registry accounts
+    Users {
+        #[command(subcommand)]
+        command: AdminUsersCommands,
+    },
+    /// Manage API tokens
+    Tokens {
+        #[command(subcommand)]
+        command: AdminTokensCommands,
+    },
+    /// List Docker blob upload sessions in progress
+    Uploads(AdminListArgs),
+    /// Show the audit log of mutating operations, newest first
+    Audit(AdminAuditArgs),
+}
+
+#[derive(Subcommand)]
+pub enum AdminUsersCommands {
+    /// Create an account or replace its password and scopes
+    Add(AdminUserAddArgs),
+    /// List accounts
+    List(AdminListArgs),
+    /// Remove an account and revoke its tokens
+    Remove(AdminUserRemoveArgs),
+}
+
+#[derive(Subcommand)]
+pub enum AdminTokensCommands {
+    /// Issue a new API token; the secret is printed once
+    Create(AdminTokenCreateArgs),
+    /// List API tokens
+    List(AdminTokenListArgs),
+    /// Revoke an API token
+    Revoke(AdminTokenRevokeArgs),
 }
 
 #[derive(Args)]
@@ -302,3 +403,105 @@ pub struct AdminGcArgs {
     #[arg(long)]
     pub crates: bool,
 }
+
+#[derive(Args)]
+pub struct AdminListArgs {
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminUserAddArgs {
+    /// Account login
+    pub login: String,
+    /// Account password
+    #[arg(long)]
+    pub password: String,
+    /// Grant full access, including the admin API
+    #[arg(long)]
+    pub admin: bool,
+    /// Scope the account may request, e.g. `repository:team/*:pull,push`; repeatable
+    #[arg(long = "scope")]
+    pub scopes: Vec<String>,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminUserRemoveArgs {
+    /// Account login
+    pub login: String,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminTokenCreateArgs {
+    /// Token name, e.g. `ci-publish`
+    pub name: String,
+    /// Owning account; defaults to the authenticated admin
+    #[arg(long)]
+    pub login: Option<String>,
+    /// Granted scope, e.g. `crate:foo:publish`; repeatable
+    #[arg(long = "scope", required = true)]
+    pub scopes: Vec<String>,
+    /// Token lifetime in days; never expires when omitted
+    #[arg(long)]
+    pub expires_in_days: Option<i64>,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminTokenListArgs {
+    /// Only list tokens owned by this account
+    #[arg(long)]
+    pub login: Option<String>,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminAuditArgs {
+    /// Only entries of this subject (account login)
+    #[arg(long)]
+    pub subject: Option<String>,
+    /// Only entries of this action or action prefix, e.g. `crates.yank` or `docker`
+    #[arg(long)]
+    pub action: Option<String>,
+    /// Only entries for this repository, crate, chart, user or token
+    #[arg(long)]
+    pub target: Option<String>,
+    /// Only entries with this outcome: success, denied or failure
+    #[arg(long)]
+    pub outcome: Option<String>,
+    /// Only entries at or after this RFC 3339 time
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Only entries before this RFC 3339 time
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Page number (1-based)
+    #[arg(long, default_value_t = 1)]
+    pub page: usize,
+    /// Entries per page (1-500)
+    #[arg(long, default_value_t = 50)]
+    pub per_page: usize,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
+
+#[derive(Args)]
+pub struct AdminTokenRevokeArgs {
+    /// Token id as shown by `warehouse admin tokens list`
+    pub id: String,
+    /// Registry name; defaults to active registry from config
+    #[arg(long)]
+    pub registry: Option<String>,
+}
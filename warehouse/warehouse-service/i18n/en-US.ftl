@@ -17,6 +17,12 @@ ui_service_docker_desc = Browse images, tags, and manifests.
 ui_service_crates_title = Crates Registry
 ui_service_crates_desc = Browse published crates and versions.
 
+ui_service_files_title = File Repositories
+ui_service_files_desc = Browse, download, and delete stored files.
+
+ui_service_helm_title = Helm Charts
+ui_service_helm_desc = Browse uploaded charts and their versions.
+
 # ── Docker ───────────────────────────────────────────────────────────────────
 
 ui_header_docker = Warehouse - Docker Repository Explorer
@@ -36,7 +42,32 @@ ui_meta_tag = Tag
 ui_meta_digest = Digest
 ui_meta_media_type = Media Type
 ui_meta_manifest_size = Manifest Size
+ui_meta_storage = Storage Used
 ui_meta_unknown = unknown
+ui_inspect = Inspect
+
+ui_platforms = Platforms
+ui_layers = Layers
+ui_history = History
+ui_col_created_by = Created By
+ui_col_layer = Layer
+ui_empty_no_platforms = No platform images found.
+ui_empty_platform_not_stored = This platform image has not been pushed or pulled.
+ui_empty_no_layers = No layers.
+ui_empty_no_history = No build history recorded.
+ui_meta_platform = Platform
+ui_meta_platform_digest = Platform Digest
+ui_meta_compressed_size = Compressed Size
+ui_meta_created = Created
+ui_meta_author = Author
+ui_meta_entrypoint = Entrypoint
+ui_meta_cmd = Command
+ui_meta_working_dir = Working Directory
+ui_meta_user = User
+ui_meta_env = Environment
+ui_meta_exposed_ports = Exposed Ports
+ui_meta_labels = Labels
+ui_meta_config_digest = Config Digest
 
 # ── Crates ───────────────────────────────────────────────────────────────────
 
@@ -66,11 +97,59 @@ ui_meta_rust_version = Rust version
 ui_meta_links = Links
 ui_meta_features = Features
 ui_meta_deps = Dependencies
+ui_meta_description = Description
+ui_meta_authors = Authors
+ui_meta_license = License
+ui_meta_homepage = Homepage
+ui_meta_documentation = Documentation
+ui_meta_repository = Repository
+ui_meta_keywords = Keywords
+ui_meta_categories = Categories
+ui_meta_published_by = Published by
+ui_meta_published_at = Published at
+ui_meta_readme = README
 
 ui_deps_normal = dependencies
 ui_deps_build = build-dependencies
 ui_deps_dev = dev-dependencies
 
+# ── Files ────────────────────────────────────────────────────────────────────
+
+ui_header_files = Warehouse - File Repository Explorer
+ui_files = Files
+ui_files_empty = No files uploaded yet.
+
+ui_col_name = Name
+ui_col_size = Size
+ui_col_modified = Uploaded
+
+ui_empty_no_files = No files found.
+ui_empty_select_file = Select a file to inspect metadata.
+
+ui_meta_path = Path
+ui_meta_size = Size
+ui_meta_content_type = Content Type
+ui_meta_uploaded_by = Uploaded by
+ui_meta_uploaded_at = Uploaded at
+ui_download = Download
+
+# ── Helm ─────────────────────────────────────────────────────────────────────
+
+ui_header_helm = Warehouse - Helm Chart Explorer
+ui_charts = Charts
+ui_charts_empty = No charts uploaded yet.
+
+ui_col_app_version = App Version
+ui_col_created = Created
+
+ui_empty_select_chart = Select a chart from the list.
+
+ui_meta_api_version = API Version
+ui_meta_app_version = App Version
+ui_meta_chart_type = Type
+ui_meta_deprecated = Deprecated
+ui_meta_repo_add = Add repository
+
 # ── Auth ─────────────────────────────────────────────────────────────────────
 
 ui_login_sign_in = Sign in
@@ -0,0 +1,126 @@
+//! Append-only audit log of mutating requests.
+//!
+//! Every push, delete, publish, yank, owner change and admin operation is
+//! written as one JSON object per line by the audit middleware, whatever
+//! its outcome. Entries are never rewritten; rotate the file externally
+//! (e.g. logrotate with `copytruncate`) if it grows too large.
+//!
+//! Configuration:
+//! - `AUDIT_LOG_PATH` (default `./storage/audit/audit.jsonl`)
+
+use actix_web::{HttpMessage, HttpRequest};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use utoipa::ToSchema;
+
+static AUDIT_LOG_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    PathBuf::from(envmnt::get_or(
+        "AUDIT_LOG_PATH",
+        "./storage/audit/audit.jsonl",
+    ))
+});
+
+/// Serializes appends so concurrent entries never interleave.
+static WRITE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditEvent {
+    /// RFC 3339 time the request finished.
+    pub timestamp: String,
+    /// Authenticated subject (`sub` of the token); absent when the request
+    /// was rejected before authentication.
+    pub subject: Option<String>,
+    pub client_ip: String,
+    /// Dotted action name, e.g. `docker.manifest.push` or `crates.yank`.
+    pub action: String,
+    /// Repository, crate, chart, user or token the action applies to.
+    pub target: String,
+    /// Tag, crate version or chart version, when the action has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// `success`, `denied` (401/403) or `failure`.
+    pub outcome: String,
+    /// HTTP status of the response.
+    pub status: u16,
+}
+
+/// Details only the handler knows (e.g. the crate name inside a publish
+/// body). Stored in the request extensions and picked up by the middleware.
+#[derive(Clone, Debug, Default)]
+pub struct AuditDetails {
+    pub target: Option<String>,
+    pub version: Option<String>,
+    pub digest: Option<String>,
+}
+
+/// Attaches the target, version and digest of the request to its audit
+/// entry, overriding what the middleware derives from the path.
+pub fn annotate(req: &HttpRequest, target: &str, version: Option<&str>, digest: Option<&str>) {
+    req.extensions_mut().insert(AuditDetails {
+        target: Some(target.to_string()),
+        version: version.map(str::to_string),
+        digest: digest.map(str::to_string),
+    });
+}
+
+pub fn outcome(status: u16) -> &'static str {
+    match status {
+        200..=399 => "success",
+        401 | 403 => "denied",
+        _ => "failure",
+    }
+}
+
+/// Appends an entry. Failures are logged; the request has already been
+/// served and is not affected.
+pub async fn record(event: &AuditEvent) {
+    if let Err(e) = append(event).await {
+        tracing::error!(
+            "failed to write audit entry for {} {}: {e}",
+            event.action,
+            event.target
+        );
+    }
+}
+
+async fn append(event: &AuditEvent) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+
+    let _write = WRITE.lock().await;
+    if let Some(parent) = AUDIT_LOG_PATH.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_LOG_PATH.as_path())
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await
+}
+
+/// Reads the entries accepted by `filter`, oldest first. Lines that do not
+/// parse are skipped.
+pub async fn read(filter: impl Fn(&AuditEvent) -> bool) -> std::io::Result<Vec<AuditEvent>> {
+    let file = match tokio::fs::File::open(AUDIT_LOG_PATH.as_path()).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut lines = BufReader::new(file).lines();
+    let mut events = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        if let Ok(event) = serde_json::from_str::<AuditEvent>(&line)
+            && filter(&event)
+        {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
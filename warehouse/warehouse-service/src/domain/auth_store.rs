@@ -0,0 +1,524 @@
+//! File-backed store for registry accounts and long-lived API tokens.
+//!
+//! Layout under `AUTH_STORAGE_PATH` (default `./storage/auth`):
+//!
+//! | File | Content |
+//! |---|---|
+//! | `users.json` | Accounts with argon2 password hashes and granted scopes |
+//! | `tokens.json` | API tokens; only the sha256 of each secret is kept |
+//!
+//! The `REGISTRY_USERNAME` / `REGISTRY_PASSWORD` account from the environment
+//! is always accepted as an administrator so a fresh instance can be
+//! bootstrapped before any users exist.
+//!
+//! Password hashing is slow by design, so async callers verify passwords on
+//! the blocking thread pool. Successful verifications are remembered for
+//! `AUTH_CACHE_SECONDS` (default 60, 0 disables the cache), which keeps
+//! repeated Basic credentials such as the UI session cookie cheap.
+//!
+//! API tokens never grant more than their owner holds: a token's scopes are
+//! narrowed to the owner's current grants on every use, and tokens of a
+//! removed account stop working.
+
+use crate::domain::jwt::{Claims, JwtConfig, scope_allows};
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+static AUTH_STORAGE_ROOT: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("AUTH_STORAGE_PATH", "./storage/auth"));
+
+static VERIFIED_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(
+        envmnt::get_or("AUTH_CACHE_SECONDS", "60")
+            .parse()
+            .unwrap_or(60),
+    )
+});
+
+pub static AUTH_STORE: LazyLock<AuthStore> =
+    LazyLock::new(|| AuthStore::load(Path::new(AUTH_STORAGE_ROOT.as_str())));
+
+/// Prefix of API token secrets; lets the middleware tell them apart from JWTs.
+pub const API_TOKEN_PREFIX: &str = "wh_";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct User {
+    pub login: String,
+    pub password_hash: String,
+    #[serde(default)]
+    pub admin: bool,
+    /// Scopes this account may request, e.g. `repository:team/*:pull,push`.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub created_at: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub login: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// Public view of a [`User`], without the password hash.
+#[derive(Serialize, ToSchema)]
+pub struct UserInfo {
+    pub login: String,
+    pub admin: bool,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+}
+
+/// Public view of an [`ApiToken`], without the secret hash.
+#[derive(Serialize, ToSchema)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub login: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+}
+
+/// Principal resolved from a password or an API token.
+pub struct Identity {
+    pub login: String,
+    pub admin: bool,
+    pub scopes: Vec<String>,
+}
+
+impl Identity {
+    /// Returns `true` when this identity may perform `action` on `resource`.
+    pub fn allows(&self, resource_type: &str, resource: &str, action: &str) -> bool {
+        self.admin || scope_allows(&self.scopes.join(" "), resource_type, resource, action)
+    }
+
+    /// Narrows a requested scope string to the actions this identity holds.
+    /// Entries with no remaining actions are dropped.
+    pub fn grant(&self, requested: &str) -> String {
+        if self.admin {
+            return requested.to_string();
+        }
+
+        requested
+            .split_whitespace()
+            .filter_map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let resource_type = parts.next()?;
+                let resource = parts.next()?;
+                let actions: Vec<&str> = parts
+                    .next()?
+                    .split(',')
+                    .filter(|action| self.allows(resource_type, resource, action))
+                    .collect();
+
+                (!actions.is_empty())
+                    .then(|| format!("{resource_type}:{resource}:{}", actions.join(",")))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Default)]
+struct AuthState {
+    users: Vec<User>,
+    tokens: Vec<ApiToken>,
+}
+
+pub struct AuthStore {
+    root: PathBuf,
+    state: RwLock<AuthState>,
+    /// Successful password verifications, keyed by [`verification_key`].
+    verified: Mutex<HashMap<String, Instant>>,
+}
+
+impl AuthStore {
+    fn load(root: &Path) -> Self {
+        let users = read_json(&root.join("users.json"));
+        let tokens = read_json(&root.join("tokens.json"));
+
+        Self {
+            root: root.to_path_buf(),
+            state: RwLock::new(AuthState { users, tokens }),
+            verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verifies a login/password pair. The password may also be an API token
+    /// owned by `login`, which is how `docker login` is used with tokens.
+    ///
+    /// This may run a password hash; call it from a blocking context.
+    pub fn authenticate_password(
+        &self,
+        config: &JwtConfig,
+        login: &str,
+        password: &str,
+    ) -> Option<Identity> {
+        if bootstrap_matches(config, login, password) {
+            return Some(Identity {
+                login: login.to_string(),
+                admin: true,
+                scopes: Vec::new(),
+            });
+        }
+
+        if password.starts_with(API_TOKEN_PREFIX) {
+            return self
+                .authenticate_token(config, password)
+                .filter(|identity| identity.login == login);
+        }
+
+        let user = self
+            .state
+            .read()
+            .ok()?
+            .users
+            .iter()
+            .find(|u| u.login == login)
+            .cloned()?;
+
+        let key = verification_key(login, password, &user.password_hash);
+        if !self.recently_verified(&key) {
+            let parsed = PasswordHash::new(&user.password_hash).ok()?;
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .ok()?;
+            self.remember_verified(key);
+        }
+
+        Some(Identity {
+            login: user.login,
+            admin: user.admin,
+            scopes: user.scopes,
+        })
+    }
+
+    /// Resolves an API token secret. The identity carries the token's own
+    /// scopes narrowed to what its owner currently holds, never the owner's
+    /// admin flag.
+    pub fn authenticate_token(&self, config: &JwtConfig, secret: &str) -> Option<Identity> {
+        self.resolve_token(config, secret)
+            .map(|(identity, _)| identity)
+    }
+
+    /// Builds request claims for an API token presented as a bearer token.
+    /// Claims of a token without an expiry never expire.
+    pub fn token_claims(&self, config: &JwtConfig, secret: &str) -> Option<Claims> {
+        let (identity, expires_at) = self.resolve_token(config, secret)?;
+        let now = Utc::now().timestamp() as usize;
+
+        Some(Claims {
+            sub: identity.login,
+            service: config.service_name.clone(),
+            scope: identity.scopes.join(" "),
+            exp: expires_at.map_or(usize::MAX, |e| e.timestamp().max(0) as usize),
+            iat: now,
+        })
+    }
+
+    /// Returns the grants of an account: the bootstrap account and admins
+    /// hold every scope. `None` when the account does not exist.
+    pub fn account(&self, config: &JwtConfig, login: &str) -> Option<Identity> {
+        let state = self.state.read().ok()?;
+        account_identity(config, &state, login)
+    }
+
+    fn resolve_token(
+        &self,
+        config: &JwtConfig,
+        secret: &str,
+    ) -> Option<(Identity, Option<DateTime<Utc>>)> {
+        let hash = hash_secret(secret);
+        let state = self.state.read().ok()?;
+        let token = state.tokens.iter().find(|t| t.token_hash == hash)?;
+
+        if token_expired(token) {
+            return None;
+        }
+        let owner = account_identity(config, &state, &token.login)?;
+        let expires_at = token
+            .expires_at
+            .as_deref()
+            .and_then(|e| DateTime::parse_from_rfc3339(e).ok())
+            .map(|e| e.with_timezone(&Utc));
+
+        let identity = Identity {
+            login: token.login.clone(),
+            admin: false,
+            scopes: owner
+                .grant(&token.scopes.join(" "))
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+        };
+        Some((identity, expires_at))
+    }
+
+    fn recently_verified(&self, key: &str) -> bool {
+        self.verified
+            .lock()
+            .ok()
+            .and_then(|verified| verified.get(key).copied())
+            .is_some_and(|at| at.elapsed() < *VERIFIED_TTL)
+    }
+
+    fn remember_verified(&self, key: String) {
+        if VERIFIED_TTL.is_zero() {
+            return;
+        }
+        if let Ok(mut verified) = self.verified.lock() {
+            verified.retain(|_, at| at.elapsed() < *VERIFIED_TTL);
+            verified.insert(key, Instant::now());
+        }
+    }
+
+    pub fn list_users(&self) -> Vec<UserInfo> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        state.users.iter().map(user_info).collect()
+    }
+
+    /// Creates an account, or replaces the password and scopes of an existing one.
+    pub fn upsert_user(
+        &self,
+        login: &str,
+        password: &str,
+        admin: bool,
+        scopes: Vec<String>,
+    ) -> std::io::Result<UserInfo> {
+        let salt = SaltString::encode_b64(Uuid::new_v4().as_bytes())
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| std::io::Error::other(e.to_string()))?
+            .to_string();
+
+        let mut state = self.write_state()?;
+        let created_at = state
+            .users
+            .iter()
+            .find(|u| u.login == login)
+            .map(|u| u.created_at.clone())
+            .unwrap_or_else(|| Utc::now().to_rfc3339());
+
+        let user = User {
+            login: login.to_string(),
+            password_hash,
+            admin,
+            scopes,
+            created_at,
+        };
+        let info = user_info(&user);
+
+        state.users.retain(|u| u.login != login);
+        state.users.push(user);
+        state.users.sort_by(|a, b| a.login.cmp(&b.login));
+
+        self.persist_users(&state)?;
+        Ok(info)
+    }
+
+    /// Removes an account together with all of its API tokens.
+    pub fn remove_user(&self, login: &str) -> std::io::Result<bool> {
+        let mut state = self.write_state()?;
+        let before = state.users.len();
+        state.users.retain(|u| u.login != login);
+        if state.users.len() == before {
+            return Ok(false);
+        }
+        state.tokens.retain(|t| t.login != login);
+
+        self.persist_users(&state)?;
+        self.persist_tokens(&state)?;
+        Ok(true)
+    }
+
+    /// Returns `true` for the bootstrap account and accounts flagged `admin`.
+    pub fn is_admin(&self, config: &JwtConfig, login: &str) -> bool {
+        config.username.as_deref() == Some(login)
+            || self
+                .state
+                .read()
+                .is_ok_and(|state| state.users.iter().any(|u| u.login == login && u.admin))
+    }
+
+    pub fn list_tokens(&self, login: Option<&str>) -> Vec<ApiTokenInfo> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        state
+            .tokens
+            .iter()
+            .filter(|t| login.is_none_or(|l| t.login == l))
+            .map(token_info)
+            .collect()
+    }
+
+    /// Issues a new token and returns its metadata with the plaintext secret,
+    /// which is not recoverable afterwards.
+    pub fn create_token(
+        &self,
+        login: &str,
+        name: &str,
+        scopes: Vec<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> std::io::Result<(ApiTokenInfo, String)> {
+        let secret = format!(
+            "{API_TOKEN_PREFIX}{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let token = ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            login: login.to_string(),
+            token_hash: hash_secret(&secret),
+            scopes,
+            created_at: Utc::now().to_rfc3339(),
+            expires_at: expires_at.map(|e| e.to_rfc3339()),
+        };
+
+        let mut state = self.write_state()?;
+        let info = token_info(&token);
+        state.tokens.push(token);
+        self.persist_tokens(&state)?;
+
+        Ok((info, secret))
+    }
+
+    pub fn revoke_token(&self, id: &str) -> std::io::Result<bool> {
+        let mut state = self.write_state()?;
+        let before = state.tokens.len();
+        state.tokens.retain(|t| t.id != id);
+        if state.tokens.len() == before {
+            return Ok(false);
+        }
+
+        self.persist_tokens(&state)?;
+        Ok(true)
+    }
+
+    fn write_state(&self) -> std::io::Result<std::sync::RwLockWriteGuard<'_, AuthState>> {
+        self.state
+            .write()
+            .map_err(|_| std::io::Error::other("auth store lock poisoned"))
+    }
+
+    fn persist_users(&self, state: &AuthState) -> std::io::Result<()> {
+        write_json(&self.root.join("users.json"), &state.users)
+    }
+
+    fn persist_tokens(&self, state: &AuthState) -> std::io::Result<()> {
+        write_json(&self.root.join("tokens.json"), &state.tokens)
+    }
+}
+
+fn user_info(user: &User) -> UserInfo {
+    UserInfo {
+        login: user.login.clone(),
+        admin: user.admin,
+        scopes: user.scopes.clone(),
+        created_at: user.created_at.clone(),
+    }
+}
+
+fn token_info(token: &ApiToken) -> ApiTokenInfo {
+    ApiTokenInfo {
+        id: token.id.clone(),
+        name: token.name.clone(),
+        login: token.login.clone(),
+        scopes: token.scopes.clone(),
+        created_at: token.created_at.clone(),
+        expires_at: token.expires_at.clone(),
+    }
+}
+
+fn account_identity(config: &JwtConfig, state: &AuthState, login: &str) -> Option<Identity> {
+    if config.username.as_deref() == Some(login) {
+        return Some(Identity {
+            login: login.to_string(),
+            admin: true,
+            scopes: Vec::new(),
+        });
+    }
+
+    let user = state.users.iter().find(|u| u.login == login)?;
+    Some(Identity {
+        login: user.login.clone(),
+        admin: user.admin,
+        scopes: user.scopes.clone(),
+    })
+}
+
+/// Compares credentials with the bootstrap account in constant time.
+fn bootstrap_matches(config: &JwtConfig, login: &str, password: &str) -> bool {
+    let (Some(username), Some(expected)) = (config.username.as_deref(), config.password.as_deref())
+    else {
+        return false;
+    };
+    // Hashing first keeps the comparison independent of the lengths.
+    let login_matches =
+        Sha256::digest(login.as_bytes()).ct_eq(&Sha256::digest(username.as_bytes()));
+    let password_matches =
+        Sha256::digest(password.as_bytes()).ct_eq(&Sha256::digest(expected.as_bytes()));
+    (login_matches & password_matches).into()
+}
+
+/// Cache key of a successful verification. The stored hash is part of the
+/// key, so changing a password invalidates it.
+fn verification_key(login: &str, password: &str, password_hash: &str) -> String {
+    hash_secret(&format!("{login}\n{password}\n{password_hash}"))
+}
+
+fn token_expired(token: &ApiToken) -> bool {
+    token
+        .expires_at
+        .as_deref()
+        .and_then(|e| DateTime::parse_from_rfc3339(e).ok())
+        .is_some_and(|e| e <= Utc::now())
+}
+
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn read_json<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            tracing::error!("failed to parse {}: {e}", path.display());
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Writes through a temporary file so a crash never leaves a truncated store.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let body = serde_json::to_vec_pretty(value)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, body)?;
+    std::fs::rename(&tmp, path)
+}
@@ -0,0 +1,21 @@
+use actix_web::{HttpResponse, http::StatusCode};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct CratesErrorBody {
+    errors: Vec<CratesErrorEntry>,
+}
+
+#[derive(Serialize)]
+struct CratesErrorEntry {
+    detail: String,
+}
+
+/// Builds a cargo-compatible error response: `{"errors":[{"detail":"..."}]}`.
+pub fn response(status: StatusCode, detail: impl Into<String>) -> HttpResponse {
+    HttpResponse::build(status).json(CratesErrorBody {
+        errors: vec![CratesErrorEntry {
+            detail: detail.into(),
+        }],
+    })
+}
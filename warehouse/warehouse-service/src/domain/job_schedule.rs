@@ -0,0 +1,167 @@
+//! In-memory schedule of persistent background jobs.
+//!
+//! Webhook deliveries and replication jobs are written to disk before they
+//! are attempted. Their queues keep a [`JobSchedule`] in sync with those
+//! files: it is loaded once at startup and updated on every write, so the
+//! workers know when the next job is due without reading the queue
+//! directory again.
+//!
+//! Jobs belong to a lane (a webhook endpoint, a replication peer). Lanes are
+//! worked on concurrently, at most `concurrency` at a time; the jobs of one
+//! lane are attempted one after another, oldest first, so a slow endpoint
+//! neither delays the others nor receives its events out of order.
+
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+/// Upper bound for the worker's sleep, in case a wake-up is missed.
+const MAX_IDLE_SECS: i64 = 60;
+
+struct Entry {
+    lane: String,
+    /// Unix time of the next attempt.
+    next_attempt: i64,
+    created_at: String,
+}
+
+#[derive(Default)]
+pub(crate) struct JobSchedule {
+    entries: Mutex<HashMap<String, Entry>>,
+    wake: Notify,
+}
+
+impl JobSchedule {
+    /// Schedules job `id`, or moves it to `next_attempt`, and wakes the
+    /// worker.
+    pub(crate) fn insert(&self, id: &str, lane: &str, next_attempt: i64, created_at: &str) {
+        self.lock().insert(
+            id.to_string(),
+            Entry {
+                lane: lane.to_string(),
+                next_attempt,
+                created_at: created_at.to_string(),
+            },
+        );
+        self.wake.notify_one();
+    }
+
+    /// Forgets job `id` once it is delivered or given up.
+    pub(crate) fn remove(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    /// Attempts due jobs forever. `attempt` receives a job id and must
+    /// [`insert`](Self::insert) or [`remove`](Self::remove) it again.
+    pub(crate) async fn run<F, Fut>(&self, concurrency: usize, attempt: F)
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut running = JoinSet::new();
+        let mut busy: HashMap<tokio::task::Id, String> = HashMap::new();
+
+        loop {
+            let now = Utc::now().timestamp();
+            let busy_lanes: HashSet<&String> = busy.values().collect();
+            let mut next_due = now + MAX_IDLE_SECS;
+            let mut due: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+            for (id, entry) in self.lock().iter() {
+                if busy_lanes.contains(&entry.lane) {
+                    continue;
+                }
+                if entry.next_attempt <= now {
+                    due.entry(entry.lane.clone())
+                        .or_default()
+                        .push((entry.created_at.clone(), id.clone()));
+                } else {
+                    next_due = next_due.min(entry.next_attempt);
+                }
+            }
+
+            for (lane, mut jobs) in due {
+                // The other lanes are picked up once one finishes.
+                if running.len() >= concurrency.max(1) {
+                    break;
+                }
+                jobs.sort();
+                let attempts: Vec<Fut> = jobs.into_iter().map(|(_, id)| attempt(id)).collect();
+                let handle = running.spawn(async move {
+                    for job in attempts {
+                        job.await;
+                    }
+                });
+                busy.insert(handle.id(), lane);
+            }
+
+            let idle_for = Duration::from_secs((next_due - now).max(1) as u64);
+            tokio::select! {
+                Some(done) = running.join_next_with_id(), if !running.is_empty() => {
+                    let id = match done {
+                        Ok((id, ())) => id,
+                        Err(e) => e.id(),
+                    };
+                    busy.remove(&id);
+                }
+                _ = self.wake.notified() => {}
+                _ = tokio::time::sleep(idle_for) => {}
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JobSchedule;
+    use chrono::Utc;
+    use std::sync::{LazyLock, Mutex};
+    use std::time::Duration;
+
+    /// Attempts each job once, recording the order in which they finish;
+    /// jobs of the `slow` lane take a while.
+    async fn run_for(schedule: &'static JobSchedule, done: &'static Mutex<Vec<String>>) {
+        let attempt = move |id: String| async move {
+            if id.starts_with("slow") {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+            }
+            schedule.remove(&id);
+            done.lock().unwrap().push(id);
+        };
+        let _ = tokio::time::timeout(Duration::from_millis(700), schedule.run(4, attempt)).await;
+    }
+
+    #[tokio::test]
+    async fn lanes_run_concurrently_and_in_order() {
+        static SCHEDULE: LazyLock<JobSchedule> = LazyLock::new(JobSchedule::default);
+        static DONE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let now = Utc::now().timestamp();
+        SCHEDULE.insert("slow-2", "slow", now, "2026-01-01T00:00:02Z");
+        SCHEDULE.insert("slow-1", "slow", now, "2026-01-01T00:00:01Z");
+        SCHEDULE.insert("fast-1", "fast", now, "2026-01-01T00:00:03Z");
+
+        run_for(&SCHEDULE, &DONE).await;
+
+        assert_eq!(*DONE.lock().unwrap(), ["fast-1", "slow-1", "slow-2"]);
+    }
+
+    #[tokio::test]
+    async fn jobs_wait_until_due() {
+        static SCHEDULE: LazyLock<JobSchedule> = LazyLock::new(JobSchedule::default);
+        static DONE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let now = Utc::now().timestamp();
+        SCHEDULE.insert("later", "lane", now + 30, "2026-01-01T00:00:01Z");
+        SCHEDULE.insert("now", "lane", now, "2026-01-01T00:00:02Z");
+
+        run_for(&SCHEDULE, &DONE).await;
+
+        assert_eq!(*DONE.lock().unwrap(), ["now"]);
+    }
+}
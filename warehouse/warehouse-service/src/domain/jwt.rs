@@ -8,6 +8,17 @@ pub struct JwtConfig {
     pub auth_enabled: bool,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Require a token for crates index, download and search requests too
+    /// (mutations always require one).
+    pub crates_auth_required: bool,
+    /// Require a token for file downloads and listings too (uploads and
+    /// deletes always require one).
+    pub files_auth_required: bool,
+    /// Require credentials for `index.yaml`, chart downloads and lookups too
+    /// (uploads and deletes always require them).
+    pub helm_auth_required: bool,
+    /// Lifetime in seconds of tokens issued with `offline_token=true`.
+    pub offline_token_ttl: i64,
 }
 
 impl JwtConfig {
@@ -28,6 +39,19 @@ impl JwtConfig {
             (None, None)
         };
 
+        let crates_auth_required = envmnt::get_or("CRATES_AUTH_REQUIRED", "false")
+            .parse()
+            .unwrap_or(false);
+        let files_auth_required = envmnt::get_or("FILES_AUTH_REQUIRED", "false")
+            .parse()
+            .unwrap_or(false);
+        let helm_auth_required = envmnt::get_or("HELM_AUTH_REQUIRED", "false")
+            .parse()
+            .unwrap_or(false);
+        let offline_token_ttl = envmnt::get_or("OFFLINE_TOKEN_TTL_SECONDS", "2592000")
+            .parse()
+            .unwrap_or(30 * 24 * 60 * 60);
+
         Self {
             jwt_secret,
             service_name,
@@ -35,11 +59,15 @@ impl JwtConfig {
             auth_enabled,
             username,
             password,
+            crates_auth_required,
+            files_auth_required,
+            helm_auth_required,
+            offline_token_ttl,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub service: String,
@@ -47,3 +75,43 @@ pub struct Claims {
     pub exp: usize,
     pub iat: usize,
 }
+
+impl Claims {
+    /// Returns `true` when the token scope grants `action` on `resource`.
+    pub fn allows(&self, resource_type: &str, resource: &str, action: &str) -> bool {
+        scope_allows(&self.scope, resource_type, resource, action)
+    }
+}
+
+/// Checks a space-separated scope string such as
+/// `repository:team/*:pull,push crate:*:publish` for a matching entry.
+/// A resource ending in `*` matches every resource with that prefix.
+pub fn scope_allows(scope: &str, resource_type: &str, resource: &str, action: &str) -> bool {
+    scope.split_whitespace().any(|entry| {
+        let mut parts = entry.splitn(3, ':');
+        let scope_type = parts.next().unwrap_or_default();
+        let scope_resource = parts.next().unwrap_or_default();
+        let scope_actions = parts.next().unwrap_or_default();
+
+        if scope_type != resource_type {
+            return false;
+        }
+
+        if !resource_matches(scope_resource, resource) {
+            return false;
+        }
+
+        scope_actions
+            .split(',')
+            .any(|allowed| allowed == action || allowed == "*")
+    })
+}
+
+/// Matches `resource` against a pattern such as `team/*`: a trailing `*`
+/// matches every resource with that prefix, anything else must match exactly.
+pub fn resource_matches(pattern: &str, resource: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => resource.starts_with(prefix),
+        None => pattern == resource,
+    }
+}
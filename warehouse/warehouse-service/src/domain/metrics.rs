@@ -0,0 +1,471 @@
+//! In-process metrics, rendered in the Prometheus text exposition format by
+//! `GET /metrics`.
+//!
+//! Requests are grouped by the part of the registry they hit (see
+//! [`route_group`]) rather than by raw path, which keeps label cardinality
+//! bounded no matter how many repositories or crates exist.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Upper bounds (seconds) of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Route groups, in exposition order.
+const ROUTE_GROUPS: [&str; 18] = [
+    "docker_blob",
+    "docker_manifest",
+    "docker_registry",
+    "docker_token",
+    "crates_index",
+    "crates_publish",
+    "crates_download",
+    "crates_api",
+    "files_upload",
+    "files_download",
+    "files_api",
+    "helm_upload",
+    "helm_download",
+    "helm_api",
+    "admin",
+    "ui",
+    "health",
+    "other",
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// Cumulative counts, one per entry of [`LATENCY_BUCKETS`].
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+struct GroupStats {
+    /// Responses by status code.
+    responses: BTreeMap<u16, u64>,
+    latency: Histogram,
+    bytes_received: u64,
+    bytes_sent: u64,
+}
+
+#[derive(Default, Clone)]
+struct GcStats {
+    runs: u64,
+    failures: u64,
+    last_run_timestamp: u64,
+    last_duration_seconds: f64,
+    last_deleted: u64,
+    last_kept: u64,
+}
+
+static GROUPS: LazyLock<Mutex<BTreeMap<&'static str, GroupStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+static GC: LazyLock<Mutex<BTreeMap<&'static str, GcStats>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+static ACTIVE_UPLOADS: AtomicI64 = AtomicI64::new(0);
+static AUTH_FAILURES_TOTAL: AtomicU64 = AtomicU64::new(0);
+static STARTED_AT: LazyLock<SystemTime> = LazyLock::new(SystemTime::now);
+
+// ---------------------------------------------------------------------------
+// Recording
+// ---------------------------------------------------------------------------
+
+/// Forces the start time to be taken now rather than on the first scrape.
+pub fn init() {
+    LazyLock::force(&STARTED_AT);
+}
+
+/// Maps a request onto the route group its metrics are recorded under.
+pub fn route_group(method: &str, path: &str) -> &'static str {
+    if path == "/token" {
+        return "docker_token";
+    }
+    if path.starts_with("/v2/") || path == "/v2" {
+        if path.contains("/blobs/") {
+            return "docker_blob";
+        }
+        if path.contains("/manifests/") || path.contains("/referrers/") {
+            return "docker_manifest";
+        }
+        return "docker_registry";
+    }
+    if path == "/index" || path.starts_with("/index/") {
+        return "crates_index";
+    }
+    if path.starts_with("/api/v1/crates") {
+        if method == "PUT" && path.trim_end_matches('/') == "/api/v1/crates/new" {
+            return "crates_publish";
+        }
+        if path.trim_end_matches('/').ends_with("/download") {
+            return "crates_download";
+        }
+        return "crates_api";
+    }
+    if path.starts_with("/api/v1/files") {
+        return match method {
+            "PUT" => "files_upload",
+            "GET" | "HEAD" => "files_download",
+            _ => "files_api",
+        };
+    }
+    if path == "/helm" || path.starts_with("/helm/") {
+        if path.starts_with("/helm/api/") {
+            return if method == "POST" {
+                "helm_upload"
+            } else {
+                "helm_api"
+            };
+        }
+        return "helm_download";
+    }
+    if path == "/admin" || path.starts_with("/admin/") {
+        return "admin";
+    }
+    if path == "/ui" || path.starts_with("/ui/") {
+        return "ui";
+    }
+    if path == "/health" || path == "/metrics" {
+        return "health";
+    }
+    "other"
+}
+
+/// Records one finished request; `bytes_sent` is the response body size when
+/// known up front.
+pub fn record_request(group: &'static str, status: u16, elapsed: Duration, bytes_sent: u64) {
+    let seconds = elapsed.as_secs_f64();
+    let mut groups = GROUPS.lock().unwrap_or_else(PoisonError::into_inner);
+    let stats = groups.entry(group).or_default();
+
+    *stats.responses.entry(status).or_default() += 1;
+    stats.bytes_sent += bytes_sent;
+
+    let latency = &mut stats.latency;
+    latency.count += 1;
+    latency.sum += seconds;
+    for (bucket, bound) in latency.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+        if seconds <= bound {
+            *bucket += 1;
+        }
+    }
+}
+
+/// Adds request body bytes read by a handler.
+pub fn record_bytes_received(group: &'static str, bytes: u64) {
+    let mut groups = GROUPS.lock().unwrap_or_else(PoisonError::into_inner);
+    groups.entry(group).or_default().bytes_received += bytes;
+}
+
+pub fn record_auth_failure() {
+    AUTH_FAILURES_TOTAL.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts an upload request as active for as long as the guard lives.
+pub struct ActiveUpload(());
+
+impl ActiveUpload {
+    pub fn start() -> Self {
+        ACTIVE_UPLOADS.fetch_add(1, Ordering::Relaxed);
+        Self(())
+    }
+}
+
+impl Drop for ActiveUpload {
+    fn drop(&mut self) {
+        ACTIVE_UPLOADS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Records the outcome of a garbage collection run of `registry`
+/// (`docker` or `crates`); `result` holds the deleted and kept counts.
+pub fn record_gc(registry: &'static str, elapsed: Duration, result: Result<(u64, u64), ()>) {
+    let mut gc = GC.lock().unwrap_or_else(PoisonError::into_inner);
+    let stats = gc.entry(registry).or_default();
+
+    stats.runs += 1;
+    stats.last_run_timestamp = unix_seconds(SystemTime::now());
+    stats.last_duration_seconds = elapsed.as_secs_f64();
+    match result {
+        Ok((deleted, kept)) => {
+            stats.last_deleted = deleted;
+            stats.last_kept = kept;
+        }
+        Err(()) => stats.failures += 1,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Exposition
+// ---------------------------------------------------------------------------
+
+/// Values gathered at scrape time by the caller.
+pub struct Snapshot {
+    /// Clients with auth failures inside the throttling window, and the
+    /// number of failures they account for.
+    pub auth_failure_clients: usize,
+    pub auth_failures_recent: usize,
+    /// `(registry, objects, bytes)` per registry store.
+    pub storage: Vec<(&'static str, u64, u64)>,
+}
+
+/// Renders every metric in the Prometheus text format (version 0.0.4).
+pub fn render(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+
+    header(
+        &mut out,
+        "warehouse_http_requests_total",
+        "counter",
+        "Completed HTTP requests by route group and status code.",
+    );
+    let groups = GROUPS.lock().unwrap_or_else(PoisonError::into_inner);
+    for (group, stats) in ordered(&groups) {
+        for (status, count) in &stats.responses {
+            let _ = writeln!(
+                out,
+                "warehouse_http_requests_total{{group=\"{group}\",status=\"{status}\"}} {count}"
+            );
+        }
+    }
+
+    header(
+        &mut out,
+        "warehouse_http_request_duration_seconds",
+        "histogram",
+        "HTTP request latency by route group.",
+    );
+    for (group, stats) in ordered(&groups) {
+        let latency = &stats.latency;
+        for (count, bound) in latency.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "warehouse_http_request_duration_seconds_bucket{{group=\"{group}\",le=\"{bound}\"}} {count}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "warehouse_http_request_duration_seconds_bucket{{group=\"{group}\",le=\"+Inf\"}} {}",
+            latency.count
+        );
+        let _ = writeln!(
+            out,
+            "warehouse_http_request_duration_seconds_sum{{group=\"{group}\"}} {}",
+            latency.sum
+        );
+        let _ = writeln!(
+            out,
+            "warehouse_http_request_duration_seconds_count{{group=\"{group}\"}} {}",
+            latency.count
+        );
+    }
+
+    header(
+        &mut out,
+        "warehouse_http_received_bytes_total",
+        "counter",
+        "Request body bytes received (uploads, publishes) by route group.",
+    );
+    for (group, stats) in ordered(&groups) {
+        let _ = writeln!(
+            out,
+            "warehouse_http_received_bytes_total{{group=\"{group}\"}} {}",
+            stats.bytes_received
+        );
+    }
+
+    header(
+        &mut out,
+        "warehouse_http_sent_bytes_total",
+        "counter",
+        "Response body bytes sent (downloads) by route group.",
+    );
+    for (group, stats) in ordered(&groups) {
+        let _ = writeln!(
+            out,
+            "warehouse_http_sent_bytes_total{{group=\"{group}\"}} {}",
+            stats.bytes_sent
+        );
+    }
+    drop(groups);
+
+    header(
+        &mut out,
+        "warehouse_active_uploads",
+        "gauge",
+        "Upload requests currently holding a concurrency permit.",
+    );
+    let _ = writeln!(
+        out,
+        "warehouse_active_uploads {}",
+        ACTIVE_UPLOADS.load(Ordering::Relaxed)
+    );
+
+    header(
+        &mut out,
+        "warehouse_auth_failures_total",
+        "counter",
+        "Rejected authentication attempts since start.",
+    );
+    let _ = writeln!(
+        out,
+        "warehouse_auth_failures_total {}",
+        AUTH_FAILURES_TOTAL.load(Ordering::Relaxed)
+    );
+
+    header(
+        &mut out,
+        "warehouse_auth_failures_recent",
+        "gauge",
+        "Authentication failures inside the throttling window.",
+    );
+    let _ = writeln!(
+        out,
+        "warehouse_auth_failures_recent {}",
+        snapshot.auth_failures_recent
+    );
+
+    header(
+        &mut out,
+        "warehouse_auth_failure_clients",
+        "gauge",
+        "Clients with authentication failures inside the throttling window.",
+    );
+    let _ = writeln!(
+        out,
+        "warehouse_auth_failure_clients {}",
+        snapshot.auth_failure_clients
+    );
+
+    header(
+        &mut out,
+        "warehouse_storage_objects",
+        "gauge",
+        "Objects held by each registry store.",
+    );
+    for (registry, objects, _) in &snapshot.storage {
+        let _ = writeln!(
+            out,
+            "warehouse_storage_objects{{registry=\"{registry}\"}} {objects}"
+        );
+    }
+
+    header(
+        &mut out,
+        "warehouse_storage_bytes",
+        "gauge",
+        "Bytes held by each registry store.",
+    );
+    for (registry, _, bytes) in &snapshot.storage {
+        let _ = writeln!(
+            out,
+            "warehouse_storage_bytes{{registry=\"{registry}\"}} {bytes}"
+        );
+    }
+
+    let gc = GC.lock().unwrap_or_else(PoisonError::into_inner).clone();
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_runs_total",
+        "counter",
+        "Garbage collection runs.",
+        |s| s.runs.to_string(),
+    );
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_failures_total",
+        "counter",
+        "Garbage collection runs that failed.",
+        |s| s.failures.to_string(),
+    );
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_last_run_timestamp_seconds",
+        "gauge",
+        "Unix time the last garbage collection finished.",
+        |s| s.last_run_timestamp.to_string(),
+    );
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_last_duration_seconds",
+        "gauge",
+        "Duration of the last garbage collection.",
+        |s| s.last_duration_seconds.to_string(),
+    );
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_last_deleted",
+        "gauge",
+        "Objects deleted by the last successful garbage collection.",
+        |s| s.last_deleted.to_string(),
+    );
+    gc_metric(
+        &mut out,
+        &gc,
+        "warehouse_gc_last_kept",
+        "gauge",
+        "Objects kept by the last successful garbage collection.",
+        |s| s.last_kept.to_string(),
+    );
+
+    header(
+        &mut out,
+        "warehouse_start_time_seconds",
+        "gauge",
+        "Unix time the service started.",
+    );
+    let _ = writeln!(
+        out,
+        "warehouse_start_time_seconds {}",
+        unix_seconds(*STARTED_AT)
+    );
+
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn gc_metric(
+    out: &mut String,
+    gc: &BTreeMap<&'static str, GcStats>,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl Fn(&GcStats) -> String,
+) {
+    header(out, name, kind, help);
+    for (registry, stats) in gc {
+        let _ = writeln!(out, "{name}{{registry=\"{registry}\"}} {}", value(stats));
+    }
+}
+
+/// Groups in [`ROUTE_GROUPS`] order.
+fn ordered<'a>(
+    groups: &'a BTreeMap<&'static str, GroupStats>,
+) -> impl Iterator<Item = (&'static str, &'a GroupStats)> {
+    ROUTE_GROUPS
+        .iter()
+        .filter_map(|group| groups.get(group).map(|stats| (*group, stats)))
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
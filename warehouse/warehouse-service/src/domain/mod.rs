@@ -1,2 +1,8 @@
+pub mod audit;
+pub mod auth_store;
+pub mod crates_error;
 pub mod docker_error;
+pub mod job_schedule;
 pub mod jwt;
+pub mod metrics;
+pub mod webhooks;
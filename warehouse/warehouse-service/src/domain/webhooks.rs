@@ -0,0 +1,506 @@
+//! Outbound webhooks for registry events.
+//!
+//! Configuration:
+//! - `WEBHOOK_ENDPOINTS`: comma-separated URLs every event is POSTed to
+//!   (empty disables webhooks)
+//! - `WEBHOOK_SECRET` (optional): HMAC-SHA256 key; each delivery then carries
+//!   `X-Warehouse-Signature: sha256=<hex digest of the body>`
+//! - `WEBHOOK_EVENTS` (default `*`): comma-separated filter out of
+//!   `docker.push`, `docker.delete`, `crates.publish`, `crates.yank`,
+//!   `crates.unyank`
+//! - `WEBHOOK_MAX_ATTEMPTS` (default 10), `WEBHOOK_TIMEOUT_SECONDS`
+//!   (default 10)
+//! - `WEBHOOK_CONCURRENCY` (default 8): endpoints delivered to at the same
+//!   time; deliveries to one endpoint are sent one after another
+//! - `WEBHOOK_QUEUE_PATH` (default `./storage/webhooks`)
+//!
+//! Docker events use the Docker distribution notification envelope
+//! (`application/vnd.docker.distribution.events.v1+json`); crate events use
+//! the same envelope shape with a crate target
+//! (`application/vnd.warehouse.crates.events.v1+json`).
+//!
+//! Every delivery is written to `<queue>/pending/<id>.json` before it is
+//! attempted, so deliveries survive restarts. Failed attempts are retried
+//! with exponential backoff (5s doubling up to 1h); deliveries that exhaust
+//! their attempts are moved to `<queue>/failed/`.
+
+use crate::domain::job_schedule::JobSchedule;
+use crate::domain::jwt::Claims;
+use actix_web::{HttpMessage, HttpRequest};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
+use uuid::Uuid;
+
+const DOCKER_EVENTS_MEDIA_TYPE: &str = "application/vnd.docker.distribution.events.v1+json";
+const CRATES_EVENTS_MEDIA_TYPE: &str = "application/vnd.warehouse.crates.events.v1+json";
+
+const BACKOFF_BASE_SECS: i64 = 5;
+const BACKOFF_MAX_SECS: i64 = 3600;
+
+struct WebhookConfig {
+    endpoints: Vec<String>,
+    secret: String,
+    events: Vec<String>,
+    max_attempts: u32,
+    timeout: Duration,
+    concurrency: usize,
+    queue_root: PathBuf,
+}
+
+static CONFIG: LazyLock<WebhookConfig> = LazyLock::new(|| WebhookConfig {
+    endpoints: list("WEBHOOK_ENDPOINTS", ""),
+    secret: envmnt::get_or("WEBHOOK_SECRET", ""),
+    events: list("WEBHOOK_EVENTS", "*"),
+    max_attempts: envmnt::get_or("WEBHOOK_MAX_ATTEMPTS", "10")
+        .parse()
+        .unwrap_or(10),
+    timeout: Duration::from_secs(
+        envmnt::get_or("WEBHOOK_TIMEOUT_SECONDS", "10")
+            .parse()
+            .unwrap_or(10),
+    ),
+    concurrency: envmnt::get_or("WEBHOOK_CONCURRENCY", "8")
+        .parse()
+        .unwrap_or(8),
+    queue_root: PathBuf::from(envmnt::get_or("WEBHOOK_QUEUE_PATH", "./storage/webhooks")),
+});
+
+static REGISTRY_BASE_URL: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("REGISTRY_BASE_URL", "https://localhost"));
+
+/// Identifies this process in `source.instanceID`.
+static INSTANCE_ID: LazyLock<String> = LazyLock::new(|| Uuid::new_v4().to_string());
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent(concat!("warehouse/", env!("CARGO_PKG_VERSION")))
+        .timeout(CONFIG.timeout)
+        .build()
+        .unwrap_or_default()
+});
+
+/// Pending deliveries, by endpoint.
+static SCHEDULE: LazyLock<JobSchedule> = LazyLock::new(JobSchedule::default);
+
+fn list(name: &str, default: &str) -> Vec<String> {
+    envmnt::get_or(name, default)
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn enabled(event: &str) -> bool {
+    !CONFIG.endpoints.is_empty() && CONFIG.events.iter().any(|e| e == "*" || e == event)
+}
+
+// ---------------------------------------------------------------------------
+// Event envelope
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct Envelope<T: Serialize> {
+    events: Vec<Event<T>>,
+}
+
+#[derive(Serialize)]
+struct Event<T: Serialize> {
+    id: String,
+    timestamp: String,
+    action: &'static str,
+    target: T,
+    request: RequestInfo,
+    actor: Actor,
+    source: Source,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerTarget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    digest: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length: Option<u64>,
+    repository: String,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CrateTarget {
+    name: String,
+    version: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct RequestInfo {
+    id: String,
+    addr: String,
+    host: String,
+    method: String,
+    useragent: String,
+}
+
+#[derive(Serialize)]
+struct Actor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Source {
+    addr: String,
+    #[serde(rename = "instanceID")]
+    instance_id: String,
+}
+
+fn envelope<T: Serialize>(req: &HttpRequest, action: &'static str, target: T) -> Envelope<T> {
+    let connection = req.connection_info();
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    Envelope {
+        events: vec![Event {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            action,
+            target,
+            request: RequestInfo {
+                id: Uuid::new_v4().to_string(),
+                addr: connection
+                    .realip_remote_addr()
+                    .unwrap_or_default()
+                    .to_string(),
+                host: connection.host().to_string(),
+                method: req.method().to_string(),
+                useragent: header("User-Agent"),
+            },
+            actor: Actor {
+                name: req.extensions().get::<Claims>().map(|c| c.sub.clone()),
+            },
+            source: Source {
+                addr: envmnt::get_or("SERVER_ADDR", "0.0.0.0:443"),
+                instance_id: INSTANCE_ID.clone(),
+            },
+        }],
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Emitters
+// ---------------------------------------------------------------------------
+
+/// A manifest was pushed to `repository`, by `tag` unless pushed by digest.
+pub async fn docker_push(
+    req: &HttpRequest,
+    repository: &str,
+    digest: &str,
+    media_type: Option<&str>,
+    size: u64,
+    tag: Option<&str>,
+) {
+    if !enabled("docker.push") {
+        return;
+    }
+    let target = DockerTarget {
+        media_type: media_type.map(str::to_string),
+        size: Some(size),
+        digest: digest.to_string(),
+        length: Some(size),
+        repository: repository.to_string(),
+        url: manifest_url(repository, digest),
+        tag: tag.map(str::to_string),
+    };
+    queue(
+        "docker.push",
+        DOCKER_EVENTS_MEDIA_TYPE,
+        &envelope(req, "push", target),
+    )
+    .await;
+}
+
+/// A manifest was deleted from `repository`.
+pub async fn docker_delete(req: &HttpRequest, repository: &str, digest: &str) {
+    if !enabled("docker.delete") {
+        return;
+    }
+    let target = DockerTarget {
+        media_type: None,
+        size: None,
+        digest: digest.to_string(),
+        length: None,
+        repository: repository.to_string(),
+        url: manifest_url(repository, digest),
+        tag: None,
+    };
+    queue(
+        "docker.delete",
+        DOCKER_EVENTS_MEDIA_TYPE,
+        &envelope(req, "delete", target),
+    )
+    .await;
+}
+
+/// A crate version was published (`publish`), yanked (`yank`) or unyanked
+/// (`unyank`).
+pub async fn crate_event(req: &HttpRequest, action: &'static str, name: &str, version: &str) {
+    let event = match action {
+        "publish" => "crates.publish",
+        "yank" => "crates.yank",
+        _ => "crates.unyank",
+    };
+    if !enabled(event) {
+        return;
+    }
+    let target = CrateTarget {
+        name: name.to_string(),
+        version: version.to_string(),
+        url: format!(
+            "{}/api/v1/crates/{name}/{version}/download",
+            REGISTRY_BASE_URL.trim_end_matches('/')
+        ),
+    };
+    queue(
+        event,
+        CRATES_EVENTS_MEDIA_TYPE,
+        &envelope(req, action, target),
+    )
+    .await;
+}
+
+fn manifest_url(repository: &str, digest: &str) -> String {
+    format!(
+        "{}/v2/{repository}/manifests/{digest}",
+        REGISTRY_BASE_URL.trim_end_matches('/')
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Persistent queue
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, Deserialize)]
+struct Delivery {
+    id: String,
+    endpoint: String,
+    event: String,
+    content_type: String,
+    body: String,
+    attempts: u32,
+    /// Unix time of the next attempt.
+    next_attempt: i64,
+    created_at: String,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+fn pending_dir() -> PathBuf {
+    CONFIG.queue_root.join("pending")
+}
+
+fn failed_dir() -> PathBuf {
+    CONFIG.queue_root.join("failed")
+}
+
+/// Persists and schedules one delivery per endpoint. Failures are logged;
+/// the registry operation that triggered the event has already succeeded.
+async fn queue<T: Serialize>(event: &str, content_type: &str, envelope: &Envelope<T>) {
+    let body = match serde_json::to_string(envelope) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("failed to serialize {event} webhook event: {e}");
+            return;
+        }
+    };
+
+    for endpoint in &CONFIG.endpoints {
+        let delivery = Delivery {
+            id: Uuid::new_v4().simple().to_string(),
+            endpoint: endpoint.clone(),
+            event: event.to_string(),
+            content_type: content_type.to_string(),
+            body: body.clone(),
+            attempts: 0,
+            next_attempt: Utc::now().timestamp(),
+            created_at: Utc::now().to_rfc3339(),
+            last_error: None,
+        };
+        match write_delivery(&pending_dir(), &delivery).await {
+            Ok(()) => schedule(&delivery),
+            Err(e) => tracing::error!("failed to queue {event} webhook for {endpoint}: {e}"),
+        }
+    }
+}
+
+fn schedule(delivery: &Delivery) {
+    SCHEDULE.insert(
+        &delivery.id,
+        &delivery.endpoint,
+        delivery.next_attempt,
+        &delivery.created_at,
+    );
+}
+
+fn delivery_path(id: &str) -> PathBuf {
+    pending_dir().join(format!("{id}.json"))
+}
+
+async fn write_delivery(dir: &Path, delivery: &Delivery) -> std::io::Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    let data = serde_json::to_vec_pretty(delivery).map_err(std::io::Error::other)?;
+    let path = dir.join(format!("{}.json", delivery.id));
+    let tmp = dir.join(format!(".{}.tmp", delivery.id));
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, &path).await
+}
+
+/// Every delivery left in the queue by a previous run.
+async fn pending_deliveries() -> Vec<Delivery> {
+    let Ok(mut entries) = tokio::fs::read_dir(pending_dir()).await else {
+        return Vec::new();
+    };
+
+    let mut pending = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match read_delivery(&path).await {
+            Ok(delivery) => pending.push(delivery),
+            Err(e) => tracing::warn!("skipping unreadable webhook delivery {path:?}: {e}"),
+        }
+    }
+    pending
+}
+
+async fn read_delivery(path: &Path) -> std::io::Result<Delivery> {
+    let data = tokio::fs::read(path).await?;
+    serde_json::from_slice(&data).map_err(std::io::Error::other)
+}
+
+// ---------------------------------------------------------------------------
+// Delivery worker
+// ---------------------------------------------------------------------------
+
+/// Starts the background worker that drains the queue, including deliveries
+/// left over from a previous run. Does nothing when no endpoint is set.
+pub fn start() {
+    if CONFIG.endpoints.is_empty() {
+        return;
+    }
+
+    actix_web::rt::spawn(async {
+        for delivery in pending_deliveries().await {
+            schedule(&delivery);
+        }
+        SCHEDULE.run(CONFIG.concurrency, attempt).await;
+    });
+}
+
+async fn attempt(id: String) {
+    let path = delivery_path(&id);
+    let mut delivery = match read_delivery(&path).await {
+        Ok(delivery) => delivery,
+        Err(e) => {
+            tracing::warn!("dropping unreadable webhook delivery {path:?}: {e}");
+            SCHEDULE.remove(&id);
+            return;
+        }
+    };
+
+    match send(&delivery).await {
+        Ok(()) => {
+            SCHEDULE.remove(&id);
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                tracing::error!("failed to remove delivered webhook {path:?}: {e}");
+            }
+            return;
+        }
+        Err(e) => {
+            delivery.attempts += 1;
+            delivery.last_error = Some(e);
+        }
+    }
+
+    if delivery.attempts >= CONFIG.max_attempts {
+        tracing::error!(
+            "giving up on {} webhook {} to {} after {} attempts: {}",
+            delivery.event,
+            delivery.id,
+            delivery.endpoint,
+            delivery.attempts,
+            delivery.last_error.as_deref().unwrap_or_default()
+        );
+        SCHEDULE.remove(&id);
+        if write_delivery(&failed_dir(), &delivery).await.is_ok() {
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+        return;
+    }
+
+    let delay = BACKOFF_BASE_SECS
+        .saturating_mul(1 << (delivery.attempts - 1).min(20))
+        .min(BACKOFF_MAX_SECS);
+    delivery.next_attempt = Utc::now().timestamp() + delay;
+    tracing::warn!(
+        "{} webhook {} to {} failed (attempt {}), retrying in {delay}s: {}",
+        delivery.event,
+        delivery.id,
+        delivery.endpoint,
+        delivery.attempts,
+        delivery.last_error.as_deref().unwrap_or_default()
+    );
+    if let Err(e) = write_delivery(&pending_dir(), &delivery).await {
+        tracing::error!("failed to reschedule webhook {}: {e}", delivery.id);
+    }
+    schedule(&delivery);
+}
+
+async fn send(delivery: &Delivery) -> Result<(), String> {
+    let mut request = HTTP_CLIENT
+        .post(&delivery.endpoint)
+        .header("Content-Type", &delivery.content_type)
+        .header("X-Warehouse-Event", &delivery.event)
+        .header("X-Warehouse-Delivery", &delivery.id)
+        .body(delivery.body.clone());
+    if !CONFIG.secret.is_empty() {
+        request = request.header(
+            "X-Warehouse-Signature",
+            format!("sha256={}", sign(&CONFIG.secret, delivery.body.as_bytes())),
+        );
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("endpoint returned {}", response.status()))
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
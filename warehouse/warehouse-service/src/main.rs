@@ -10,6 +10,7 @@ use utoipa_swagger_ui::SwaggerUi;
 pub mod domain;
 pub mod middleware;
 pub mod routers;
+pub mod storage;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -22,6 +23,20 @@ async fn main() -> std::io::Result<()> {
     let addr: SocketAddr = addr_str.parse().unwrap();
 
     let jwt_config = domain::jwt::JwtConfig::init();
+    domain::metrics::init();
+    domain::webhooks::start();
+    routers::replication::start();
+    if routers::crates_enabled() {
+        routers::crates::search::index::init().await;
+        routers::admin::gc::start(routers::admin::gc::Registry::Crates);
+    }
+    if routers::docker_enabled() {
+        routers::docker::signatures::init();
+        routers::docker::tag_protection::init();
+        routers::docker::uploads::start();
+        routers::admin::docker::retention::start();
+        routers::admin::gc::start(routers::admin::gc::Registry::Docker);
+    }
     let max_body_bytes: usize = envmnt::get_or("MAX_REQUEST_BODY_BYTES", "1073741824")
         .parse()
         .unwrap_or(1024 * 1024 * 1024);
@@ -38,14 +53,19 @@ async fn main() -> std::io::Result<()> {
                 max_concurrent_uploads,
             ))
             .wrap(middleware::auth::WarehouseAuth::new(jwt_config.clone()))
+            .wrap(middleware::audit::WarehouseAudit)
             .wrap(middleware::logger::FilteredLogger)
+            .wrap(middleware::metrics::WarehouseMetrics)
             // Register Actix services
             .service(routers::admin::scope())
             .service(routers::docker::scope())
             .service(routers::docker::token::handle)
             .service(routers::crates::scope())
             .service(routers::crates::scope_index())
+            .service(routers::files::scope())
+            .service(routers::helm::scope())
             .service(routers::health::scope())
+            .service(routers::metrics::scope())
             .service(routers::ui::scope())
             // Swagger UI
             .service(routers::swagger_redirect)
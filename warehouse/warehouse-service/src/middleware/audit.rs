@@ -0,0 +1,205 @@
+use crate::domain::audit::{self, AuditDetails, AuditEvent};
+use crate::domain::jwt::Claims;
+use crate::middleware::auth::client_key;
+use actix_web::HttpMessage;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use chrono::Utc;
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+use std::task::{Context, Poll};
+
+/// Writes an audit entry for every mutating request to the Docker, crates,
+/// files, Helm and admin APIs. Wrapped outside the auth middleware so
+/// rejected attempts are recorded as well.
+#[derive(Clone, Default)]
+pub struct WarehouseAudit;
+
+impl<S, B> Transform<S, ServiceRequest> for WarehouseAudit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = WarehouseAuditMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(WarehouseAuditMiddleware { service })
+    }
+}
+
+pub struct WarehouseAuditMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for WarehouseAuditMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(operation) = classify(req.method(), req.path()) else {
+            let fut = self.service.call(req);
+            return Box::pin(fut);
+        };
+        let client_ip = client_key(&req);
+        // Completing a blob upload names the digest in the query.
+        let query_digest = query_param(req.query_string(), "digest");
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            let status = match &res {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+
+            let mut event = AuditEvent {
+                timestamp: Utc::now().to_rfc3339(),
+                subject: None,
+                client_ip,
+                action: operation.action.to_string(),
+                target: operation.target,
+                version: operation.version,
+                digest: query_digest,
+                outcome: audit::outcome(status.as_u16()).to_string(),
+                status: status.as_u16(),
+            };
+            if let Ok(res) = &res {
+                let extensions = res.request().extensions();
+                event.subject = extensions.get::<Claims>().map(|c| c.sub.clone());
+                if let Some(details) = extensions.get::<AuditDetails>() {
+                    event.target = details.target.clone().unwrap_or(event.target);
+                    event.version = details.version.clone().or(event.version);
+                    event.digest = details.digest.clone().or(event.digest);
+                }
+                if let Some(digest) = res
+                    .headers()
+                    .get("Docker-Content-Digest")
+                    .and_then(|v| v.to_str().ok())
+                {
+                    event.digest = Some(digest.to_string());
+                }
+            }
+
+            audit::record(&event).await;
+            res
+        })
+    }
+}
+
+struct Operation {
+    action: &'static str,
+    target: String,
+    version: Option<String>,
+}
+
+/// Maps a mutating request to its audit action, or `None` for reads and
+/// paths that no mutating handler serves.
+fn classify(method: &Method, path: &str) -> Option<Operation> {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return None;
+    }
+    let op = |action, target: &str, version: Option<&str>| {
+        Some(Operation {
+            action,
+            target: target.to_string(),
+            version: version.map(str::to_string),
+        })
+    };
+
+    if let Some(rest) = path.strip_prefix("/v2/") {
+        if let Some((name, reference)) = rest.rsplit_once("/manifests/") {
+            return match *method {
+                Method::PUT => op("docker.manifest.push", name, Some(reference)),
+                Method::DELETE => op("docker.manifest.delete", name, Some(reference)),
+                _ => None,
+            };
+        }
+        if let Some((name, upload)) = rest.rsplit_once("/blobs/uploads/") {
+            return match (method, upload.is_empty()) {
+                (&Method::POST, true) => op("docker.blob.upload_start", name, None),
+                (&Method::PATCH, false) => op("docker.blob.upload_chunk", name, None),
+                (&Method::PUT, false) => op("docker.blob.upload_complete", name, None),
+                (&Method::DELETE, false) => op("docker.blob.upload_cancel", name, None),
+                _ => None,
+            };
+        }
+        return None;
+    }
+
+    if let Some(rest) = path.strip_prefix("/api/v1/crates/") {
+        let segments: Vec<&str> = rest.split('/').collect();
+        return match (method, segments.as_slice()) {
+            // The publish handler fills in name and version from the body.
+            (&Method::PUT, ["new"]) => op("crates.publish", "", None),
+            (&Method::DELETE, [name, version, "yank"]) => op("crates.yank", name, Some(version)),
+            (&Method::PUT, [name, version, "unyank"]) => op("crates.unyank", name, Some(version)),
+            (&Method::PUT, [name, "owners"]) => op("crates.owners.add", name, None),
+            (&Method::DELETE, [name, "owners"]) => op("crates.owners.remove", name, None),
+            _ => None,
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/api/v1/files/") {
+        let (repository, file) = rest.split_once('/')?;
+        if repository.is_empty() || file.is_empty() {
+            return None;
+        }
+        return match *method {
+            Method::PUT => op("files.upload", rest, None),
+            Method::DELETE => op("files.delete", rest, None),
+            _ => None,
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/helm/api/charts") {
+        let segments: Vec<&str> = rest.trim_start_matches('/').split('/').collect();
+        return match (method, segments.as_slice()) {
+            // The upload handler fills in name and version from the archive.
+            (&Method::POST, [""]) => op("helm.upload", "", None),
+            (&Method::DELETE, [name, version]) => op("helm.delete", name, Some(version)),
+            _ => None,
+        };
+    }
+
+    if let Some(rest) = path.strip_prefix("/admin/") {
+        let segments: Vec<&str> = rest.split('/').collect();
+        return match (method, segments.as_slice()) {
+            (&Method::PUT, ["users", login]) => op("admin.user.put", login, None),
+            (&Method::DELETE, ["users", login]) => op("admin.user.delete", login, None),
+            // The handler fills in the owning account.
+            (&Method::POST, ["tokens"]) => op("admin.token.create", "", None),
+            (&Method::DELETE, ["tokens", id]) => op("admin.token.revoke", id, None),
+            (&Method::POST, ["docker", "gc"]) => op("admin.docker.gc", "docker", None),
+            (&Method::POST, ["crates", "gc"]) => op("admin.crates.gc", "crates", None),
+            (&Method::POST, ["docker", "retention"]) => {
+                op("admin.docker.retention", "docker", None)
+            }
+            (&Method::PUT, ["replication", "crates", name, version]) => {
+                op("admin.replication.receive", name, Some(version))
+            }
+            _ => None,
+        };
+    }
+
+    None
+}
+
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (k == key && !v.is_empty()).then(|| v.replace("%3A", ":").replace("%3a", ":"))
+    })
+}
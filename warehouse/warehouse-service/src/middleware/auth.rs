@@ -1,14 +1,19 @@
-use crate::domain::docker_error;
+use crate::domain::auth_store::{API_TOKEN_PREFIX, AUTH_STORE};
 use crate::domain::jwt::{Claims, JwtConfig};
+use crate::domain::{crates_error, docker_error, metrics};
 use actix_web::{
-    Error,
+    Error, HttpMessage,
     body::{EitherBody, MessageBody},
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::StatusCode,
     http::header::{HeaderValue, WWW_AUTHENTICATE},
+    web,
 };
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 use futures_util::future::{LocalBoxFuture, Ready, ok};
 use jsonwebtoken::{DecodingKey, Validation, decode};
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::{LazyLock, Mutex};
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
@@ -27,18 +32,22 @@ impl WarehouseAuth {
         let max_failures = envmnt::get_or("MAX_AUTH_FAILURES_PER_MINUTE", "30")
             .parse()
             .unwrap_or(30);
-        let window_secs = envmnt::get_or("AUTH_FAILURE_WINDOW_SECONDS", "60")
-            .parse()
-            .unwrap_or(60);
 
         Self {
             config,
             max_failures,
-            window: Duration::from_secs(window_secs),
+            window: failure_window(),
         }
     }
 }
 
+fn failure_window() -> Duration {
+    let window_secs = envmnt::get_or("AUTH_FAILURE_WINDOW_SECONDS", "60")
+        .parse()
+        .unwrap_or(60);
+    Duration::from_secs(window_secs)
+}
+
 impl<S, B> Transform<S, ServiceRequest> for WarehouseAuth
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
@@ -52,7 +61,7 @@ where
 
     fn new_transform(&self, service: S) -> Self::Future {
         ok(WarehouseAuthMiddleware {
-            service,
+            service: Rc::new(service),
             config: self.config.clone(),
             max_failures: self.max_failures,
             window: self.window,
@@ -61,7 +70,7 @@ where
 }
 
 pub struct WarehouseAuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
     config: JwtConfig,
     max_failures: usize,
     window: Duration,
@@ -83,62 +92,123 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let path = req.path().to_string();
 
-        // Only protect /v2/*
-        if !path.starts_with("/v2/") {
-            let fut = self.service.call(req);
-            return Box::pin(async move {
-                let res = fut.await?;
-                Ok(res.map_into_left_body())
-            });
+        if path.starts_with("/v2/") {
+            return self.call_docker(req);
         }
 
+        if path == "/admin" || path.starts_with("/admin/") {
+            return self.call_admin(req);
+        }
+
+        if let Some(access) = crates_access(&req, self.config.crates_auth_required) {
+            return self.call_crates(req, access);
+        }
+
+        if let Some(access) = files_access(&req, self.config.files_auth_required) {
+            return self.call_scoped(req, access);
+        }
+
+        if let Some(access) = helm_access(&req, self.config.helm_auth_required) {
+            return self.call_scoped(req, access);
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+impl<S, B> WarehouseAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    fn call_docker(
+        &self,
+        req: ServiceRequest,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>> {
         if too_many_auth_failures(&req, self.max_failures, self.window) {
             return throttled(req, &self.config);
         }
 
-        let auth_header = req
+        let token = req
             .headers()
             .get("Authorization")
-            .and_then(|h| h.to_str().ok());
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
 
-        if auth_header.is_none() {
+        let Some(claims) = token.and_then(|t| decode_claims(t, &self.config)) else {
             record_auth_failure(&req, self.window);
             return unauthorized(req, &self.config);
-        }
+        };
 
-        let token = auth_header.unwrap().strip_prefix("Bearer ");
+        clear_auth_failures(&req);
 
-        if token.is_none() {
-            record_auth_failure(&req, self.window);
-            return unauthorized(req, &self.config);
+        let allowed = repository_action(&req)
+            .is_none_or(|(repository, action)| claims.allows("repository", &repository, action));
+        // Inserted before the scope check so denied attempts are audited
+        // with their subject.
+        req.extensions_mut().insert(claims);
+        if !allowed {
+            return denied(req);
         }
 
-        let validation = Validation::default();
-
-        let decoded = decode::<Claims>(
-            token.unwrap(),
-            &DecodingKey::from_secret(self.config.jwt_secret.as_ref()),
-            &validation,
-        );
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(res.map_into_left_body())
+        })
+    }
 
-        if decoded.is_err() {
-            record_auth_failure(&req, self.window);
-            return unauthorized(req, &self.config);
+    fn call_crates(
+        &self,
+        req: ServiceRequest,
+        access: CrateAccess,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>> {
+        if too_many_auth_failures(&req, self.max_failures, self.window) {
+            return crates_error_response(
+                req,
+                StatusCode::TOO_MANY_REQUESTS,
+                "too many authentication attempts",
+            );
         }
 
-        let claims = decoded.unwrap().claims;
+        // Cargo sends the raw token; the warehouse CLI sends `Bearer <token>`.
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .map(|h| h.strip_prefix("Bearer ").unwrap_or(h).trim());
 
-        if claims.service != self.config.service_name {
+        let Some(claims) = token.and_then(|t| decode_claims(t, &self.config)) else {
             record_auth_failure(&req, self.window);
-            return unauthorized(req, &self.config);
-        }
+            return crates_error_response(
+                req,
+                StatusCode::UNAUTHORIZED,
+                "authentication required; run `cargo login` with a valid registry token",
+            );
+        };
 
         clear_auth_failures(&req);
 
-        if let Some((repository, action)) = repository_action(&req)
-            && !scope_allows(&claims.scope, &repository, action)
-        {
-            return denied(req);
+        // Publish carries the crate name in the request body, so the handler
+        // performs that scope check itself.
+        let allowed = access
+            .name
+            .as_ref()
+            .is_none_or(|name| claims.allows("crate", name, access.action));
+        req.extensions_mut().insert(claims);
+        if !allowed && let Some(name) = &access.name {
+            return crates_error_response(
+                req,
+                StatusCode::FORBIDDEN,
+                format!(
+                    "token is not authorized to {} crate `{name}`",
+                    access.action
+                ),
+            );
         }
 
         let fut = self.service.call(req);
@@ -147,6 +217,202 @@ where
             Ok(res.map_into_left_body())
         })
     }
+
+    /// File repositories and the Helm chart repository accept Basic
+    /// credentials (handy for `curl -u` and `helm repo add --username`) or a
+    /// bearer token (JWT or API token) carrying a matching scope.
+    fn call_scoped(
+        &self,
+        req: ServiceRequest,
+        access: ScopedAccess,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>> {
+        if too_many_auth_failures(&req, self.max_failures, self.window) {
+            return crates_error_response(
+                req,
+                StatusCode::TOO_MANY_REQUESTS,
+                "too many authentication attempts",
+            );
+        }
+
+        let header = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let requested = format!(
+            "{}:{}:{}",
+            access.resource_type,
+            access.resource.as_deref().unwrap_or("*"),
+            access.action
+        );
+
+        let service = self.service.clone();
+        let config = self.config.clone();
+        let window = self.window;
+        Box::pin(async move {
+            let claims = if let Some(encoded) = header.strip_prefix("Basic ") {
+                basic_claims(encoded, &config, &requested).await
+            } else {
+                let token = header.strip_prefix("Bearer ").unwrap_or(&header).trim();
+                decode_claims(token, &config)
+            };
+
+            let Some(claims) = claims else {
+                record_auth_failure(&req, window);
+                let mut response =
+                    crates_error::response(StatusCode::UNAUTHORIZED, "authentication required");
+                response.headers_mut().insert(
+                    WWW_AUTHENTICATE,
+                    HeaderValue::from_static("Basic realm=\"warehouse\""),
+                );
+                return Ok(req.into_response(response.map_into_right_body()));
+            };
+
+            clear_auth_failures(&req);
+
+            // Listings and chart uploads check the scope in the handler instead.
+            let allowed = access.resource.as_ref().is_none_or(|resource| {
+                claims.allows(access.resource_type, resource, access.action)
+            });
+            req.extensions_mut().insert(claims);
+            if !allowed && let Some(resource) = &access.resource {
+                let target = match access.resource_type {
+                    "files" => format!("files in `{resource}`"),
+                    resource_type => format!("{resource_type} `{resource}`"),
+                };
+                return crates_error_response(
+                    req,
+                    StatusCode::FORBIDDEN,
+                    format!("token is not authorized to {} {target}", access.action),
+                )
+                .await;
+            }
+
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+
+    /// Admin endpoints accept Basic credentials of an admin account, or a
+    /// bearer token (JWT or API token) carrying the `admin:*:*` scope.
+    fn call_admin(
+        &self,
+        req: ServiceRequest,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>> {
+        if too_many_auth_failures(&req, self.max_failures, self.window) {
+            return throttled(req, &self.config);
+        }
+
+        let header = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let service = self.service.clone();
+        let config = self.config.clone();
+        let window = self.window;
+        Box::pin(async move {
+            let claims = if let Some(encoded) = header.strip_prefix("Basic ") {
+                basic_claims(encoded, &config, "admin:*:*").await
+            } else {
+                let token = header.strip_prefix("Bearer ").unwrap_or(&header).trim();
+                decode_claims(token, &config)
+            };
+
+            let Some(claims) = claims else {
+                record_auth_failure(&req, window);
+                return admin_unauthorized(req).await;
+            };
+
+            clear_auth_failures(&req);
+
+            let allowed = claims.allows("admin", "*", "*");
+            req.extensions_mut().insert(claims);
+            if !allowed {
+                return denied(req).await;
+            }
+
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+/// Accepts a signed JWT issued by `/token`, or a long-lived API token.
+fn decode_claims(token: &str, config: &JwtConfig) -> Option<Claims> {
+    if token.starts_with(API_TOKEN_PREFIX) {
+        return AUTH_STORE.token_claims(config, token);
+    }
+
+    let claims = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .ok()?
+    .claims;
+
+    (claims.service == config.service_name).then_some(claims)
+}
+
+/// Resolves Basic credentials into claims holding the part of `requested`
+/// the account is allowed. The password is verified on the blocking pool.
+async fn basic_claims(encoded: &str, config: &JwtConfig, requested: &str) -> Option<Claims> {
+    let decoded = STANDARD.decode(encoded).ok()?;
+    let creds = String::from_utf8(decoded).ok()?;
+    let (login, password) = creds.split_once(':')?;
+    let (login, password, verify_config) =
+        (login.to_string(), password.to_string(), config.clone());
+    let identity =
+        web::block(move || AUTH_STORE.authenticate_password(&verify_config, &login, &password))
+            .await
+            .ok()??;
+    let now = chrono::Utc::now().timestamp() as usize;
+
+    Some(Claims {
+        scope: identity.grant(requested),
+        sub: identity.login,
+        service: config.service_name.clone(),
+        exp: now,
+        iat: now,
+    })
+}
+
+fn admin_unauthorized<B>(
+    req: ServiceRequest,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>>
+where
+    B: MessageBody + 'static,
+{
+    Box::pin(async move {
+        let mut response = docker_error::response(
+            StatusCode::UNAUTHORIZED,
+            docker_error::UNAUTHORIZED,
+            "authentication required",
+        );
+        response.headers_mut().insert(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"warehouse\""),
+        );
+        let response = response.map_into_right_body();
+
+        Ok(req.into_response(response))
+    })
+}
+
+fn crates_error_response<B>(
+    req: ServiceRequest,
+    status: StatusCode,
+    detail: impl Into<String>,
+) -> LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>>
+where
+    B: MessageBody + 'static,
+{
+    let response = crates_error::response(status, detail).map_into_right_body();
+    Box::pin(async move { Ok(req.into_response(response)) })
 }
 
 fn throttled<B>(
@@ -210,7 +476,13 @@ fn repository_action(req: &ServiceRequest) -> Option<(String, &'static str)> {
         return None;
     }
 
-    for marker in ["/blobs/", "/manifests/", "/tags/list"] {
+    for marker in [
+        "/blobs/",
+        "/manifests/",
+        "/referrers/",
+        "/tags/list",
+        "/inspect/",
+    ] {
         if let Some((repo, _)) = rest.split_once(marker)
             && !repo.is_empty()
         {
@@ -221,27 +493,117 @@ fn repository_action(req: &ServiceRequest) -> Option<(String, &'static str)> {
     None
 }
 
-fn scope_allows(scope: &str, repository: &str, action: &str) -> bool {
-    scope.split_whitespace().any(|entry| {
-        let mut parts = entry.splitn(3, ':');
-        let scope_type = parts.next().unwrap_or_default();
-        let scope_repo = parts.next().unwrap_or_default();
-        let scope_actions = parts.next().unwrap_or_default();
+/// Crate permission a request under `/api/v1/crates` or `/index` needs.
+struct CrateAccess {
+    name: Option<String>,
+    action: &'static str,
+}
 
-        if scope_type != "repository" {
-            return false;
+/// Classifies crates registry requests. Mutations always need a token; reads
+/// only when `auth_required` is set (cargo's `auth-required` registries).
+fn crates_access(req: &ServiceRequest, auth_required: bool) -> Option<CrateAccess> {
+    let path = req.path().trim_end_matches('/');
+    let is_read = matches!(
+        *req.method(),
+        actix_web::http::Method::GET | actix_web::http::Method::HEAD
+    );
+
+    if path == "/index" || path.starts_with("/index/") {
+        if !auth_required || path == "/index/config.json" {
+            return None;
         }
+        let name = path.rsplit('/').next().map(str::to_ascii_lowercase);
+        return Some(CrateAccess {
+            name,
+            action: "pull",
+        });
+    }
 
-        if scope_repo != repository && scope_repo != "*" {
-            return false;
+    let rest = match path.strip_prefix("/api/v1/crates")? {
+        "" => "",
+        rest => rest.strip_prefix('/')?,
+    };
+    let segments: Vec<&str> = rest.split('/').filter(|s| !s.is_empty()).collect();
+    let crate_name = |name: &str| Some(name.to_ascii_lowercase());
+
+    let (name, action) = match (segments.as_slice(), is_read) {
+        (["new"], false) => (None, "publish"),
+        ([name, _, "yank" | "unyank"], false) => (crate_name(name), "yank"),
+        ([name, "owners"], false) => (crate_name(name), "owners"),
+        (_, false) => (None, "publish"),
+        ([], true) if auth_required => (None, "pull"),
+        ([name, ..], true) if auth_required => (crate_name(name), "pull"),
+        _ => return None,
+    };
+
+    Some(CrateAccess { name, action })
+}
+
+/// Permission a file repository or Helm chart repository request needs.
+struct ScopedAccess {
+    /// Scope resource type: `files` or `chart`.
+    resource_type: &'static str,
+    /// Repository or chart name; `None` when the request does not name one.
+    resource: Option<String>,
+    action: &'static str,
+}
+
+/// Classifies file repository requests. Uploads and deletes always need
+/// credentials; reads only when `auth_required` is set.
+fn files_access(req: &ServiceRequest, auth_required: bool) -> Option<ScopedAccess> {
+    let rest = match req.path().strip_prefix("/api/v1/files")? {
+        "" => "",
+        rest => rest.strip_prefix('/')?,
+    };
+    let repository = rest
+        .split('/')
+        .next()
+        .filter(|r| !r.is_empty())
+        .map(str::to_string);
+
+    Some(ScopedAccess {
+        resource_type: "files",
+        resource: repository,
+        action: scoped_action(req, auth_required)?,
+    })
+}
+
+/// Classifies Helm chart repository requests. Uploads and deletes always
+/// need credentials; reads only when `auth_required` is set.
+fn helm_access(req: &ServiceRequest, auth_required: bool) -> Option<ScopedAccess> {
+    let rest = match req.path().strip_prefix("/helm")? {
+        "" => "",
+        rest => rest.strip_prefix('/')?,
+    };
+    let segments: Vec<&str> = rest.split('/').collect();
+    // Uploads carry the chart name in the archive, so the handler checks it.
+    let chart = match segments.as_slice() {
+        ["charts", name, ..] | ["api", "charts", name, ..] if !name.is_empty() => {
+            Some(name.to_string())
         }
+        _ => None,
+    };
 
-        scope_actions
-            .split(',')
-            .any(|allowed| allowed == action || allowed == "*")
+    Some(ScopedAccess {
+        resource_type: "chart",
+        resource: chart,
+        action: scoped_action(req, auth_required)?,
     })
 }
 
+/// `pull` for reads (`None` when reads are anonymous), `delete` for deletes
+/// and `push` for every other write.
+fn scoped_action(req: &ServiceRequest, auth_required: bool) -> Option<&'static str> {
+    match *req.method() {
+        actix_web::http::Method::GET | actix_web::http::Method::HEAD if auth_required => {
+            Some("pull")
+        }
+        actix_web::http::Method::GET | actix_web::http::Method::HEAD => None,
+        actix_web::http::Method::DELETE => Some("delete"),
+        _ => Some("push"),
+    }
+}
+
 fn unauthorized<B>(
     req: ServiceRequest,
     config: &JwtConfig,
@@ -269,7 +631,8 @@ where
     })
 }
 
-fn client_key(req: &ServiceRequest) -> String {
+/// Client address used for auth throttling and the audit log.
+pub(crate) fn client_key(req: &ServiceRequest) -> String {
     req.connection_info()
         .realip_remote_addr()
         .map(|s| s.to_string())
@@ -291,6 +654,7 @@ fn too_many_auth_failures(req: &ServiceRequest, max_failures: usize, window: Dur
 }
 
 fn record_auth_failure(req: &ServiceRequest, window: Duration) {
+    metrics::record_auth_failure();
     let key = client_key(req);
     let now = Instant::now();
     if let Ok(mut map) = AUTH_FAILURES.lock() {
@@ -300,6 +664,28 @@ fn record_auth_failure(req: &ServiceRequest, window: Duration) {
     }
 }
 
+/// Returns the number of clients with failures inside the throttling window
+/// and the number of failures they account for.
+pub(crate) fn recent_auth_failures() -> (usize, usize) {
+    let window = failure_window();
+    let now = Instant::now();
+    let Ok(map) = AUTH_FAILURES.lock() else {
+        return (0, 0);
+    };
+
+    map.values()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|t| now.duration_since(**t) <= window)
+                .count()
+        })
+        .filter(|recent| *recent > 0)
+        .fold((0, 0), |(clients, failures), recent| {
+            (clients + 1, failures + recent)
+        })
+}
+
 fn clear_auth_failures(req: &ServiceRequest) {
     let key = client_key(req);
     if let Ok(mut map) = AUTH_FAILURES.lock() {
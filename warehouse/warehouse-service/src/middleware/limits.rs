@@ -1,4 +1,4 @@
-use crate::domain::docker_error;
+use crate::domain::{docker_error, metrics};
 use actix_web::{
     Error,
     body::{EitherBody, MessageBody},
@@ -84,6 +84,7 @@ where
         let fut = self.service.call(req);
         Box::pin(async move {
             let _permit = permit;
+            let _active = metrics::ActiveUpload::start();
             let res = fut.await?;
             Ok(res.map_into_left_body())
         })
@@ -98,5 +99,10 @@ fn is_upload_mutation(req: &ServiceRequest) -> bool {
             | actix_web::http::Method::PUT
     );
 
-    is_write && req.path().contains("/blobs/uploads")
+    let is_file_upload =
+        *req.method() == actix_web::http::Method::PUT && req.path().starts_with("/api/v1/files/");
+    let is_chart_upload =
+        *req.method() == actix_web::http::Method::POST && req.path() == "/helm/api/charts";
+
+    (is_write && req.path().contains("/blobs/uploads")) || is_file_upload || is_chart_upload
 }
@@ -0,0 +1,74 @@
+use crate::domain::metrics;
+use actix_web::HttpMessage;
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use futures_util::StreamExt;
+use futures_util::future::{LocalBoxFuture, Ready, ok};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+/// Records request counts, latencies and body sizes per route group. Wrapped
+/// outermost so throttled and unauthenticated requests are counted too.
+#[derive(Clone, Default)]
+pub struct WarehouseMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for WarehouseMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = WarehouseMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(WarehouseMetricsMiddleware { service })
+    }
+}
+
+pub struct WarehouseMetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for WarehouseMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let group = metrics::route_group(req.method().as_str(), req.path());
+        let started = Instant::now();
+
+        // Count body bytes as the handler reads them; chunked uploads carry
+        // no Content-Length.
+        let payload = req.take_payload().inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                metrics::record_bytes_received(group, chunk.len() as u64);
+            }
+        });
+        req.set_payload(Payload::Stream {
+            payload: Box::pin(payload),
+        });
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            let bytes_sent = match res.response().body().size() {
+                BodySize::Sized(n) => n,
+                _ => 0,
+            };
+            metrics::record_request(group, res.status().as_u16(), started.elapsed(), bytes_sent);
+            Ok(res)
+        })
+    }
+}
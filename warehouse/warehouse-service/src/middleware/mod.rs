@@ -1,3 +1,5 @@
+pub mod audit;
 pub mod auth;
 pub mod limits;
 pub mod logger;
+pub mod metrics;
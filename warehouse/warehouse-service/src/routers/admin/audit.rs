@@ -0,0 +1,125 @@
+use crate::domain::audit::{self, AuditEvent};
+use crate::domain::crates_error;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, get, web};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_PER_PAGE: usize = 500;
+
+#[derive(Deserialize, IntoParams)]
+pub struct AuditQuery {
+    /// Only entries of this subject.
+    pub subject: Option<String>,
+    /// Only entries of this action, or of every action under a prefix
+    /// (`docker` matches `docker.manifest.push`).
+    pub action: Option<String>,
+    /// Only entries for this repository, crate, chart, user or token.
+    pub target: Option<String>,
+    /// Only entries with this outcome (`success`, `denied` or `failure`).
+    pub outcome: Option<String>,
+    /// Only entries at or after this RFC 3339 time.
+    pub since: Option<String>,
+    /// Only entries before this RFC 3339 time.
+    pub until: Option<String>,
+    /// Page number (1-based, default 1).
+    pub page: Option<usize>,
+    /// Entries per page (1–500, default 50).
+    pub per_page: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditPage {
+    /// Matching entries, newest first.
+    events: Vec<AuditEvent>,
+    /// Number of matching entries across all pages.
+    total: usize,
+    page: usize,
+    per_page: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    operation_id = "get_audit_log",
+    tags = ["admin"],
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Audit log entries, newest first", body = AuditPage),
+        (status = 400, description = "Invalid time filter"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 500, description = "Failed to read the audit log"),
+    )
+)]
+#[get("/audit")]
+pub async fn handle(query: web::Query<AuditQuery>) -> impl Responder {
+    let query = query.into_inner();
+    let (since, until) = match (parse_time(&query.since), parse_time(&query.until)) {
+        (Ok(since), Ok(until)) => (since, until),
+        (Err(value), _) | (_, Err(value)) => {
+            return crates_error::response(
+                StatusCode::BAD_REQUEST,
+                format!("invalid time `{value}`; expected RFC 3339"),
+            );
+        }
+    };
+
+    let matches = |event: &AuditEvent| {
+        query
+            .subject
+            .as_ref()
+            .is_none_or(|s| event.subject.as_ref() == Some(s))
+            && query.action.as_ref().is_none_or(|a| {
+                event.action == *a
+                    || event
+                        .action
+                        .strip_prefix(a.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+            && query.target.as_ref().is_none_or(|t| event.target == *t)
+            && query.outcome.as_ref().is_none_or(|o| event.outcome == *o)
+            && (since.is_none() && until.is_none() || {
+                let at = DateTime::parse_from_rfc3339(&event.timestamp).ok();
+                at.is_some_and(|at| since.is_none_or(|s| at >= s) && until.is_none_or(|u| at < u))
+            })
+    };
+
+    let events = match audit::read(matches).await {
+        Ok(events) => events,
+        Err(e) => {
+            tracing::error!("failed to read audit log: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to read audit log",
+            );
+        }
+    };
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, MAX_PER_PAGE);
+    let total = events.len();
+    let events = events
+        .into_iter()
+        .rev()
+        .skip((page - 1).saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+
+    HttpResponse::Ok().json(AuditPage {
+        events,
+        total,
+        page,
+        per_page,
+    })
+}
+
+fn parse_time(value: &Option<String>) -> Result<Option<DateTime<FixedOffset>>, String> {
+    match value {
+        None => Ok(None),
+        Some(value) => DateTime::parse_from_rfc3339(value)
+            .map(Some)
+            .map_err(|_| value.clone()),
+    }
+}
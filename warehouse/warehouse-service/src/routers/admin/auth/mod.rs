@@ -0,0 +1,8 @@
+pub mod tokens;
+pub mod users;
+
+/// Scope entries must look like `type:resource:actions`.
+fn valid_scope(scope: &str) -> bool {
+    let parts: Vec<&str> = scope.split(':').collect();
+    parts.len() == 3 && parts.iter().all(|p| !p.is_empty())
+}
@@ -0,0 +1,180 @@
+//! Named, long-lived API tokens. The secret is returned once on creation;
+//! only its sha256 is stored. Tokens work as a cargo registry token, as a
+//! bearer token for the admin API and as the password for `docker login`.
+//! A token's scopes must be held by its owning account.
+
+use crate::domain::auth_store::{AUTH_STORE, ApiTokenInfo};
+use crate::domain::jwt::{Claims, JwtConfig};
+use crate::domain::{audit, crates_error};
+use crate::routers::admin::auth::valid_scope;
+use actix_web::{
+    HttpMessage, HttpRequest, HttpResponse, Responder, delete, get, http::StatusCode, post, web,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// ---------------------------------------------------------------------------
+// Shared types
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateTokenRequest {
+    /// Human-readable token name, e.g. `ci-publish`.
+    pub name: String,
+    /// Owning account; defaults to the caller.
+    #[serde(default)]
+    pub login: Option<String>,
+    /// Granted scopes, e.g. `crate:foo:publish` or `repository:team/*:pull`.
+    pub scopes: Vec<String>,
+    /// Token lifetime in days; tokens never expire when omitted.
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreateTokenResponse {
+    /// Token secret. It is shown only once.
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TokensResponse {
+    pub tokens: Vec<ApiTokenInfo>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TokensQuery {
+    /// Only list tokens owned by this account.
+    pub login: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// GET /admin/tokens
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get,
+    path = "/tokens",
+    operation_id = "list_tokens",
+    tags = ["admin"],
+    params(TokensQuery),
+    responses(
+        (status = 200, description = "API tokens (without secrets)", body = TokensResponse, content_type = "application/json"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+    )
+)]
+#[get("/tokens")]
+pub async fn list(query: web::Query<TokensQuery>) -> impl Responder {
+    HttpResponse::Ok().json(TokensResponse {
+        tokens: AUTH_STORE.list_tokens(query.login.as_deref()),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// POST /admin/tokens
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    post,
+    path = "/tokens",
+    operation_id = "create_token",
+    tags = ["admin"],
+    request_body(content = CreateTokenRequest, content_type = "application/json"),
+    responses(
+        (status = 201, description = "Token created", body = CreateTokenResponse, content_type = "application/json"),
+        (status = 400, description = "Invalid name, scope or lifetime, or a scope the owner does not hold"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Owning account not found"),
+    )
+)]
+#[post("/tokens")]
+pub async fn create(
+    req: HttpRequest,
+    config: web::Data<JwtConfig>,
+    body: web::Json<CreateTokenRequest>,
+) -> impl Responder {
+    let body = body.into_inner();
+
+    let login = match body.login {
+        Some(login) => login,
+        None => match req.extensions().get::<Claims>() {
+            Some(claims) => claims.sub.clone(),
+            None => return HttpResponse::Unauthorized().finish(),
+        },
+    };
+
+    audit::annotate(&req, &login, None, None);
+
+    let name = body.name.trim();
+    if name.is_empty() {
+        return crates_error::response(StatusCode::BAD_REQUEST, "token name must not be empty");
+    }
+    if body.scopes.is_empty() {
+        return crates_error::response(StatusCode::BAD_REQUEST, "at least one scope is required");
+    }
+    if let Some(scope) = body.scopes.iter().find(|s| !valid_scope(s)) {
+        return crates_error::response(
+            StatusCode::BAD_REQUEST,
+            format!("invalid scope `{scope}`; expected `type:resource:actions`"),
+        );
+    }
+    if body.expires_in_days.is_some_and(|d| d <= 0) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "expires_in_days must be positive");
+    }
+
+    // The bootstrap account from the environment may own tokens as well.
+    let Some(owner) = AUTH_STORE.account(&config, &login) else {
+        return crates_error::response(StatusCode::NOT_FOUND, format!("user `{login}` not found"));
+    };
+    // A token never holds more than its owner.
+    if let Some(scope) = body.scopes.iter().find(|s| owner.grant(s) != **s) {
+        return crates_error::response(
+            StatusCode::BAD_REQUEST,
+            format!("scope `{scope}` exceeds the grants of `{login}`"),
+        );
+    }
+
+    let expires_at = body.expires_in_days.map(|d| Utc::now() + Duration::days(d));
+
+    match AUTH_STORE.create_token(&login, name, body.scopes, expires_at) {
+        Ok((info, token)) => HttpResponse::Created().json(CreateTokenResponse { token, info }),
+        Err(e) => {
+            tracing::error!("failed to save token: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DELETE /admin/tokens/{id}
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    delete,
+    path = "/tokens/{id}",
+    operation_id = "revoke_token",
+    tags = ["admin"],
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Token not found"),
+    )
+)]
+#[delete("/tokens/{id}")]
+pub async fn revoke(path: web::Path<String>) -> impl Responder {
+    match AUTH_STORE.revoke_token(&path.into_inner()) {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => crates_error::response(StatusCode::NOT_FOUND, "token not found"),
+        Err(e) => {
+            tracing::error!("failed to revoke token: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Registry accounts. Passwords are stored as argon2 hashes; `scopes` bound
+//! what `/token` will issue to the account (admins are unrestricted).
+
+use crate::domain::auth_store::{AUTH_STORE, UserInfo};
+use crate::domain::crates_error;
+use crate::routers::admin::auth::valid_scope;
+use actix_web::{HttpResponse, Responder, delete, get, http::StatusCode, put, web};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// ---------------------------------------------------------------------------
+// Shared types
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize, ToSchema)]
+pub struct UserRequest {
+    pub password: String,
+    #[serde(default)]
+    pub admin: bool,
+    /// Scopes the account may request, e.g. `repository:team/*:pull,push`.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UsersResponse {
+    pub users: Vec<UserInfo>,
+}
+
+// ---------------------------------------------------------------------------
+// GET /admin/users
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get,
+    path = "/users",
+    operation_id = "list_users",
+    tags = ["admin"],
+    responses(
+        (status = 200, description = "Registry accounts", body = UsersResponse, content_type = "application/json"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+    )
+)]
+#[get("/users")]
+pub async fn list() -> impl Responder {
+    HttpResponse::Ok().json(UsersResponse {
+        users: AUTH_STORE.list_users(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// PUT /admin/users/{login}
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    put,
+    path = "/users/{login}",
+    operation_id = "put_user",
+    tags = ["admin"],
+    params(("login" = String, Path, description = "Account login")),
+    request_body(content = UserRequest, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Account created or updated", body = UserInfo, content_type = "application/json"),
+        (status = 400, description = "Invalid login, password or scope"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+    )
+)]
+#[put("/users/{login}")]
+pub async fn put(path: web::Path<String>, body: web::Json<UserRequest>) -> impl Responder {
+    let login = path.into_inner();
+    let body = body.into_inner();
+
+    if !valid_login(&login) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid login");
+    }
+    if body.password.is_empty() {
+        return crates_error::response(StatusCode::BAD_REQUEST, "password must not be empty");
+    }
+    if let Some(scope) = body.scopes.iter().find(|s| !valid_scope(s)) {
+        return crates_error::response(
+            StatusCode::BAD_REQUEST,
+            format!("invalid scope `{scope}`; expected `type:resource:actions`"),
+        );
+    }
+
+    // Argon2 hashing is deliberately slow; keep it off the async workers.
+    let result =
+        web::block(move || AUTH_STORE.upsert_user(&login, &body.password, body.admin, body.scopes))
+            .await;
+
+    match result {
+        Ok(Ok(user)) => HttpResponse::Ok().json(user),
+        Ok(Err(e)) => {
+            tracing::error!("failed to save user: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+        Err(e) => {
+            tracing::error!("failed to save user: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DELETE /admin/users/{login}
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    delete,
+    path = "/users/{login}",
+    operation_id = "delete_user",
+    tags = ["admin"],
+    params(("login" = String, Path, description = "Account login")),
+    responses(
+        (status = 204, description = "Account and its API tokens removed"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Account not found"),
+    )
+)]
+#[delete("/users/{login}")]
+pub async fn remove(path: web::Path<String>) -> impl Responder {
+    match AUTH_STORE.remove_user(&path.into_inner()) {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => crates_error::response(StatusCode::NOT_FOUND, "user not found"),
+        Err(e) => {
+            tracing::error!("failed to remove user: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn valid_login(login: &str) -> bool {
+    !login.is_empty()
+        && login.len() <= 64
+        && login
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@'))
+}
@@ -6,6 +6,7 @@
 //! |---|---|
 //! | `.crate` tarball whose index entry is **yanked** | Deleted |
 //! | `.crate` tarball with **no index entry** at all (orphan) | Deleted |
+//! | `metadata.json` / `README.md` of a deleted or missing tarball | Deleted |
 //! | Version sub-directory that is now empty after tarball removal | Removed |
 //! | Index entry that references a **missing** `.crate` file | Entry removed from index (index rebuilt) |
 //! | `owners.json` whose parent crate directory has no index file | Deleted |
@@ -14,12 +15,15 @@
 //! The index files themselves are never deleted; they are only repaired when
 //! they contain entries pointing to missing tarballs.
 
-use crate::routers::CRATES_STORAGE_ROOT;
-use crate::routers::crates::{crate_file_path, validate_crate_name, validate_version};
-use actix_web::{HttpResponse, Responder, post};
+use crate::routers::CRATES_STORE;
+use crate::routers::admin::gc::{GcQuery, GcRun, ProgressReporter, Registry, start_response};
+use crate::routers::crates::{
+    crate_file_key, index_file_key, lock_crate, metadata_file_key, readme_file_key,
+    validate_crate_name, validate_version,
+};
+use actix_web::{Responder, post, web};
 use serde::Serialize;
-use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use utoipa::ToSchema;
 
 // ---------------------------------------------------------------------------
@@ -36,7 +40,7 @@ pub struct CratesGcReport {
     pub removed_index_entries: usize,
     /// Number of orphaned `owners.json` files deleted
     pub deleted_owner_files: usize,
-    /// Number of empty directories removed
+    /// Number of version directories left empty and removed
     pub removed_empty_dirs: usize,
 }
 
@@ -49,104 +53,97 @@ pub struct CratesGcReport {
     path = "/crates/gc",
     operation_id = "run_crates_garbage_collection",
     tags = ["admin"],
+    params(GcQuery),
     responses(
-        (status = 200, description = "Garbage collection completed", body = CratesGcReport, content_type = "application/json"),
+        (status = 200, description = "Garbage collection completed (`wait=true`)", body = CratesGcReport, content_type = "application/json"),
+        (status = 202, description = "Garbage collection started", body = GcRun, content_type = "application/json"),
+        (status = 409, description = "Garbage collection already running"),
         (status = 500, description = "GC failure"),
     )
 )]
 #[post("/crates/gc")]
-pub async fn handle() -> impl Responder {
-    match garbage_collect().await {
-        Ok(report) => HttpResponse::Ok().json(report),
-        Err(e) => {
-            tracing::error!("crates GC failed: {e}");
-            HttpResponse::InternalServerError().finish()
-        }
-    }
+pub async fn handle(query: web::Query<GcQuery>) -> impl Responder {
+    start_response(Registry::Crates, query.wait).await
 }
 
 // ---------------------------------------------------------------------------
 // Core GC logic
 // ---------------------------------------------------------------------------
 
-async fn garbage_collect() -> std::io::Result<CratesGcReport> {
-    let root = PathBuf::from(CRATES_STORAGE_ROOT.as_str());
+pub(in crate::routers::admin) async fn garbage_collect(
+    progress: &ProgressReporter,
+) -> std::io::Result<CratesGcReport> {
     let mut report = CratesGcReport::default();
 
-    // Iterate over each crate directory  (<root>/<crate-name>/)
-    let mut crate_dirs = tokio::fs::read_dir(&root).await?;
-
-    while let Some(entry) = crate_dirs.next_entry().await? {
-        let crate_dir = entry.path();
-        let file_type = entry.file_type().await?;
-
-        if !file_type.is_dir() {
+    // Group every stored key by crate:  <crate-name>/<version>/<file>  and
+    // <crate-name>/owners.json.  The index sub-tree and the upstream cache
+    // are skipped.
+    let mut crates: BTreeMap<String, CrateKeys> = BTreeMap::new();
+    for key in CRATES_STORE.list("").await? {
+        let mut parts = key.split('/');
+        let (Some(crate_name), Some(second)) = (parts.next(), parts.next()) else {
             continue;
-        }
-
-        let crate_name = entry.file_name().to_string_lossy().to_ascii_lowercase();
-
-        // Skip the index sub-tree entirely
-        if crate_name == "index" {
+        };
+        if crate_name == "index" || !validate_crate_name(crate_name) {
             continue;
         }
 
-        if !validate_crate_name(&crate_name) {
-            continue;
+        let entry = crates.entry(crate_name.to_string()).or_default();
+        match parts.next() {
+            Some(_) if validate_version(second) => {
+                entry
+                    .versions
+                    .entry(second.to_string())
+                    .or_default()
+                    .insert(key.clone());
+            }
+            None => {
+                entry.files.insert(key.clone());
+            }
+            Some(_) => {}
         }
+    }
 
+    let total = crates.len();
+    for (done, (crate_name, keys)) in crates.into_iter().enumerate() {
+        progress.update("sweep", done, total);
         // ------------------------------------------------------------------
         // 1. Read the index file to learn which versions exist and which are
         //    yanked.  Build two sets:
         //      • indexed_versions  – every version mentioned in the index
         //      • yanked_versions   – versions whose entry has yanked=true
         // ------------------------------------------------------------------
-        let (indexed_versions, yanked_versions, index_path, index_lines) =
+        // Publishes, yanks and unyanks of this crate wait until it is done.
+        let _guard = lock_crate(&crate_name).await;
+        let (indexed_versions, yanked_versions, index_key, index_lines) =
             read_index_state(&crate_name).await;
 
         // ------------------------------------------------------------------
-        // 2. Walk the version sub-directories and decide fate of each tarball
+        // 2. Walk the versions and decide fate of each tarball
         // ------------------------------------------------------------------
-        let mut version_dirs = tokio::fs::read_dir(&crate_dir).await?;
-
-        while let Some(v_entry) = version_dirs.next_entry().await? {
-            let v_path = v_entry.path();
-            let v_type = v_entry.file_type().await?;
-
-            if !v_type.is_dir() {
-                // owners.json or other metadata files – handled separately below
-                continue;
-            }
-
-            let version = v_entry.file_name().to_string_lossy().into_owned();
-            if !validate_version(&version) {
-                continue;
-            }
-
-            let Some(tarball) = crate_file_path(&crate_name, &version) else {
+        for (version, version_keys) in &keys.versions {
+            let Some(tarball) = crate_file_key(&crate_name, version) else {
                 continue;
             };
 
-            let tarball_exists = tokio::fs::metadata(&tarball).await.is_ok();
-
-            if !tarball_exists {
-                // Nothing to delete; the version directory might still be empty
-                try_remove_empty_dir(&v_path, &mut report).await;
+            if !version_keys.contains(&tarball) {
+                // Nothing to delete; drop what is left of the version
+                remove_version_metadata(&crate_name, version, version_keys, &mut report).await;
                 continue;
             }
 
             let should_delete =
-                yanked_versions.contains(&version) || !indexed_versions.contains(&version);
+                yanked_versions.contains(version) || !indexed_versions.contains(version);
 
             if should_delete {
-                if tokio::fs::remove_file(&tarball).await.is_ok() {
+                if CRATES_STORE.delete(&tarball).await.is_ok() {
                     report.deleted_crates += 1;
                     tracing::debug!(
                         "GC: deleted {crate_name}-{version}.crate (yanked={})",
-                        yanked_versions.contains(&version)
+                        yanked_versions.contains(version)
                     );
                 }
-                try_remove_empty_dir(&v_path, &mut report).await;
+                remove_version_metadata(&crate_name, version, version_keys, &mut report).await;
             } else {
                 report.kept_crates += 1;
             }
@@ -155,29 +152,39 @@ async fn garbage_collect() -> std::io::Result<CratesGcReport> {
         // ------------------------------------------------------------------
         // 3. Repair the index: remove entries whose tarball is now gone
         // ------------------------------------------------------------------
-        if let Some(path) = &index_path {
-            report.removed_index_entries += repair_index(path, &index_lines, &crate_name).await;
+        if let Some(key) = &index_key {
+            report.removed_index_entries += repair_index(key, &index_lines, &crate_name).await;
         }
 
         // ------------------------------------------------------------------
-        // 4. Orphaned owners.json: crate dir exists but no index file at all
+        // 4. Orphaned owners.json: crate has files but no index file at all
         // ------------------------------------------------------------------
-        if index_path.is_none() {
-            let owners_file = crate_dir.join("owners.json");
-            if tokio::fs::metadata(&owners_file).await.is_ok()
-                && tokio::fs::remove_file(&owners_file).await.is_ok()
-            {
-                report.deleted_owner_files += 1;
-                tracing::debug!("GC: deleted orphaned owners.json for {crate_name}");
-            }
-            // The crate dir itself may now be empty
-            try_remove_empty_dir(&crate_dir, &mut report).await;
+        let owners_file = format!("{crate_name}/owners.json");
+        if index_key.is_none()
+            && keys.files.contains(&owners_file)
+            && CRATES_STORE.delete(&owners_file).await.is_ok()
+        {
+            report.deleted_owner_files += 1;
+            tracing::debug!("GC: deleted orphaned owners.json for {crate_name}");
         }
     }
 
+    // Repaired indexes may have changed which versions are searchable.
+    progress.update("reindex", total, total);
+    crate::routers::crates::search::index::rebuild().await;
+
     Ok(report)
 }
 
+/// Stored keys of one crate.
+#[derive(Default)]
+struct CrateKeys {
+    /// `<version>` → keys below `<crate-name>/<version>/`
+    versions: BTreeMap<String, BTreeSet<String>>,
+    /// Keys directly below `<crate-name>/`
+    files: BTreeSet<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Index helpers
 // ---------------------------------------------------------------------------
@@ -185,23 +192,23 @@ async fn garbage_collect() -> std::io::Result<CratesGcReport> {
 /// Reads the index file for a crate and returns:
 /// - set of all indexed versions
 /// - set of yanked versions
-/// - path to the index file (None if it doesn't exist)
+/// - key of the index file (None if it doesn't exist)
 /// - raw lines (for later repair pass)
 async fn read_index_state(
     crate_name: &str,
 ) -> (
     HashSet<String>,
     HashSet<String>,
-    Option<PathBuf>,
+    Option<String>,
     Vec<String>,
 ) {
-    let Some(path) = crate::routers::crates::index_file_path(crate_name) else {
+    let Some(key) = index_file_key(crate_name) else {
         return (HashSet::new(), HashSet::new(), None, Vec::new());
     };
 
-    let content = match tokio::fs::read_to_string(&path).await {
-        Ok(s) => s,
-        Err(_) => return (HashSet::new(), HashSet::new(), None, Vec::new()),
+    let content = match CRATES_STORE.get(&key).await {
+        Ok(Some(data)) => String::from_utf8_lossy(&data).into_owned(),
+        _ => return (HashSet::new(), HashSet::new(), None, Vec::new()),
     };
 
     let mut indexed = HashSet::new();
@@ -225,12 +232,12 @@ async fn read_index_state(
         }
     }
 
-    (indexed, yanked, Some(path), lines)
+    (indexed, yanked, Some(key), lines)
 }
 
-/// Removes index entries whose `.crate` tarball no longer exists on disk.
+/// Removes index entries whose `.crate` tarball no longer exists in storage.
 /// Returns the number of entries removed.
-async fn repair_index(index_path: &Path, lines: &[String], crate_name: &str) -> usize {
+async fn repair_index(index_key: &str, lines: &[String], crate_name: &str) -> usize {
     let mut removed = 0usize;
     let mut kept_lines: Vec<&str> = Vec::new();
 
@@ -239,9 +246,10 @@ async fn repair_index(index_path: &Path, lines: &[String], crate_name: &str) ->
             match v.get("vers").and_then(|x| x.as_str()) {
                 Some(vers) => {
                     // Keep the entry if the tarball exists
-                    crate_file_path(crate_name, vers)
-                        .map(|p| std::path::Path::new(&p).exists())
-                        .unwrap_or(false)
+                    match crate_file_key(crate_name, vers) {
+                        Some(key) => matches!(CRATES_STORE.size(&key).await, Ok(Some(_))),
+                        None => false,
+                    }
                 }
                 None => true, // can't parse version → preserve to be safe
             }
@@ -253,14 +261,14 @@ async fn repair_index(index_path: &Path, lines: &[String], crate_name: &str) ->
             kept_lines.push(line.as_str());
         } else {
             removed += 1;
-            tracing::debug!("GC: removing index entry for {crate_name} from {index_path:?}");
+            tracing::debug!("GC: removing index entry for {crate_name} from {index_key}");
         }
     }
 
     if removed > 0 {
         let new_content = kept_lines.join("\n") + if kept_lines.is_empty() { "" } else { "\n" };
-        if let Err(e) = tokio::fs::write(index_path, new_content.as_bytes()).await {
-            tracing::error!("GC: failed to rewrite index {index_path:?}: {e}");
+        if let Err(e) = CRATES_STORE.put(index_key, new_content.into_bytes()).await {
+            tracing::error!("GC: failed to rewrite index {index_key}: {e}");
         }
     }
 
@@ -268,20 +276,36 @@ async fn repair_index(index_path: &Path, lines: &[String], crate_name: &str) ->
 }
 
 // ---------------------------------------------------------------------------
-// Directory helpers
+// Version helpers
 // ---------------------------------------------------------------------------
 
-/// Removes `dir` if it is empty, incrementing the report counter on success.
-async fn try_remove_empty_dir(dir: &Path, report: &mut CratesGcReport) {
-    // A directory is "empty" if it has no entries at all, or only empty
-    // sub-directories (we only do one level here – version dirs are shallow).
-    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
-        return;
-    };
-    if entries.next_entry().await.ok().flatten().is_none()
-        && tokio::fs::remove_dir(dir).await.is_ok()
+/// Removes the `metadata.json` / `README.md` stored next to a deleted tarball.
+/// Counts the version directory as removed once nothing else is left in it.
+async fn remove_version_metadata(
+    crate_name: &str,
+    version: &str,
+    version_keys: &BTreeSet<String>,
+    report: &mut CratesGcReport,
+) {
+    let metadata: Vec<String> = [
+        metadata_file_key(crate_name, version),
+        readme_file_key(crate_name, version),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    for key in &metadata {
+        let _ = CRATES_STORE.delete(key).await;
+    }
+
+    // The tarball itself was deleted (or never existed) before this call.
+    let tarball = crate_file_key(crate_name, version);
+    if version_keys
+        .iter()
+        .all(|k| tarball.as_ref() == Some(k) || metadata.contains(k))
     {
         report.removed_empty_dirs += 1;
-        tracing::debug!("GC: removed empty dir {dir:?}");
+        tracing::debug!("GC: removed empty version {crate_name}/{version}");
     }
 }
@@ -1,15 +1,19 @@
-use crate::routers::DOCKER_STORAGE_ROOT;
-use actix_web::{HttpResponse, Responder, post};
+use crate::routers::DOCKER_STORE;
+use crate::routers::admin::gc::{GcQuery, GcRun, ProgressReporter, Registry, start_response};
+use actix_web::{Responder, post, web};
 use serde::Serialize;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use utoipa::ToSchema;
 
 #[derive(Serialize, ToSchema)]
-struct GcReport {
-    deleted: usize,
-    kept: usize,
+pub(crate) struct GcReport {
+    pub(crate) deleted: usize,
+    pub(crate) kept: usize,
+    /// Unreferenced blobs kept because they are younger than the grace
+    /// period.
+    pub(crate) kept_recent: usize,
 }
 
 #[utoipa::path(
@@ -17,121 +21,102 @@ struct GcReport {
     path = "/docker/gc",
     operation_id = "run_garbage_collection",
     tags = ["admin"],
+    params(GcQuery),
     responses(
-        (status = 200, description = "Garbage collection completed", body = GcReport),
+        (status = 200, description = "Garbage collection completed (`wait=true`)", body = GcReport),
+        (status = 202, description = "Garbage collection started", body = GcRun),
+        (status = 409, description = "Garbage collection already running"),
         (status = 500, description = "GC failure")
     )
 )]
 #[post("/docker/gc")]
-pub async fn handle() -> impl Responder {
-    match garbage_collect().await {
-        Ok(report) => HttpResponse::Ok().json(report),
-        Err(_) => HttpResponse::InternalServerError().finish(),
-    }
+pub async fn handle(query: web::Query<GcQuery>) -> impl Responder {
+    start_response(Registry::Docker, query.wait).await
 }
 
-async fn garbage_collect() -> std::io::Result<GcReport> {
-    let root = PathBuf::from(DOCKER_STORAGE_ROOT.as_str());
-
-    let manifest_entries = collect_digest_files(&root, "manifests").await?;
-    let blob_entries = collect_digest_files(&root, "blobs").await?;
+pub(in crate::routers::admin) async fn garbage_collect(
+    progress: &ProgressReporter,
+) -> std::io::Result<GcReport> {
+    let manifest_keys: HashMap<String, String> = collect_digest_keys("manifests")
+        .await?
+        .into_iter()
+        .collect();
+    let blob_entries = collect_digest_keys("blobs").await?;
 
-    let manifest_paths: HashMap<String, PathBuf> = manifest_entries.into_iter().collect();
     let mut referenced_blobs = HashSet::new();
-    let mut to_visit: VecDeque<String> = manifest_paths.keys().cloned().collect();
+    let mut to_visit: VecDeque<String> = manifest_keys.keys().cloned().collect();
     let mut visited = HashSet::new();
 
     while let Some(digest) = to_visit.pop_front() {
         if !visited.insert(digest.clone()) {
             continue;
         }
+        progress.update("mark", visited.len(), manifest_keys.len());
 
-        let Some(path) = manifest_paths.get(&digest) else {
+        let Some(key) = manifest_keys.get(&digest) else {
             continue;
         };
 
-        let data = tokio::fs::read(path).await?;
+        let Some(data) = DOCKER_STORE.get(key).await? else {
+            continue;
+        };
         mark_manifest_references(&data, &mut referenced_blobs, &mut to_visit);
     }
 
     let mut deleted = 0usize;
     let mut kept = 0usize;
+    let mut kept_recent = 0usize;
+    let now = SystemTime::now();
+    let total = blob_entries.len();
 
-    for (digest, path) in blob_entries {
+    for (done, (digest, key)) in blob_entries.into_iter().enumerate() {
+        progress.update("sweep", done, total);
         if referenced_blobs.contains(&digest) {
             kept += 1;
-        } else {
-            tokio::fs::remove_file(path).await?;
-            deleted += 1;
+            continue;
         }
-    }
-
-    cleanup_empty_dirs(&root).await?;
-
-    Ok(GcReport { deleted, kept })
-}
-
-async fn collect_digest_files(root: &Path, kind: &str) -> std::io::Result<Vec<(String, PathBuf)>> {
-    let mut files = Vec::new();
-    let mut stack = vec![root.to_path_buf()];
-
-    while let Some(dir) = stack.pop() {
-        let mut entries = match tokio::fs::read_dir(&dir).await {
-            Ok(e) => e,
-            Err(_) => continue,
+        // Layers of a push in progress are unreferenced until its manifest
+        // is PUT; leave young blobs for a later run.
+        let recent = match DOCKER_STORE.modified(&key).await? {
+            Some(modified) => now
+                .duration_since(modified)
+                .is_ok_and(|age| age < progress.grace_period()),
+            None => false,
         };
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            let file_type = entry.file_type().await?;
-
-            if file_type.is_dir() {
-                stack.push(path);
-                continue;
-            }
-
-            if !is_digest_file_path(&path, kind) {
-                continue;
-            }
-
-            let Some(hex) = path.file_name().and_then(|n| n.to_str()) else {
-                continue;
-            };
-
-            if !is_sha256_hex(hex) {
-                continue;
-            }
-
-            files.push((format!("sha256:{hex}"), path));
+        if recent {
+            kept_recent += 1;
+        } else {
+            DOCKER_STORE.delete(&key).await?;
+            deleted += 1;
         }
     }
 
-    Ok(files)
+    Ok(GcReport {
+        deleted,
+        kept,
+        kept_recent,
+    })
 }
 
-fn is_digest_file_path(path: &Path, kind: &str) -> bool {
-    let Some(parent) = path.parent() else {
-        return false;
-    };
-    let Some(sha_dir) = parent.file_name().and_then(|n| n.to_str()) else {
-        return false;
-    };
-    let Some(kind_dir) = parent
-        .parent()
-        .and_then(|p| p.file_name())
-        .and_then(|n| n.to_str())
-    else {
-        return false;
-    };
-
-    sha_dir == "sha256" && kind_dir == kind
+/// Returns `(digest, key)` pairs of every `<kind>/sha256/<hex>` object.
+async fn collect_digest_keys(kind: &str) -> std::io::Result<Vec<(String, String)>> {
+    let prefix = format!("{kind}/sha256/");
+    Ok(DOCKER_STORE
+        .list(&prefix)
+        .await?
+        .into_iter()
+        .filter_map(|key| {
+            let hex = key.strip_prefix(&prefix)?;
+            is_sha256_hex(hex).then(|| (format!("sha256:{hex}"), key.clone()))
+        })
+        .collect())
 }
 
 fn is_sha256_hex(v: &str) -> bool {
     v.len() == 64 && v.bytes().all(|b| b.is_ascii_hexdigit())
 }
 
-fn mark_manifest_references(
+pub(crate) fn mark_manifest_references(
     manifest_data: &[u8],
     referenced_blobs: &mut HashSet<String>,
     manifests_to_visit: &mut VecDeque<String>,
@@ -184,40 +169,3 @@ fn mark_manifest_references(
         }
     }
 }
-
-async fn cleanup_empty_dirs(root: &Path) -> std::io::Result<()> {
-    let mut dirs = Vec::new();
-    let mut stack = vec![root.to_path_buf()];
-
-    // Collect all directories
-    while let Some(dir) = stack.pop() {
-        let mut entries = match tokio::fs::read_dir(&dir).await {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if entry.file_type().await?.is_dir() {
-                stack.push(path.clone());
-                dirs.push(path);
-            }
-        }
-    }
-
-    // Sort deepest first
-    dirs.sort_by_key(|p| std::cmp::Reverse(p.components().count()));
-
-    for dir in dirs {
-        if dir == root {
-            continue;
-        }
-
-        let mut entries = tokio::fs::read_dir(&dir).await?;
-        if entries.next_entry().await?.is_none() {
-            let _ = tokio::fs::remove_dir(&dir).await;
-        }
-    }
-
-    Ok(())
-}
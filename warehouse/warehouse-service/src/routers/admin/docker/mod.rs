@@ -1 +1,3 @@
 pub mod gc;
+pub mod retention;
+pub mod uploads;
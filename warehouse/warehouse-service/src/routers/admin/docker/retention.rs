@@ -0,0 +1,487 @@
+//! Tag retention policies for the Docker registry.
+//!
+//! Rules are read from the JSON file at `DOCKER_RETENTION_POLICY_PATH`
+//! (default `./retention.json`) on every run, so edits apply without a
+//! restart:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     {
+//!       "repository": "team/*",
+//!       "keep_last": 10,
+//!       "keep_tags": "^(latest|stable)$",
+//!       "expire_after": "30d"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The first rule whose `repository` pattern matches applies; a trailing `*`
+//! matches by prefix. Tags are ranked newest first by the tag list ordering
+//! (semver-aware, see `registry::storage`). A tag is kept when it is among
+//! the `keep_last` first, matches the `keep_tags` regex, or was pushed less
+//! than `expire_after` ago (`s`, `m`, `h`, `d` or `w` suffix); every other tag
+//! is removed. A rule with neither `keep_last` nor `expire_after` keeps
+//! everything. Tags protected by a tag protection rule are always kept.
+//! Cosign `sha256-<hex>.sig`, `.att` and `.sbom` tags are not ranked: they
+//! are kept as long as the manifest they belong to is.
+//!
+//! Manifests that were only reachable through removed tags (including index
+//! children and referrers) are removed from the repository as well, and
+//! deleted once no repository reaches them any more, followed by a blob GC
+//! pass. Manifests pushed by digest are never removed on their own.
+//! Manifest pushes wait while tags and manifests are being deleted.
+//!
+//! `DOCKER_RETENTION_INTERVAL_SECONDS` (default 0, disabled) applies the
+//! policy periodically.
+
+use crate::domain::crates_error;
+use crate::domain::jwt::resource_matches;
+use crate::routers::admin::gc::{self, GcRun, Registry};
+use crate::routers::docker::registry::storage::{
+    linked_manifests, list_repositories, list_tags_for_repository, reachable_manifests,
+};
+use crate::routers::docker::{
+    MANIFEST_WRITES, manifest_key, manifest_link_key, signatures, tag_key, tag_protection,
+};
+use crate::routers::{DOCKER_STORE, quotas};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, post, web};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+use utoipa::{IntoParams, ToSchema};
+
+static POLICY_PATH: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("DOCKER_RETENTION_POLICY_PATH", "./retention.json"));
+
+/// Serializes manual and scheduled runs.
+static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Deserialize)]
+struct RetentionPolicy {
+    #[serde(default)]
+    rules: Vec<RetentionRule>,
+}
+
+#[derive(Deserialize)]
+struct RetentionRule {
+    repository: String,
+    keep_last: Option<usize>,
+    keep_tags: Option<String>,
+    expire_after: Option<String>,
+}
+
+struct CompiledRule {
+    repository: String,
+    keep_last: Option<usize>,
+    keep_tags: Option<Regex>,
+    expire_after: Option<Duration>,
+}
+
+/// A cosign signature, attestation or SBOM tag of a repository with a rule.
+struct CosignTag {
+    repository: String,
+    tag: String,
+    key: String,
+    digest: String,
+    /// Digest of the signed manifest.
+    subject: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RetentionQuery {
+    /// Report what would be removed without deleting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub(super) struct RetentionReport {
+    dry_run: bool,
+    repositories: Vec<RepositoryRetention>,
+    deleted_tags: usize,
+    deleted_manifests: usize,
+    /// Blob GC run after applying the policy; absent on dry runs.
+    gc: Option<GcRun>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RepositoryRetention {
+    repository: String,
+    /// Repository pattern of the rule that applied.
+    rule: String,
+    kept: Vec<String>,
+    deleted: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/docker/retention",
+    operation_id = "apply_retention_policy",
+    tags = ["admin"],
+    params(RetentionQuery),
+    responses(
+        (status = 200, description = "Retention policy applied (or evaluated on a dry run)", body = RetentionReport),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 500, description = "Invalid policy or storage failure")
+    )
+)]
+#[post("/docker/retention")]
+pub async fn handle(query: web::Query<RetentionQuery>) -> impl Responder {
+    match apply(query.dry_run).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            tracing::error!("retention policy failed: {e}");
+            crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, e)
+        }
+    }
+}
+
+/// Applies the policy every `DOCKER_RETENTION_INTERVAL_SECONDS`, if set.
+pub fn start() {
+    let interval: u64 = envmnt::get_or("DOCKER_RETENTION_INTERVAL_SECONDS", "0")
+        .parse()
+        .unwrap_or(0);
+    if interval == 0 {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        // The first tick completes immediately; wait a full interval instead.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match apply(false).await {
+                Ok(report) => tracing::info!(
+                    "retention policy removed {} tags and {} manifests",
+                    report.deleted_tags,
+                    report.deleted_manifests
+                ),
+                Err(e) => tracing::error!("scheduled retention policy failed: {e}"),
+            }
+        }
+    });
+}
+
+async fn apply(dry_run: bool) -> Result<RetentionReport, String> {
+    let rules = load_policy()?;
+    let _running = RUNNING.lock().await;
+    let now = SystemTime::now();
+
+    let mut repositories = Vec::new();
+    // (repository, digest) of every tag, split by the policy outcome.
+    let mut kept_roots = Vec::new();
+    let mut removed_roots = Vec::new();
+    // (tag key, digest) pairs to remove.
+    let mut removed_tags = Vec::new();
+    // Cosign tags are decided once the fate of the images they sign is known.
+    let mut cosign_tags = Vec::new();
+
+    for repository in list_repositories().await {
+        let rule = rules
+            .iter()
+            .find(|r| resource_matches(&r.repository, &repository));
+        let tags = list_tags_for_repository(&repository)
+            .await
+            .unwrap_or_default();
+
+        let mut kept = Vec::new();
+        let mut deleted = Vec::new();
+        let mut position = 0;
+        for tag in tags {
+            let Some(key) = tag_key(&repository, &tag) else {
+                continue;
+            };
+            let Ok(Some(content)) = DOCKER_STORE.get(&key).await else {
+                continue;
+            };
+            let digest = String::from_utf8_lossy(&content).trim().to_string();
+
+            if rule.is_some()
+                && let Some(subject) = signatures::cosign_subject(&tag)
+            {
+                cosign_tags.push(CosignTag {
+                    repository: repository.clone(),
+                    tag,
+                    key,
+                    digest,
+                    subject,
+                });
+                continue;
+            }
+            let keep = match rule {
+                Some(_) if tag_protection::protecting_rule(&repository, &tag).is_some() => true,
+                Some(rule) => rule.keeps(position, &tag, &key, now).await,
+                None => true,
+            };
+            position += 1;
+            if keep {
+                kept_roots.push((repository.clone(), digest));
+                kept.push(tag);
+            } else {
+                removed_roots.push((repository.clone(), digest.clone()));
+                removed_tags.push((key, digest));
+                deleted.push(tag);
+            }
+        }
+
+        if let Some(rule) = rule {
+            repositories.push(RepositoryRetention {
+                repository,
+                rule: rule.repository.clone(),
+                kept,
+                deleted,
+            });
+        }
+    }
+
+    // Manifests pushed by digest stay, unless they belong to the images
+    // whose tags are removed here.
+    let mut unrooted = reachable_manifests(removed_roots.clone()).await;
+    let cosign_roots = cosign_tags
+        .iter()
+        .map(|t| (t.repository.clone(), t.digest.clone()))
+        .collect();
+    unrooted.extend(reachable_manifests(cosign_roots).await);
+    kept_roots.extend(
+        linked_manifests()
+            .await
+            .into_iter()
+            .filter(|root| !unrooted.contains(root)),
+    );
+
+    // Signatures, attestations and SBOMs follow the image they belong to.
+    let signed: HashSet<String> = reachable_manifests(kept_roots.clone())
+        .await
+        .into_iter()
+        .map(|(_, digest)| digest)
+        .collect();
+    for cosign in cosign_tags {
+        let keep = signed.contains(&cosign.subject)
+            || tag_protection::protecting_rule(&cosign.repository, &cosign.tag).is_some();
+        let Some(retention) = repositories
+            .iter_mut()
+            .find(|r| r.repository == cosign.repository)
+        else {
+            continue;
+        };
+        if keep {
+            kept_roots.push((cosign.repository, cosign.digest));
+            retention.kept.push(cosign.tag);
+        } else {
+            removed_roots.push((cosign.repository, cosign.digest.clone()));
+            removed_tags.push((cosign.key, cosign.digest));
+            retention.deleted.push(cosign.tag);
+        }
+    }
+
+    // Manifests are shared between repositories: a repository drops the
+    // manifests its remaining roots no longer reach, and a manifest is only
+    // deleted once no remaining root anywhere reaches it.
+    let kept_reach = reachable_manifests(kept_roots).await;
+    let orphaned: Vec<(String, String)> = reachable_manifests(removed_roots)
+        .await
+        .into_iter()
+        .filter(|root| !kept_reach.contains(root))
+        .collect();
+    let kept_digests: HashSet<&String> = kept_reach.iter().map(|(_, digest)| digest).collect();
+    let deleted_manifests = orphaned
+        .iter()
+        .map(|(_, digest)| digest)
+        .filter(|digest| !kept_digests.contains(digest))
+        .collect::<HashSet<_>>()
+        .len();
+
+    let mut report = RetentionReport {
+        dry_run,
+        repositories,
+        deleted_tags: removed_tags.len(),
+        deleted_manifests,
+        gc: None,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    // Pushes wait while tags and manifests are deleted. Tags may have been
+    // pushed since the evaluation, so reachability is checked again against
+    // the roots left once the expired tags are gone.
+    let writes = MANIFEST_WRITES.write().await;
+    for (key, digest) in &removed_tags {
+        // Skip tags re-pushed since they were evaluated.
+        match DOCKER_STORE.get(key).await {
+            Ok(Some(content)) if String::from_utf8_lossy(&content).trim() == digest => {}
+            _ => continue,
+        }
+        DOCKER_STORE.delete(key).await.map_err(|e| e.to_string())?;
+    }
+    let orphaned_set: HashSet<&(String, String)> = orphaned.iter().collect();
+    let mut roots = tagged_roots().await;
+    roots.extend(
+        linked_manifests()
+            .await
+            .into_iter()
+            .filter(|root| !orphaned_set.contains(root)),
+    );
+    let still_reachable = reachable_manifests(roots).await;
+    let still_reachable_digests: HashSet<&String> =
+        still_reachable.iter().map(|(_, digest)| digest).collect();
+
+    let mut unlinked = HashSet::new();
+    for root in &orphaned {
+        if still_reachable.contains(root) {
+            continue;
+        }
+        let (repository, digest) = root;
+        unlink_manifest(repository, digest)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !still_reachable_digests.contains(digest) {
+            unlinked.insert(digest);
+        }
+    }
+    for digest in &unlinked {
+        if let Some(key) = manifest_key(digest) {
+            DOCKER_STORE.delete(&key).await.map_err(|e| e.to_string())?;
+        }
+    }
+    drop(writes);
+    quotas::docker_usage_changed();
+    report.deleted_manifests = unlinked.len();
+
+    report.gc = Some(gc::run(Registry::Docker, "retention").await);
+    Ok(report)
+}
+
+/// `(repository, digest)` of every tag in the registry.
+async fn tagged_roots() -> Vec<(String, String)> {
+    let mut roots = Vec::new();
+    for repository in list_repositories().await {
+        for tag in list_tags_for_repository(&repository)
+            .await
+            .unwrap_or_default()
+        {
+            let Some(key) = tag_key(&repository, &tag) else {
+                continue;
+            };
+            if let Ok(Some(content)) = DOCKER_STORE.get(&key).await {
+                let digest = String::from_utf8_lossy(&content).trim().to_string();
+                roots.push((repository.clone(), digest));
+            }
+        }
+    }
+    roots
+}
+
+impl CompiledRule {
+    async fn keeps(&self, position: usize, tag: &str, key: &str, now: SystemTime) -> bool {
+        if self.keep_last.is_none() && self.expire_after.is_none() {
+            return true;
+        }
+        if self.keep_last.is_some_and(|n| position < n) {
+            return true;
+        }
+        if self.keep_tags.as_ref().is_some_and(|re| re.is_match(tag)) {
+            return true;
+        }
+        let Some(max_age) = self.expire_after else {
+            return false;
+        };
+        // Tags of unknown age are never expired.
+        match DOCKER_STORE.modified(key).await {
+            Ok(Some(modified)) => now.duration_since(modified).is_ok_and(|age| age < max_age),
+            _ => true,
+        }
+    }
+}
+
+fn load_policy() -> Result<Vec<CompiledRule>, String> {
+    let data = match std::fs::read(POLICY_PATH.as_str()) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {}: {e}", POLICY_PATH.as_str())),
+    };
+    let policy: RetentionPolicy = serde_json::from_slice(&data)
+        .map_err(|e| format!("invalid retention policy {}: {e}", POLICY_PATH.as_str()))?;
+
+    policy
+        .rules
+        .into_iter()
+        .map(|rule| {
+            let keep_tags = rule
+                .keep_tags
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("invalid keep_tags for `{}`: {e}", rule.repository))?;
+            let expire_after = match rule.expire_after.as_deref() {
+                Some(value) => Some(parse_duration(value).ok_or_else(|| {
+                    format!("invalid expire_after `{value}` for `{}`", rule.repository)
+                })?),
+                None => None,
+            };
+            Ok(CompiledRule {
+                repository: rule.repository,
+                keep_last: rule.keep_last,
+                keep_tags,
+                expire_after,
+            })
+        })
+        .collect()
+}
+
+/// Parses durations such as `90s`, `30m`, `12h`, `30d` or `2w`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let unit = value.chars().last()?;
+    let amount: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount.checked_mul(seconds)?))
+}
+
+/// Removes a manifest from `repository`: its link and its referrers index
+/// entries. The manifest itself is left to the caller.
+async fn unlink_manifest(repository: &str, digest: &str) -> std::io::Result<()> {
+    let Some(key) = manifest_key(digest) else {
+        return Ok(());
+    };
+    let hex = digest.trim_start_matches("sha256:");
+
+    if let Some(data) = DOCKER_STORE.get(&key).await?
+        && let Ok(value) = serde_json::from_slice::<Value>(&data)
+        && let Some(subject) = value
+            .get("subject")
+            .and_then(|s| s.get("digest"))
+            .and_then(|d| d.as_str())
+    {
+        let subject_hex = subject.trim_start_matches("sha256:");
+        DOCKER_STORE
+            .delete(&format!("{repository}/_referrers/{subject_hex}/{hex}"))
+            .await?;
+    }
+    for referrer in DOCKER_STORE
+        .list(&format!("{repository}/_referrers/{hex}/"))
+        .await?
+    {
+        DOCKER_STORE.delete(&referrer).await?;
+    }
+
+    match manifest_link_key(repository, digest) {
+        Some(link_key) => DOCKER_STORE.delete(&link_key).await,
+        None => Ok(()),
+    }
+}
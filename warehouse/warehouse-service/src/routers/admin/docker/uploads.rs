@@ -0,0 +1,37 @@
+use crate::routers::docker::uploads::{self, UploadSession};
+use actix_web::{HttpResponse, Responder, get};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub(super) struct UploadSessions {
+    /// Idle time after which sessions are reaped; absent when disabled.
+    ttl_seconds: Option<u64>,
+    sessions: Vec<UploadSession>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/docker/uploads",
+    operation_id = "list_upload_sessions",
+    tags = ["admin"],
+    responses(
+        (status = 200, description = "Blob upload sessions in progress, oldest first", body = UploadSessions),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 500, description = "Storage failure")
+    )
+)]
+#[get("/docker/uploads")]
+pub async fn handle() -> impl Responder {
+    match uploads::list_sessions().await {
+        Ok(sessions) => HttpResponse::Ok().json(UploadSessions {
+            ttl_seconds: uploads::ttl().map(|ttl| ttl.as_secs()),
+            sessions,
+        }),
+        Err(e) => {
+            tracing::error!("failed to list upload sessions: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
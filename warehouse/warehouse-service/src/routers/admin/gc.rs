@@ -0,0 +1,411 @@
+//! Background garbage collection runs.
+//!
+//! Docker and crates GC run as background jobs, one at a time per registry.
+//! Each run reports its progress through [`status`] and is appended to a
+//! persisted history once it finishes.
+//!
+//! Configuration:
+//! - `DOCKER_GC_INTERVAL_SECONDS` / `CRATES_GC_INTERVAL_SECONDS` (default 0,
+//!   disabled): run GC periodically
+//! - `GC_GRACE_PERIOD_SECONDS` (default 3600): unreferenced Docker blobs
+//!   written more recently are kept, so layers of pushes whose manifest has
+//!   not been PUT yet survive. Blobs a push reuses instead of uploading
+//!   (found by `HEAD`, mounted, or uploaded again) get a fresh timestamp for
+//!   the same reason.
+//! - `GC_HISTORY_PATH` (default `./storage/gc/history.jsonl`) and
+//!   `GC_HISTORY_LIMIT` (default 100 runs kept)
+
+use super::crates::gc as crates_gc;
+use super::docker::gc as docker_gc;
+use crate::domain::{crates_error, metrics};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, get, web};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::MutexGuard;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+static GRACE_PERIOD: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(
+        envmnt::get_or("GC_GRACE_PERIOD_SECONDS", "3600")
+            .parse()
+            .unwrap_or(3600),
+    )
+});
+
+static HISTORY_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    PathBuf::from(envmnt::get_or(
+        "GC_HISTORY_PATH",
+        "./storage/gc/history.jsonl",
+    ))
+});
+
+static HISTORY_LIMIT: LazyLock<usize> = LazyLock::new(|| {
+    envmnt::get_or("GC_HISTORY_LIMIT", "100")
+        .parse()
+        .unwrap_or(100)
+});
+
+/// Held for the whole run; blocks concurrent GC of the same registry.
+static DOCKER_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+static CRATES_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// In-flight run per registry.
+static RUNNING: LazyLock<Mutex<HashMap<&'static str, GcRun>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    Docker,
+    Crates,
+}
+
+impl Registry {
+    fn as_str(self) -> &'static str {
+        match self {
+            Registry::Docker => "docker",
+            Registry::Crates => "crates",
+        }
+    }
+
+    fn lock(self) -> &'static tokio::sync::Mutex<()> {
+        match self {
+            Registry::Docker => &DOCKER_LOCK,
+            Registry::Crates => &CRATES_LOCK,
+        }
+    }
+}
+
+/// One GC run, in flight or finished.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct GcRun {
+    pub(crate) id: String,
+    pub(crate) registry: String,
+    /// `manual`, `scheduled` or `retention`.
+    pub(crate) trigger: String,
+    /// `running`, `succeeded` or `failed`.
+    pub(crate) status: String,
+    pub(crate) started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) finished_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) progress: Option<GcProgress>,
+    /// Registry-specific GC report of a successful run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub(crate) report: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub(crate) struct GcProgress {
+    /// Current phase, e.g. `mark` or `sweep`.
+    pub(crate) phase: String,
+    pub(crate) done: usize,
+    pub(crate) total: usize,
+}
+
+/// Unreferenced objects written within the grace period are kept.
+pub(crate) fn grace_period() -> Duration {
+    *GRACE_PERIOD
+}
+
+/// Reports progress of the current run to the status endpoint.
+pub(crate) struct ProgressReporter {
+    registry: &'static str,
+}
+
+impl ProgressReporter {
+    pub(crate) fn update(&self, phase: &str, done: usize, total: usize) {
+        let mut running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(run) = running.get_mut(self.registry) {
+            run.progress = Some(GcProgress {
+                phase: phase.to_string(),
+                done,
+                total,
+            });
+        }
+    }
+
+    /// Unreferenced objects written within the grace period are kept.
+    pub(crate) fn grace_period(&self) -> Duration {
+        grace_period()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Running
+// ---------------------------------------------------------------------------
+
+/// Starts a run in the background. Returns `None` when one is already
+/// running for `registry`.
+pub(crate) fn spawn(registry: Registry, trigger: &'static str) -> Option<GcRun> {
+    let guard = registry.lock().try_lock().ok()?;
+    let run = begin(registry, trigger);
+    let started = run.clone();
+    actix_web::rt::spawn(execute(registry, run, guard));
+    Some(started)
+}
+
+/// Runs to completion, first waiting for a run in progress to finish.
+pub(crate) async fn run(registry: Registry, trigger: &'static str) -> GcRun {
+    let guard = registry.lock().lock().await;
+    let run = begin(registry, trigger);
+    execute(registry, run, guard).await
+}
+
+fn begin(registry: Registry, trigger: &'static str) -> GcRun {
+    let run = GcRun {
+        id: Uuid::new_v4().simple().to_string(),
+        registry: registry.as_str().to_string(),
+        trigger: trigger.to_string(),
+        status: "running".to_string(),
+        started_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        duration_seconds: None,
+        progress: None,
+        report: None,
+        error: None,
+    };
+    RUNNING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(registry.as_str(), run.clone());
+    run
+}
+
+async fn execute(registry: Registry, mut run: GcRun, _guard: MutexGuard<'static, ()>) -> GcRun {
+    let progress = ProgressReporter {
+        registry: registry.as_str(),
+    };
+    let started = Instant::now();
+
+    let result = match registry {
+        Registry::Docker => docker_gc::garbage_collect(&progress).await.map(|r| {
+            let counts = (r.deleted as u64, r.kept as u64);
+            (counts, serde_json::to_value(r).unwrap_or_default())
+        }),
+        Registry::Crates => crates_gc::garbage_collect(&progress).await.map(|r| {
+            let counts = (r.deleted_crates as u64, r.kept_crates as u64);
+            (counts, serde_json::to_value(r).unwrap_or_default())
+        }),
+    };
+    let elapsed = started.elapsed();
+    metrics::record_gc(
+        registry.as_str(),
+        elapsed,
+        result.as_ref().map(|(counts, _)| *counts).map_err(|_| ()),
+    );
+
+    run.finished_at = Some(Utc::now().to_rfc3339());
+    run.duration_seconds = Some(elapsed.as_secs_f64());
+    match result {
+        Ok((_, report)) => {
+            run.status = "succeeded".to_string();
+            run.report = Some(report);
+        }
+        Err(e) => {
+            tracing::error!("{} GC failed: {e}", registry.as_str());
+            run.status = "failed".to_string();
+            run.error = Some(e.to_string());
+        }
+    }
+    RUNNING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(registry.as_str());
+    // Progress only matters while the run is in flight.
+    run.progress = None;
+
+    if let Err(e) = append_history(&run).await {
+        tracing::error!("failed to record GC run {}: {e}", run.id);
+    }
+    run
+}
+
+/// Runs GC of `registry` every `<REGISTRY>_GC_INTERVAL_SECONDS`, if set.
+pub fn start(registry: Registry) {
+    let variable = match registry {
+        Registry::Docker => "DOCKER_GC_INTERVAL_SECONDS",
+        Registry::Crates => "CRATES_GC_INTERVAL_SECONDS",
+    };
+    let interval: u64 = envmnt::get_or(variable, "0").parse().unwrap_or(0);
+    if interval == 0 {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        // The first tick completes immediately; wait a full interval instead.
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if spawn(registry, "scheduled").is_none() {
+                tracing::info!(
+                    "skipping scheduled {} GC: a run is in progress",
+                    registry.as_str()
+                );
+            }
+        }
+    });
+}
+
+// ---------------------------------------------------------------------------
+// History
+// ---------------------------------------------------------------------------
+
+async fn read_history() -> Vec<GcRun> {
+    let Ok(data) = tokio::fs::read_to_string(HISTORY_PATH.as_path()).await else {
+        return Vec::new();
+    };
+    data.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Appends a finished run, keeping the newest `GC_HISTORY_LIMIT` entries.
+async fn append_history(run: &GcRun) -> std::io::Result<()> {
+    static WRITE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _write = WRITE.lock().await;
+
+    let mut runs = read_history().await;
+    runs.push(run.clone());
+    let skip = runs.len().saturating_sub(*HISTORY_LIMIT);
+
+    let mut data = String::new();
+    for run in &runs[skip..] {
+        data.push_str(&serde_json::to_string(run)?);
+        data.push('\n');
+    }
+
+    if let Some(parent) = HISTORY_PATH.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = HISTORY_PATH.with_extension("jsonl.tmp");
+    tokio::fs::write(&tmp, data).await?;
+    tokio::fs::rename(&tmp, HISTORY_PATH.as_path()).await
+}
+
+// ---------------------------------------------------------------------------
+// Endpoints
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, ToSchema)]
+pub struct GcStatus {
+    docker: RegistryGcStatus,
+    crates: RegistryGcStatus,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RegistryGcStatus {
+    /// The run in progress, with its progress.
+    running: Option<GcRun>,
+    /// The most recently finished run.
+    last_run: Option<GcRun>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/gc/status",
+    operation_id = "get_gc_status",
+    tags = ["admin"],
+    responses(
+        (status = 200, description = "Running and last finished GC run per registry", body = GcStatus),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+    )
+)]
+#[get("/gc/status")]
+pub async fn status() -> impl Responder {
+    let finished = read_history().await;
+    let running = RUNNING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    let registry_status = |registry: Registry| RegistryGcStatus {
+        running: running.get(registry.as_str()).cloned(),
+        last_run: finished
+            .iter()
+            .rev()
+            .find(|r| r.registry == registry.as_str())
+            .cloned(),
+    };
+
+    HttpResponse::Ok().json(GcStatus {
+        docker: registry_status(Registry::Docker),
+        crates: registry_status(Registry::Crates),
+    })
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    /// Only list runs of this registry (`docker` or `crates`).
+    pub registry: Option<String>,
+    /// Maximum number of runs to return (default 20).
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GcHistory {
+    /// Finished runs, newest first.
+    runs: Vec<GcRun>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/gc/history",
+    operation_id = "get_gc_history",
+    tags = ["admin"],
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Finished GC runs, newest first", body = GcHistory),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+    )
+)]
+#[get("/gc/history")]
+pub async fn history(query: web::Query<HistoryQuery>) -> impl Responder {
+    let runs = read_history()
+        .await
+        .into_iter()
+        .rev()
+        .filter(|r| query.registry.as_ref().is_none_or(|reg| &r.registry == reg))
+        .take(query.limit.unwrap_or(20))
+        .collect();
+    HttpResponse::Ok().json(GcHistory { runs })
+}
+
+/// Response of the `POST /admin/<registry>/gc` endpoints.
+pub(crate) async fn start_response(registry: Registry, wait: bool) -> HttpResponse {
+    if wait {
+        return match run(registry, "manual").await.report {
+            Some(report) => HttpResponse::Ok().json(report),
+            None => HttpResponse::InternalServerError().finish(),
+        };
+    }
+    match spawn(registry, "manual") {
+        Some(run) => HttpResponse::Accepted().json(run),
+        None => crates_error::response(
+            StatusCode::CONFLICT,
+            format!("{} GC is already running", registry.as_str()),
+        ),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct GcQuery {
+    /// Wait for the run to finish and return its report instead of
+    /// starting it in the background.
+    #[serde(default)]
+    pub wait: bool,
+}
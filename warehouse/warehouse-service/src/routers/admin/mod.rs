@@ -2,14 +2,33 @@ use actix_web::dev::HttpServiceFactory;
 use actix_web::web;
 use utoipa::OpenApi;
 
+pub mod audit;
+pub mod auth;
 pub mod crates;
 pub mod docker;
+pub mod gc;
+pub mod quotas;
+pub mod replication;
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
+        audit::handle,
+        auth::tokens::create,
+        auth::tokens::list,
+        auth::tokens::revoke,
+        auth::users::list,
+        auth::users::put,
+        auth::users::remove,
         crates::gc::handle,
         docker::gc::handle,
+        docker::retention::handle,
+        docker::uploads::handle,
+        gc::status,
+        gc::history,
+        quotas::handle,
+        replication::status,
+        replication::receive_crate,
     ),
     tags((name = "admin", description = "Admin endpoints"))
 )]
@@ -18,6 +37,20 @@ pub struct AdminApiDoc;
 pub fn scope() -> impl HttpServiceFactory {
     // Admin endpoints
     web::scope("/admin")
+        .service(audit::handle)
+        .service(auth::tokens::create)
+        .service(auth::tokens::list)
+        .service(auth::tokens::revoke)
+        .service(auth::users::list)
+        .service(auth::users::put)
+        .service(auth::users::remove)
         .service(crates::gc::handle)
         .service(docker::gc::handle)
+        .service(docker::retention::handle)
+        .service(docker::uploads::handle)
+        .service(gc::status)
+        .service(gc::history)
+        .service(quotas::handle)
+        .service(replication::status)
+        .service(replication::receive_crate)
 }
@@ -0,0 +1,25 @@
+use crate::routers::quotas::{self, QuotaReport};
+use actix_web::{HttpResponse, Responder, get};
+
+#[utoipa::path(
+    get,
+    path = "/quotas",
+    operation_id = "get_storage_quotas",
+    tags = ["admin"],
+    responses(
+        (status = 200, description = "Usage of every configured storage quota", body = QuotaReport),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 500, description = "Storage failure")
+    )
+)]
+#[get("/quotas")]
+pub async fn handle() -> impl Responder {
+    match quotas::report().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            tracing::error!("failed to compute storage quota usage: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
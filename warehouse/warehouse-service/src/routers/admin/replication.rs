@@ -0,0 +1,64 @@
+use crate::domain::crates_error;
+use crate::routers::crates_enabled;
+use crate::routers::replication::{self, CrateReplica, ReplicationStatus};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, get, put, web};
+
+#[utoipa::path(
+    get,
+    path = "/replication",
+    operation_id = "get_replication_status",
+    tags = ["admin"],
+    responses(
+        (status = 200, description = "Queue depth and lag of every replication peer", body = ReplicationStatus),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied")
+    )
+)]
+#[get("/replication")]
+pub async fn status() -> impl Responder {
+    HttpResponse::Ok().json(replication::status().await)
+}
+
+#[utoipa::path(
+    put,
+    path = "/replication/crates/{name}/{version}",
+    operation_id = "receive_replicated_crate",
+    tags = ["admin"],
+    params(
+        ("name" = String, Path, description = "Crate name"),
+        ("version" = String, Path, description = "Crate version"),
+    ),
+    request_body = CrateReplica,
+    responses(
+        (status = 204, description = "Crate version stored"),
+        (status = 400, description = "Malformed replica"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Crate registry disabled"),
+        (status = 422, description = "Replica does not match the crate version"),
+        (status = 500, description = "Storage failure")
+    )
+)]
+#[put("/replication/crates/{name}/{version}")]
+pub async fn receive_crate(path: web::Path<(String, String)>, body: web::Bytes) -> impl Responder {
+    if !crates_enabled() {
+        return crates_error::response(StatusCode::NOT_FOUND, "crate registry is disabled");
+    }
+    // Read as raw bytes: tarballs easily exceed the default JSON size limit.
+    let replica: CrateReplica = match serde_json::from_slice(&body) {
+        Ok(replica) => replica,
+        Err(e) => {
+            return crates_error::response(
+                StatusCode::BAD_REQUEST,
+                format!("invalid replica: {e}"),
+            );
+        }
+    };
+
+    let (name, version) = path.into_inner();
+    match replication::apply_crate(&name, &version, replica).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(response) => response,
+    }
+}
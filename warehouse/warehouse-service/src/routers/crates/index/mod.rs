@@ -1,4 +1,6 @@
-use crate::routers::crates::{index_file_path, index_prefix, validate_crate_name};
+use crate::domain::jwt::JwtConfig;
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::{index_file_key, index_prefix, upstream, validate_crate_name};
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
@@ -33,12 +35,12 @@ struct IndexConfig {
     )
 )]
 #[get("/config.json")]
-async fn get_index_config() -> impl Responder {
+async fn get_index_config(jwt_config: web::Data<JwtConfig>) -> impl Responder {
     let base = REGISTRY_BASE_URL.as_str().trim_end_matches('/');
     let config = IndexConfig {
         dl: format!("{base}/api/v1/crates/{{crate}}/{{version}}/download"),
         api: base.to_string(),
-        auth_required: false,
+        auth_required: jwt_config.crates_auth_required,
     };
     HttpResponse::Ok()
         .content_type("application/json")
@@ -83,13 +85,17 @@ async fn get_crate_index(req: HttpRequest, path: web::Path<String>) -> impl Resp
         return HttpResponse::NotFound().finish();
     }
 
-    let Some(index_path) = index_file_path(&name) else {
+    let Some(index_key) = index_file_key(&name) else {
         return HttpResponse::NotFound().finish();
     };
 
-    let data = tokio::fs::read(&index_path)
-        .await
-        .unwrap_or_else(|_| b"[]".to_vec());
+    // Local crates shadow upstream ones of the same name.
+    let data = match CRATES_STORE.get(&index_key).await {
+        Ok(Some(data)) => data,
+        _ => upstream::index_file(&name)
+            .await
+            .unwrap_or_else(|| b"[]".to_vec()),
+    };
 
     // ETag based on SHA-256 of the file contents
     let etag = {
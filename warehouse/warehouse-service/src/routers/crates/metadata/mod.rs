@@ -0,0 +1,380 @@
+//! Per-version publish metadata (description, license, links, keywords …)
+//! and the README cargo sends, plus the crates.io-compatible crate and
+//! version info endpoints built on top of them.
+//!
+//! Stored next to each tarball:
+//! - `<n>/<version>/metadata.json`
+//! - `<n>/<version>/README.md`
+
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::search::compare_versions;
+use crate::routers::crates::{
+    index_file_key, metadata_file_key, readme_file_key, validate_crate_name, validate_version,
+};
+use actix_web::{HttpResponse, Responder, get, web};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+// ---------------------------------------------------------------------------
+// Stored metadata
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct CrateMetadata {
+    pub name: String,
+    pub vers: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub documentation: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub license_file: Option<String>,
+    #[serde(default)]
+    pub readme_file: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Login of the publishing account.
+    #[serde(default)]
+    pub published_by: Option<String>,
+    /// RFC 3339 publish timestamp.
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// Writes `metadata.json` and, when cargo sent one, `README.md` for a version.
+pub(super) async fn save_metadata(
+    metadata: &CrateMetadata,
+    readme: Option<&str>,
+) -> std::io::Result<()> {
+    let (Some(meta_key), Some(readme_key)) = (
+        metadata_file_key(&metadata.name, &metadata.vers),
+        readme_file_key(&metadata.name, &metadata.vers),
+    ) else {
+        return Err(std::io::Error::other("invalid crate name or version"));
+    };
+
+    let data = serde_json::to_vec_pretty(metadata).map_err(std::io::Error::other)?;
+    CRATES_STORE.put(&meta_key, data).await?;
+
+    if let Some(readme) = readme.filter(|r| !r.is_empty()) {
+        CRATES_STORE
+            .put(&readme_key, readme.as_bytes().to_vec())
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn load_metadata(name: &str, version: &str) -> Option<CrateMetadata> {
+    let data = CRATES_STORE
+        .get(&metadata_file_key(name, version)?)
+        .await
+        .ok()??;
+    serde_json::from_slice(&data).ok()
+}
+
+pub(crate) async fn load_readme(name: &str, version: &str) -> Option<String> {
+    let data = CRATES_STORE
+        .get(&readme_file_key(name, version)?)
+        .await
+        .ok()??;
+    String::from_utf8(data).ok()
+}
+
+// ---------------------------------------------------------------------------
+// Response types (crates.io-compatible subset)
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize, ToSchema)]
+pub struct CrateInfo {
+    id: String,
+    name: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+    max_version: String,
+    max_stable_version: Option<String>,
+    newest_version: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PublishedBy {
+    login: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionInfo {
+    #[serde(rename = "crate")]
+    krate: String,
+    num: String,
+    yanked: bool,
+    checksum: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+    repository: Option<String>,
+    license: Option<String>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+    authors: Vec<String>,
+    features: HashMap<String, Vec<String>>,
+    links: Option<String>,
+    rust_version: Option<String>,
+    created_at: Option<String>,
+    published_by: Option<PublishedBy>,
+    dl_path: String,
+    readme_path: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+    versions: Vec<VersionInfo>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionResponse {
+    version: VersionInfo,
+}
+
+/// The index fields needed to describe a version.
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+    cksum: String,
+    #[serde(default)]
+    yanked: bool,
+    #[serde(default)]
+    features: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    links: Option<String>,
+    #[serde(default)]
+    rust_version: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/v1/crates/{name}
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get,
+    operation_id = "get_crate",
+    tags = ["crates"],
+    path = "/{name}",
+    params(
+        ("name" = String, Path, description = "Crate name"),
+    ),
+    responses(
+        (status = 200, description = "Crate metadata and versions (newest first)", body = CrateResponse, content_type = "application/json"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Crate not found"),
+    )
+)]
+#[get("/{name}")]
+pub async fn get_crate(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    if !validate_crate_name(&name) {
+        return not_found();
+    }
+
+    let entries = read_index(&name).await;
+    let Some(newest) = entries.last() else {
+        return not_found();
+    };
+
+    let mut versions = Vec::with_capacity(entries.len());
+    for entry in entries.iter().rev() {
+        let metadata = load_metadata(&name, &entry.vers).await;
+        versions.push(version_info(&name, entry, metadata).await);
+    }
+
+    let max_version = entries
+        .iter()
+        .filter(|e| !e.yanked)
+        .map(|e| e.vers.as_str())
+        .max_by(|a, b| compare_versions(a, b))
+        .unwrap_or(newest.vers.as_str())
+        .to_string();
+    let max_stable_version = entries
+        .iter()
+        .filter(|e| !e.yanked && !e.vers.contains('-'))
+        .map(|e| e.vers.as_str())
+        .max_by(|a, b| compare_versions(a, b))
+        .map(str::to_string);
+
+    // Crate-level fields come from the highest non-yanked version.
+    let latest = load_metadata(&name, &max_version).await.unwrap_or_default();
+    let created_at = match entries.first() {
+        Some(first) => load_metadata(&name, &first.vers)
+            .await
+            .and_then(|m| m.created_at),
+        None => None,
+    };
+    let updated_at = versions.first().and_then(|v| v.created_at.clone());
+
+    HttpResponse::Ok().json(CrateResponse {
+        krate: CrateInfo {
+            id: name.clone(),
+            name,
+            description: latest.description,
+            homepage: latest.homepage,
+            documentation: latest.documentation,
+            repository: latest.repository,
+            keywords: latest.keywords,
+            categories: latest.categories,
+            max_version,
+            max_stable_version,
+            newest_version: newest.vers.clone(),
+            created_at,
+            updated_at,
+        },
+        versions,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/v1/crates/{name}/{version}
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get,
+    operation_id = "get_crate_version",
+    tags = ["crates"],
+    path = "/{name}/{version}",
+    params(
+        ("name"    = String, Path, description = "Crate name"),
+        ("version" = String, Path, description = "Crate version"),
+    ),
+    responses(
+        (status = 200, description = "Version metadata", body = VersionResponse, content_type = "application/json"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Crate or version not found"),
+    )
+)]
+#[get("/{name}/{version}")]
+pub async fn get_version(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, version) = path.into_inner();
+    if !validate_crate_name(&name) || !validate_version(&version) {
+        return not_found();
+    }
+
+    let entries = read_index(&name).await;
+    let Some(entry) = entries.iter().find(|e| e.vers == version) else {
+        return not_found();
+    };
+
+    let metadata = load_metadata(&name, &version).await;
+    HttpResponse::Ok().json(VersionResponse {
+        version: version_info(&name, entry, metadata).await,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/v1/crates/{name}/{version}/readme
+// ---------------------------------------------------------------------------
+
+#[utoipa::path(
+    get,
+    operation_id = "get_crate_readme",
+    tags = ["crates"],
+    path = "/{name}/{version}/readme",
+    params(
+        ("name"    = String, Path, description = "Crate name"),
+        ("version" = String, Path, description = "Crate version"),
+    ),
+    responses(
+        (status = 200, description = "README as published", body = String, content_type = "text/markdown"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "No README for this version"),
+    )
+)]
+#[get("/{name}/{version}/readme")]
+pub async fn get_readme(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, version) = path.into_inner();
+    let Some(readme_key) = readme_file_key(&name, &version) else {
+        return not_found();
+    };
+
+    match CRATES_STORE.get(&readme_key).await {
+        Ok(Some(body)) => HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .body(body),
+        _ => not_found(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+async fn read_index(name: &str) -> Vec<IndexEntry> {
+    let Some(key) = index_file_key(name) else {
+        return Vec::new();
+    };
+    let Ok(Some(content)) = CRATES_STORE.get(&key).await else {
+        return Vec::new();
+    };
+
+    String::from_utf8_lossy(&content)
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+async fn version_info(
+    name: &str,
+    entry: &IndexEntry,
+    metadata: Option<CrateMetadata>,
+) -> VersionInfo {
+    let has_readme = match readme_file_key(name, &entry.vers) {
+        Some(key) => matches!(CRATES_STORE.size(&key).await, Ok(Some(_))),
+        None => false,
+    };
+    let metadata = metadata.unwrap_or_default();
+
+    VersionInfo {
+        krate: name.to_string(),
+        num: entry.vers.clone(),
+        yanked: entry.yanked,
+        checksum: entry.cksum.clone(),
+        description: metadata.description,
+        homepage: metadata.homepage,
+        documentation: metadata.documentation,
+        repository: metadata.repository,
+        license: metadata.license,
+        keywords: metadata.keywords,
+        categories: metadata.categories,
+        authors: metadata.authors,
+        features: entry.features.clone(),
+        links: entry.links.clone(),
+        rust_version: entry.rust_version.clone(),
+        created_at: metadata.created_at,
+        published_by: metadata.published_by.map(|login| PublishedBy { login }),
+        dl_path: format!("/api/v1/crates/{name}/{}/download", entry.vers),
+        readme_path: has_readme.then(|| format!("/api/v1/crates/{name}/{}/readme", entry.vers)),
+    }
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "errors": [{ "detail": "crate or version not found" }]
+    }))
+}
@@ -1,49 +1,77 @@
-use crate::routers::CRATES_STORAGE_ROOT;
 use actix_web::dev::HttpServiceFactory;
 use actix_web::middleware::NormalizePath;
 use actix_web::web;
 use ops::{download, publish, unyank, yank};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use tokio::sync::OwnedMutexGuard;
 use utoipa::OpenApi;
 
 pub mod index;
+pub mod metadata;
 pub mod ops;
 pub mod owners;
 pub mod search;
+mod tarball;
+pub mod upstream;
 
 // ---------------------------------------------------------------------------
-// Path helpers
+// Storage keys
 // ---------------------------------------------------------------------------
 
-/// On-disk path for a `.crate` tarball.
+/// Store key of a `.crate` tarball.
 ///
-/// Layout: `<root>/<n>/<version>/<n>-<version>.crate`
-pub(super) fn crate_file_path(name: &str, version: &str) -> Option<PathBuf> {
+/// Layout: `<n>/<version>/<n>-<version>.crate`
+pub(super) fn crate_file_key(name: &str, version: &str) -> Option<String> {
     if !validate_crate_name(name) || !validate_version(version) {
         return None;
     }
-    Some(
-        PathBuf::from(CRATES_STORAGE_ROOT.as_str())
-            .join(name)
-            .join(version)
-            .join(format!("{name}-{version}.crate")),
-    )
+    Some(format!("{name}/{version}/{name}-{version}.crate"))
+}
+
+/// Store key of a version's publish metadata.
+///
+/// Layout: `<n>/<version>/metadata.json`
+pub(super) fn metadata_file_key(name: &str, version: &str) -> Option<String> {
+    crate_file_key(name, version).map(|_| format!("{name}/{version}/metadata.json"))
 }
 
-/// On-disk path for the newline-delimited JSON sparse index file.
+/// Store key of the README published with a version.
 ///
-/// Layout: `<root>/index/<prefix>/<n>`
-pub(super) fn index_file_path(name: &str) -> Option<PathBuf> {
+/// Layout: `<n>/<version>/README.md`
+pub(super) fn readme_file_key(name: &str, version: &str) -> Option<String> {
+    crate_file_key(name, version).map(|_| format!("{name}/{version}/README.md"))
+}
+
+/// Store key of the newline-delimited JSON sparse index file.
+///
+/// Layout: `index/<prefix>/<n>`
+pub(super) fn index_file_key(name: &str) -> Option<String> {
     if !validate_crate_name(name) {
         return None;
     }
-    let prefix = index_prefix(name);
-    Some(
-        PathBuf::from(CRATES_STORAGE_ROOT.as_str())
-            .join("index")
-            .join(&prefix)
-            .join(name),
-    )
+    Some(format!("index/{}/{name}", index_prefix(name)))
+}
+
+// ---------------------------------------------------------------------------
+// Per-crate locking
+// ---------------------------------------------------------------------------
+
+type CrateLocks = Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>;
+
+static CRATE_LOCKS: LazyLock<CrateLocks> = LazyLock::new(Default::default);
+
+/// Serialises index read-modify-write cycles (publish, yank, unyank, GC
+/// repair) of one crate within this process. The lock is released when the
+/// returned guard is dropped.
+pub(super) async fn lock_crate(name: &str) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = CRATE_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        // Drop locks nobody holds or waits for.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(name.to_ascii_lowercase()).or_default().clone()
+    };
+    lock.lock_owned().await
 }
 
 /// Sparse-index directory prefix following the crates.io convention:
@@ -93,6 +121,9 @@ pub(super) fn validate_version(version: &str) -> bool {
         yank::handle,
         unyank::handle,
         search::handle,
+        metadata::get_crate,
+        metadata::get_version,
+        metadata::get_readme,
         owners::list,
         owners::add,
         owners::remove,
@@ -132,6 +163,11 @@ pub fn scope() -> impl HttpServiceFactory {
         .service(owners::list)
         .service(owners::add)
         .service(owners::remove)
+        // Registered after the fixed-suffix routes above so `/{name}/owners`
+        // is not captured as a version.
+        .service(metadata::get_readme)
+        .service(metadata::get_version)
+        .service(metadata::get_crate)
 }
 
 pub fn scope_index() -> impl HttpServiceFactory {
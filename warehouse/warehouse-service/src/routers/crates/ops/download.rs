@@ -1,5 +1,7 @@
-use crate::routers::crates::{crate_file_path, validate_crate_name, validate_version};
-use actix_web::{HttpResponse, Responder, get, web};
+use crate::domain::crates_error;
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::{crate_file_key, upstream, validate_crate_name, validate_version};
+use actix_web::{HttpResponse, Responder, get, http::StatusCode, web};
 
 #[utoipa::path(
     get,
@@ -16,6 +18,7 @@ use actix_web::{HttpResponse, Responder, get, web};
         (status = 403,  description = "Access denied"),
         (status = 404,  description = "Crate or version not found"),
         (status = 429,  description = "Too many requests"),
+        (status = 502,  description = "Upstream registry failure or checksum mismatch"),
     )
 )]
 #[get("/{name}/{version}/download")]
@@ -26,13 +29,20 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
         return not_found();
     }
 
-    let Some(crate_path) = crate_file_path(&name, &version) else {
+    let Some(crate_key) = crate_file_key(&name, &version) else {
         return not_found();
     };
 
-    let data = match tokio::fs::read(&crate_path).await {
-        Ok(d) => d,
-        Err(_) => return not_found(),
+    let data = match CRATES_STORE.get(&crate_key).await {
+        Ok(Some(d)) => d,
+        _ => match upstream::crate_file(&name, &version).await {
+            Ok(Some(d)) => d,
+            Ok(None) => return not_found(),
+            Err(detail) => {
+                tracing::error!("upstream fetch of {name}-{version} failed: {detail}");
+                return crates_error::response(StatusCode::BAD_GATEWAY, detail);
+            }
+        },
     };
 
     HttpResponse::Ok()
@@ -1,7 +1,14 @@
+use crate::domain::jwt::Claims;
+use crate::domain::{audit, webhooks};
+use crate::routers::crates::metadata::{CrateMetadata, save_metadata};
+use crate::routers::crates::owners::{claim_ownership, release_ownership, require_owner};
 use crate::routers::crates::{
-    crate_file_path, index_file_path, validate_crate_name, validate_version,
+    crate_file_key, index_file_key, lock_crate, metadata_file_key, readme_file_key,
+    validate_crate_name, validate_version,
 };
-use actix_web::{HttpResponse, Responder, put, web};
+use crate::routers::crates::{search, tarball};
+use crate::routers::{CRATES_STORE, quotas, replication};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, put, web};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -23,8 +30,30 @@ struct PublishMetadata {
     links: Option<String>,
     #[serde(default)]
     rust_version: Option<String>,
-    // Extra fields cargo may send, but we don't need to store in the index
-    // description, homepage, etc. – we simply ignore them here.
+    // Descriptive fields are kept out of the index and stored per version in
+    // `metadata.json` (see `crates::metadata`).
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    documentation: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+    #[serde(default)]
+    readme: Option<String>,
+    #[serde(default)]
+    readme_file: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    license_file: Option<String>,
+    #[serde(default)]
+    repository: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -114,13 +143,14 @@ pub struct PublishResponse {
         (status = 401,  description = "Authentication required"),
         (status = 403,  description = "Access denied"),
         (status = 409,  description = "Version already exists"),
+        (status = 413,  description = "Crate storage quota exceeded"),
         (status = 422,  description = "Validation error"),
         (status = 429,  description = "Too many requests"),
     ),
     security(("bearerAuth" = []))
 )]
 #[put("/new")]
-pub async fn handle(body: web::Bytes) -> impl Responder {
+pub async fn handle(req: HttpRequest, body: web::Bytes) -> impl Responder {
     // ------------------------------------------------------------------
     // 1. Parse the cargo binary wire format
     //    [ u32LE json_len ][ json bytes ][ u32LE crate_len ][ crate bytes ]
@@ -131,6 +161,7 @@ pub async fn handle(body: web::Bytes) -> impl Responder {
             return error_response(actix_web::http::StatusCode::BAD_REQUEST, &msg);
         }
     };
+    audit::annotate(&req, &meta.name, Some(&meta.vers), None);
 
     // ------------------------------------------------------------------
     // 2. Validate name & version
@@ -148,53 +179,107 @@ pub async fn handle(body: web::Bytes) -> impl Responder {
         );
     }
 
+    // The auth middleware cannot see the crate name inside the payload, so the
+    // publish scope is checked here.
+    let authorized = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.allows("crate", &meta.name.to_ascii_lowercase(), "publish"));
+    if !authorized {
+        return error_response(
+            actix_web::http::StatusCode::FORBIDDEN,
+            &format!("token is not authorized to publish crate `{}`", meta.name),
+        );
+    }
+
+    // Cargo builds the tarball, but any client can send one; check it before
+    // it is stored and served to others.
+    // Decompressing up to the unpacked size limit is CPU-bound; keep it off
+    // the async workers.
+    let archive = body.slice_ref(crate_bytes);
+    let (name, vers) = (meta.name.clone(), meta.vers.clone());
+    match web::block(move || tarball::validate(&archive, &name, &vers)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(msg)) => {
+            return error_response(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY, &msg);
+        }
+        Err(e) => {
+            tracing::error!("failed to validate crate tarball: {e}");
+            return error_response(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to validate crate tarball",
+            );
+        }
+    }
+
     // ------------------------------------------------------------------
     // 3. Reject if already published
+    //    The index is the source of truth: a tarball without an index
+    //    entry is debris from an interrupted publish and gets replaced.
     // ------------------------------------------------------------------
-    let Some(crate_path) = crate_file_path(&meta.name, &meta.vers) else {
+    let (Some(crate_key), Some(index_key)) = (
+        crate_file_key(&meta.name, &meta.vers),
+        index_file_key(&meta.name),
+    ) else {
         return error_response(
             actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
             "invalid crate name or version",
         );
     };
 
-    if tokio::fs::metadata(&crate_path).await.is_ok() {
+    // Held until the index entry and the owner are written, so concurrent
+    // publishes, yanks, unyanks and owner changes of this crate cannot lose
+    // each other's updates, and two first publishes cannot both pass the
+    // ownership check.
+    let guard = lock_crate(&meta.name).await;
+
+    let owner_key = meta.name.to_ascii_lowercase();
+    let publisher = match require_owner(&req, &owner_key).await {
+        Ok(login) => login,
+        Err(response) => return response,
+    };
+
+    let mut index = match CRATES_STORE.get(&index_key).await {
+        Ok(data) => data.unwrap_or_default(),
+        Err(_) => {
+            return error_response(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to read index file",
+            );
+        }
+    };
+
+    if index_has_version(&index, &meta.vers) {
         return error_response(
             actix_web::http::StatusCode::CONFLICT,
             "this version has already been published",
         );
     }
 
-    // ------------------------------------------------------------------
-    // 4. Persist .crate tarball
-    // ------------------------------------------------------------------
-    if let Some(parent) = crate_path.parent()
-        && tokio::fs::create_dir_all(parent).await.is_err()
-    {
-        return error_response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to create storage directory",
-        );
-    }
-
-    if tokio::fs::write(&crate_path, &crate_bytes).await.is_err() {
-        return error_response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to write crate file",
-        );
+    match quotas::check_crate(&meta.name, crate_bytes.len() as u64).await {
+        Ok(Ok(())) => {}
+        Ok(Err(exceeded)) => return exceeded.crates_response(),
+        Err(e) => {
+            tracing::error!("failed to compute storage usage for {}: {e}", meta.name);
+            return error_response(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to compute storage usage",
+            );
+        }
     }
 
     // ------------------------------------------------------------------
-    // 5. Compute SHA-256 checksum
+    // 4. Compute SHA-256 checksum
     // ------------------------------------------------------------------
     let cksum = {
         let mut hasher = Sha256::new();
         hasher.update(crate_bytes);
         format!("{:x}", hasher.finalize())
     };
+    audit::annotate(&req, &meta.name, Some(&meta.vers), Some(&cksum));
 
     // ------------------------------------------------------------------
-    // 6. Build index record
+    // 5. Build index record
     // ------------------------------------------------------------------
     let index_deps: Vec<IndexDep> = meta
         .deps
@@ -239,49 +324,90 @@ pub async fn handle(body: web::Bytes) -> impl Responder {
     };
 
     // ------------------------------------------------------------------
-    // 7. Append to sparse index file
+    // 6. Persist .crate tarball and metadata
     // ------------------------------------------------------------------
-    let Some(index_path) = index_file_path(&meta.name) else {
+    if CRATES_STORE
+        .put(&crate_key, crate_bytes.to_vec())
+        .await
+        .is_err()
+    {
         return error_response(
             actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to resolve index path",
+            "failed to write crate file",
         );
-    };
+    }
 
-    if let Some(parent) = index_path.parent()
-        && tokio::fs::create_dir_all(parent).await.is_err()
-    {
-        return error_response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to create index directory",
+    let metadata = CrateMetadata {
+        name: meta.name.clone(),
+        vers: meta.vers.clone(),
+        description: meta.description,
+        documentation: meta.documentation,
+        homepage: meta.homepage,
+        repository: meta.repository,
+        license: meta.license,
+        license_file: meta.license_file,
+        readme_file: meta.readme_file,
+        keywords: meta.keywords,
+        categories: meta.categories,
+        authors: meta.authors,
+        published_by: Some(publisher.clone()),
+        created_at: Some(chrono::Utc::now().to_rfc3339()),
+    };
+    if let Err(e) = save_metadata(&metadata, meta.readme.as_deref()).await {
+        tracing::error!(
+            "failed to store metadata for {}-{}: {e}",
+            meta.name,
+            meta.vers
         );
     }
 
-    use tokio::io::AsyncWriteExt;
-    match tokio::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&index_path)
-        .await
-    {
-        Ok(mut file) => {
-            if file.write_all(record_line.as_bytes()).await.is_err() {
-                return error_response(
-                    actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    "failed to write index entry",
-                );
-            }
-        }
-        Err(_) => {
+    // ------------------------------------------------------------------
+    // 7. The first publisher becomes the initial owner
+    // ------------------------------------------------------------------
+    let claimed = match claim_ownership(&owner_key, &publisher).await {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            tracing::error!("failed to record owner of {owner_key}: {e}");
+            rollback(&meta.name, &meta.vers).await;
             return error_response(
                 actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                "failed to open index file",
+                "failed to record crate owner",
             );
         }
+    };
+
+    // ------------------------------------------------------------------
+    // 8. Append to sparse index file (rewritten atomically); on failure
+    //    roll back the tarball (and a fresh owner) so the version can be
+    //    published again
+    // ------------------------------------------------------------------
+    if !index.is_empty() && !index.ends_with(b"\n") {
+        index.push(b'\n');
+    }
+    index.extend_from_slice(record_line.as_bytes());
+    if let Err(e) = CRATES_STORE.put(&index_key, index).await {
+        tracing::error!(
+            "failed to write index entry for {}-{}: {e}",
+            meta.name,
+            meta.vers
+        );
+        rollback(&meta.name, &meta.vers).await;
+        if claimed && let Err(e) = release_ownership(&owner_key).await {
+            tracing::error!("failed to roll back owner of {owner_key}: {e}");
+        }
+        return error_response(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to write index entry",
+        );
     }
 
+    search::index::refresh(&meta.name).await;
+    drop(guard);
+    webhooks::crate_event(&req, "publish", &meta.name, &meta.vers).await;
+    replication::crate_changed(&req, &meta.name, &meta.vers).await;
+
     // ------------------------------------------------------------------
-    // 8. Respond
+    // 9. Respond
     // ------------------------------------------------------------------
     HttpResponse::Ok().json(PublishResponse {
         warnings: PublishWarnings {
@@ -321,6 +447,35 @@ fn parse_publish_body(body: &[u8]) -> Result<(PublishMetadata, &[u8]), String> {
     Ok((meta, crate_bytes))
 }
 
+/// Returns `true` if the index file already has an entry for `version`.
+fn index_has_version(index: &[u8], version: &str) -> bool {
+    #[derive(Deserialize)]
+    struct Entry {
+        vers: String,
+    }
+
+    String::from_utf8_lossy(index)
+        .lines()
+        .filter_map(|l| serde_json::from_str::<Entry>(l.trim()).ok())
+        .any(|e| e.vers == version)
+}
+
+/// Removes everything a failed publish stored for `name`-`version`.
+async fn rollback(name: &str, version: &str) {
+    for key in [
+        crate_file_key(name, version),
+        metadata_file_key(name, version),
+        readme_file_key(name, version),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if let Err(e) = CRATES_STORE.delete(&key).await {
+            tracing::error!("failed to roll back {key}: {e}");
+        }
+    }
+}
+
 fn error_response(status: actix_web::http::StatusCode, detail: &str) -> HttpResponse {
     HttpResponse::build(status).json(serde_json::json!({
         "errors": [{ "detail": detail }]
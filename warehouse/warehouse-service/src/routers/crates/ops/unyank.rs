@@ -1,5 +1,9 @@
-use crate::routers::crates::{crate_file_path, validate_crate_name, validate_version};
-use actix_web::{HttpResponse, Responder, put, web};
+use crate::domain::webhooks;
+use crate::routers::crates::owners::require_owner;
+use crate::routers::crates::search;
+use crate::routers::crates::{crate_file_key, validate_crate_name, validate_version};
+use crate::routers::{CRATES_STORE, replication};
+use actix_web::{HttpRequest, HttpResponse, Responder, put, web};
 use serde::Serialize;
 use utoipa::ToSchema;
 
@@ -27,23 +31,32 @@ pub struct OkResponse {
     security(("bearerAuth" = []))
 )]
 #[put("/{name}/{version}/unyank")]
-pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
     let (name, version) = path.into_inner();
 
     if !validate_crate_name(&name) || !validate_version(&version) {
         return not_found();
     }
 
-    // Verify the crate tarball exists on disk
-    let Some(crate_path) = crate_file_path(&name, &version) else {
+    // Verify the crate tarball exists
+    let Some(crate_key) = crate_file_key(&name, &version) else {
         return not_found();
     };
-    if tokio::fs::metadata(&crate_path).await.is_err() {
+    if !matches!(CRATES_STORE.size(&crate_key).await, Ok(Some(_))) {
         return not_found();
     }
 
+    if let Err(response) = require_owner(&req, &name.to_ascii_lowercase()).await {
+        return response;
+    }
+
     match super::yank::set_yanked(&name, &version, false).await {
-        Ok(true) => HttpResponse::Ok().json(OkResponse { ok: true }),
+        Ok(true) => {
+            search::index::refresh(&name).await;
+            webhooks::crate_event(&req, "unyank", &name, &version).await;
+            replication::crate_changed(&req, &name, &version).await;
+            HttpResponse::Ok().json(OkResponse { ok: true })
+        }
         Ok(false) => not_found(),
         Err(msg) => HttpResponse::InternalServerError().json(serde_json::json!({
             "errors": [{ "detail": msg }]
@@ -1,7 +1,11 @@
+use crate::domain::webhooks;
+use crate::routers::crates::owners::require_owner;
+use crate::routers::crates::search;
 use crate::routers::crates::{
-    crate_file_path, index_file_path, validate_crate_name, validate_version,
+    crate_file_key, index_file_key, lock_crate, validate_crate_name, validate_version,
 };
-use actix_web::{HttpResponse, Responder, delete, web};
+use crate::routers::{CRATES_STORE, replication};
+use actix_web::{HttpRequest, HttpResponse, Responder, delete, web};
 use serde::Serialize;
 use utoipa::ToSchema;
 
@@ -29,7 +33,7 @@ pub struct OkResponse {
     security(("bearerAuth" = []))
 )]
 #[delete("/{name}/{version}/yank")]
-pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
     let (name, version) = path.into_inner();
 
     if !validate_crate_name(&name) || !validate_version(&version) {
@@ -37,15 +41,24 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
     }
 
     // Verify the crate file actually exists
-    let Some(crate_path) = crate_file_path(&name, &version) else {
+    let Some(crate_key) = crate_file_key(&name, &version) else {
         return not_found();
     };
-    if tokio::fs::metadata(&crate_path).await.is_err() {
+    if !matches!(CRATES_STORE.size(&crate_key).await, Ok(Some(_))) {
         return not_found();
     }
 
+    if let Err(response) = require_owner(&req, &name.to_ascii_lowercase()).await {
+        return response;
+    }
+
     match set_yanked(&name, &version, true).await {
-        Ok(true) => HttpResponse::Ok().json(OkResponse { ok: true }),
+        Ok(true) => {
+            search::index::refresh(&name).await;
+            webhooks::crate_event(&req, "yank", &name, &version).await;
+            replication::crate_changed(&req, &name, &version).await;
+            HttpResponse::Ok().json(OkResponse { ok: true })
+        }
         Ok(false) => not_found(),
         Err(msg) => HttpResponse::InternalServerError().json(serde_json::json!({
             "errors": [{ "detail": msg }]
@@ -58,16 +71,20 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
 // ---------------------------------------------------------------------------
 
 /// Rewrites the index file so that the entry for `version` has `yanked` set to
-/// `yanked_value`.  Returns `Ok(true)` when the version was found and updated,
+/// `yanked_value`.  The rewrite replaces the file atomically and runs under the
+/// same per-crate lock as publish.  Returns `Ok(true)` when the version was found and updated,
 /// `Ok(false)` when not found, or `Err(String)` on I/O failures.
 pub async fn set_yanked(name: &str, version: &str, yanked_value: bool) -> Result<bool, String> {
-    let Some(index_path) = index_file_path(name) else {
+    let Some(index_key) = index_file_key(name) else {
         return Err("failed to resolve index path".into());
     };
 
-    let content = match tokio::fs::read_to_string(&index_path).await {
-        Ok(s) => s,
-        Err(_) => return Ok(false), // index file doesn't exist → version not found
+    let _guard = lock_crate(name).await;
+
+    let content = match CRATES_STORE.get(&index_key).await {
+        Ok(Some(data)) => String::from_utf8_lossy(&data).into_owned(),
+        Ok(None) => return Ok(false), // index file doesn't exist → version not found
+        Err(e) => return Err(format!("failed to read index file: {e}")),
     };
 
     let mut found = false;
@@ -103,7 +120,8 @@ pub async fn set_yanked(name: &str, version: &str, yanked_value: bool) -> Result
     }
 
     let new_content = new_lines.join("\n") + "\n";
-    tokio::fs::write(&index_path, new_content.as_bytes())
+    CRATES_STORE
+        .put(&index_key, new_content.into_bytes())
         .await
         .map_err(|e| format!("failed to write index file: {e}"))?;
 
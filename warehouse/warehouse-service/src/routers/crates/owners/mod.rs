@@ -1,45 +1,123 @@
-use crate::routers::crates::{CRATES_STORAGE_ROOT, validate_crate_name};
-use actix_web::{HttpResponse, Responder, delete, get, put, web};
+use crate::domain::auth_store::AUTH_STORE;
+use crate::domain::crates_error;
+use crate::domain::jwt::{Claims, JwtConfig};
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::{index_file_key, lock_crate, validate_crate_name};
+use actix_web::{
+    HttpMessage, HttpRequest, HttpResponse, Responder, delete, get, http::StatusCode, put, web,
+};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 use utoipa::ToSchema;
 
 // ---------------------------------------------------------------------------
 // Storage helper
 // ---------------------------------------------------------------------------
 
-/// On-disk path for a crate's owners file.
+/// Store key of a crate's owners file.
 ///
-/// Layout: `<root>/<n>/owners.json`
-fn owners_path(name: &str) -> PathBuf {
-    PathBuf::from(CRATES_STORAGE_ROOT.as_str())
-        .join(name)
-        .join("owners.json")
+/// Layout: `<n>/owners.json`
+fn owners_key(name: &str) -> String {
+    format!("{name}/owners.json")
 }
 
-async fn load_owners(name: &str) -> Option<Vec<Owner>> {
-    let path = owners_path(name);
-    let data = tokio::fs::read(&path).await.ok()?;
-    serde_json::from_slice(&data).ok()
+/// Reads the owners of crate `name`; `None` when the crate has no owners file.
+/// Storage failures and a corrupt file are errors, so ownership checks never
+/// mistake them for an unowned crate.
+pub(in crate::routers) async fn load_owners(name: &str) -> std::io::Result<Option<Vec<Owner>>> {
+    let Some(data) = CRATES_STORE.get(&owners_key(name)).await? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&data)
+        .map(Some)
+        .map_err(std::io::Error::other)
 }
 
 async fn save_owners(name: &str, owners: &[Owner]) -> std::io::Result<()> {
-    let path = owners_path(name);
-    // Ensure the crate directory exists
-    if let Some(parent) = path.parent() {
-        tokio::fs::create_dir_all(parent).await?;
-    }
     let data = serde_json::to_vec_pretty(owners).map_err(std::io::Error::other)?;
-    tokio::fs::write(&path, data).await
+    CRATES_STORE.put(&owners_key(name), data).await
 }
 
-/// Returns `true` if the crate directory exists (i.e. the crate has been published).
+/// Returns `true` if the crate has an index file (i.e. has been published).
 async fn crate_exists(name: &str) -> bool {
-    let path = PathBuf::from(CRATES_STORAGE_ROOT.as_str()).join(name);
-    tokio::fs::metadata(&path)
-        .await
-        .map(|m| m.is_dir())
-        .unwrap_or(false)
+    let Some(key) = index_file_key(name) else {
+        return false;
+    };
+    matches!(CRATES_STORE.size(&key).await, Ok(Some(_)))
+}
+
+// ---------------------------------------------------------------------------
+// Ownership checks (publish, yank, unyank and owner changes)
+// ---------------------------------------------------------------------------
+
+/// Ensures the authenticated caller may modify crate `name` and returns their
+/// login. Callers must be listed in `owners.json` or be a registry admin.
+/// Crates without recorded owners (published before ownership was enforced)
+/// are open to any caller holding the matching scope; an unreadable owners
+/// file is a 500, never an open crate.
+pub(super) async fn require_owner(req: &HttpRequest, name: &str) -> Result<String, HttpResponse> {
+    let Some(login) = req.extensions().get::<Claims>().map(|c| c.sub.clone()) else {
+        return Err(crates_error::response(
+            StatusCode::UNAUTHORIZED,
+            "authentication required",
+        ));
+    };
+
+    let owners = match load_owners(name).await {
+        Ok(owners) => owners.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("failed to read owners of {name}: {e}");
+            return Err(crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to read crate owners",
+            ));
+        }
+    };
+    let is_owner = owners.iter().any(|o| o.login.eq_ignore_ascii_case(&login));
+    let is_admin = req
+        .app_data::<web::Data<JwtConfig>>()
+        .is_some_and(|config| AUTH_STORE.is_admin(config, &login));
+
+    if owners.is_empty() || is_owner || is_admin {
+        return Ok(login);
+    }
+
+    Err(crates_error::response(
+        StatusCode::FORBIDDEN,
+        format!("crate `{name}` exists but `{login}` is not one of its owners"),
+    ))
+}
+
+/// Records `login` as the initial owner of a crate that has no owners yet.
+/// Returns whether an owner was recorded. Callers hold the crate lock.
+pub(super) async fn claim_ownership(name: &str, login: &str) -> std::io::Result<bool> {
+    if load_owners(name).await?.is_some_and(|o| !o.is_empty()) {
+        return Ok(false);
+    }
+
+    save_owners(
+        name,
+        &[Owner {
+            id: 1,
+            login: login.to_string(),
+            name: None,
+        }],
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Undoes [`claim_ownership`] when the first publish of a crate fails.
+pub(super) async fn release_ownership(name: &str) -> std::io::Result<()> {
+    CRATES_STORE.delete(&owners_key(name)).await
+}
+
+/// Records `owners` of a crate replicated from another instance, unless the
+/// crate already has owners here.
+pub(in crate::routers) async fn adopt_owners(name: &str, owners: &[Owner]) -> std::io::Result<()> {
+    if owners.is_empty() || load_owners(name).await?.is_some_and(|o| !o.is_empty()) {
+        return Ok(());
+    }
+    save_owners(name, owners).await
 }
 
 // ---------------------------------------------------------------------------
@@ -90,6 +168,7 @@ pub struct OkResponse {
         (status = 200, description = "Owner list", body = OwnersResponse, content_type = "application/json"),
         (status = 401, description = "Authentication required"),
         (status = 404, description = "Crate not found"),
+        (status = 500, description = "Failed to read crate owners"),
     ),
     security(("bearerAuth" = []))
 )]
@@ -104,8 +183,12 @@ pub async fn list(path: web::Path<String>) -> impl Responder {
         return not_found();
     }
 
-    let owners = load_owners(&name).await.unwrap_or_default();
-    HttpResponse::Ok().json(OwnersResponse { users: owners })
+    match load_owners(&name).await {
+        Ok(owners) => HttpResponse::Ok().json(OwnersResponse {
+            users: owners.unwrap_or_default(),
+        }),
+        Err(e) => owners_error(&name, e),
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -131,11 +214,16 @@ pub async fn list(path: web::Path<String>) -> impl Responder {
         (status = 401, description = "Authentication required"),
         (status = 403, description = "Access denied"),
         (status = 404, description = "Crate not found"),
+        (status = 500, description = "Failed to read crate owners"),
     ),
     security(("bearerAuth" = []))
 )]
 #[put("/{name}/owners")]
-pub async fn add(path: web::Path<String>, body: web::Json<OwnersRequest>) -> impl Responder {
+pub async fn add(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<OwnersRequest>,
+) -> impl Responder {
     let name = path.into_inner().to_ascii_lowercase();
 
     if !validate_crate_name(&name) {
@@ -144,13 +232,21 @@ pub async fn add(path: web::Path<String>, body: web::Json<OwnersRequest>) -> imp
     if !crate_exists(&name).await {
         return not_found();
     }
+    // Serialises owner changes with publishes and each other.
+    let _guard = lock_crate(&name).await;
+    if let Err(response) = require_owner(&req, &name).await {
+        return response;
+    }
     if body.users.is_empty() {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "errors": [{ "detail": "users list must not be empty" }]
         }));
     }
 
-    let mut owners = load_owners(&name).await.unwrap_or_default();
+    let mut owners = match load_owners(&name).await {
+        Ok(owners) => owners.unwrap_or_default(),
+        Err(e) => return owners_error(&name, e),
+    };
 
     // Assign IDs sequentially based on current max; keeps IDs stable for
     // existing entries.
@@ -205,11 +301,16 @@ pub async fn add(path: web::Path<String>, body: web::Json<OwnersRequest>) -> imp
         (status = 401, description = "Authentication required"),
         (status = 403, description = "Access denied"),
         (status = 404, description = "Crate not found"),
+        (status = 500, description = "Failed to read crate owners"),
     ),
     security(("bearerAuth" = []))
 )]
 #[delete("/{name}/owners")]
-pub async fn remove(path: web::Path<String>, body: web::Json<OwnersRequest>) -> impl Responder {
+pub async fn remove(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<OwnersRequest>,
+) -> impl Responder {
     let name = path.into_inner().to_ascii_lowercase();
 
     if !validate_crate_name(&name) {
@@ -218,13 +319,21 @@ pub async fn remove(path: web::Path<String>, body: web::Json<OwnersRequest>) ->
     if !crate_exists(&name).await {
         return not_found();
     }
+    // Serialises owner changes with publishes and each other.
+    let _guard = lock_crate(&name).await;
+    if let Err(response) = require_owner(&req, &name).await {
+        return response;
+    }
     if body.users.is_empty() {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "errors": [{ "detail": "users list must not be empty" }]
         }));
     }
 
-    let mut owners = load_owners(&name).await.unwrap_or_default();
+    let mut owners = match load_owners(&name).await {
+        Ok(owners) => owners.unwrap_or_default(),
+        Err(e) => return owners_error(&name, e),
+    };
 
     let remove_set: std::collections::HashSet<String> = body
         .users
@@ -234,6 +343,12 @@ pub async fn remove(path: web::Path<String>, body: web::Json<OwnersRequest>) ->
 
     owners.retain(|o| !remove_set.contains(&o.login.to_ascii_lowercase()));
 
+    if owners.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "errors": [{ "detail": "cannot remove all owners of a crate" }]
+        }));
+    }
+
     if let Err(e) = save_owners(&name, &owners).await {
         return HttpResponse::InternalServerError().json(serde_json::json!({
             "errors": [{ "detail": format!("failed to save owners: {e}") }]
@@ -247,6 +362,13 @@ pub async fn remove(path: web::Path<String>, body: web::Json<OwnersRequest>) ->
 // Helpers
 // ---------------------------------------------------------------------------
 
+fn owners_error(name: &str, e: std::io::Error) -> HttpResponse {
+    tracing::error!("failed to read owners of {name}: {e}");
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "errors": [{ "detail": "failed to read crate owners" }]
+    }))
+}
+
 fn not_found() -> HttpResponse {
     HttpResponse::NotFound().json(serde_json::json!({
         "errors": [{ "detail": "crate not found" }]
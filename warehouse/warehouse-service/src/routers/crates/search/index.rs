@@ -0,0 +1,204 @@
+//! In-process search index over crate name, description and keywords.
+//!
+//! The index is built from the sparse index and per-version `metadata.json`
+//! files at startup, then kept current by publish,
+//! yank, unyank and crates GC calling [`refresh`] / [`rebuild`].
+
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::metadata::load_metadata;
+use crate::routers::crates::search::compare_versions;
+use crate::routers::crates::{index_file_key, validate_crate_name};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+static SEARCH_INDEX: LazyLock<RwLock<HashMap<String, SearchEntry>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// A searchable crate: the highest non-yanked version and its metadata.
+#[derive(Debug, Clone)]
+pub(super) struct SearchEntry {
+    pub(super) name: String,
+    pub(super) max_version: String,
+    pub(super) description: Option<String>,
+    name_lower: String,
+    keywords: Vec<String>,
+    description_terms: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct IndexLine {
+    vers: String,
+    #[serde(default)]
+    yanked: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Maintenance
+// ---------------------------------------------------------------------------
+
+/// Builds the index at startup so searches see every stored crate.
+pub async fn init() {
+    rebuild().await;
+}
+
+/// Re-reads one crate from storage. Crates whose versions are all yanked (or
+/// that no longer exist) are dropped from the index.
+pub(in crate::routers) async fn refresh(name: &str) {
+    let entry = load_entry(name).await;
+    let Ok(mut index) = SEARCH_INDEX.write() else {
+        return;
+    };
+
+    match entry {
+        Some(entry) => index.insert(name.to_string(), entry),
+        None => index.remove(name),
+    };
+}
+
+/// Rebuilds the whole index from storage.
+pub(in crate::routers) async fn rebuild() {
+    let entries = build_all().await;
+    if let Ok(mut index) = SEARCH_INDEX.write() {
+        *index = entries;
+    }
+}
+
+async fn build_all() -> HashMap<String, SearchEntry> {
+    let names = CRATES_STORE
+        .list("index/")
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|key| key.rsplit_once('/').map(|(_, name)| name.to_string()))
+        .filter(|name| validate_crate_name(name));
+
+    let mut entries = HashMap::new();
+    for name in names {
+        if let Some(entry) = load_entry(&name).await {
+            entries.insert(name, entry);
+        }
+    }
+    entries
+}
+
+async fn load_entry(name: &str) -> Option<SearchEntry> {
+    let data = CRATES_STORE.get(&index_file_key(name)?).await.ok()??;
+    let content = String::from_utf8_lossy(&data);
+    let max_version = content
+        .lines()
+        .filter_map(|l| serde_json::from_str::<IndexLine>(l.trim()).ok())
+        .filter(|l| !l.yanked)
+        .map(|l| l.vers)
+        .max_by(|a, b| compare_versions(a, b))?;
+
+    let metadata = load_metadata(name, &max_version).await.unwrap_or_default();
+    let description_terms = metadata
+        .description
+        .as_deref()
+        .map(terms)
+        .unwrap_or_default();
+
+    Some(SearchEntry {
+        name: name.to_string(),
+        max_version,
+        description: metadata.description,
+        name_lower: name.to_ascii_lowercase(),
+        keywords: metadata
+            .keywords
+            .iter()
+            .map(|k| k.to_ascii_lowercase())
+            .collect(),
+        description_terms,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Querying
+// ---------------------------------------------------------------------------
+
+/// Returns entries matching every term of `query`, best match first.
+///
+/// Scoring, per crate:
+/// - whole query equals the name: +100, is a name prefix: +50, is contained
+///   in the name: +25
+/// - per term: exact keyword +15 (keyword prefix +8), name segment +10
+///   (name substring +6), description word +4 (description word prefix +2)
+///
+/// A term that matches none of name, keywords or description excludes the
+/// crate. Ties are broken by name.
+pub(super) fn search(query: &str) -> Vec<SearchEntry> {
+    let query = query.trim().to_ascii_lowercase();
+    let query_terms = terms(&query);
+    if query_terms.is_empty() {
+        return Vec::new();
+    }
+
+    let Ok(index) = SEARCH_INDEX.read() else {
+        return Vec::new();
+    };
+
+    let mut scored: Vec<(u32, &SearchEntry)> = index
+        .values()
+        .filter_map(|entry| score(entry, &query, &query_terms).map(|s| (s, entry)))
+        .collect();
+
+    scored.sort_by(|(sa, a), (sb, b)| sb.cmp(sa).then_with(|| a.name.cmp(&b.name)));
+    scored.into_iter().map(|(_, e)| e.clone()).collect()
+}
+
+fn score(entry: &SearchEntry, query: &str, query_terms: &[String]) -> Option<u32> {
+    let name = entry.name_lower.as_str();
+    let mut total = if name == query {
+        100
+    } else if name.starts_with(query) {
+        50
+    } else if name.contains(query) {
+        25
+    } else {
+        0
+    };
+
+    let name_segments = terms(name);
+
+    for term in query_terms {
+        let mut term_score = 0;
+
+        if entry.keywords.iter().any(|k| k == term) {
+            term_score += 15;
+        } else if entry.keywords.iter().any(|k| k.starts_with(term.as_str())) {
+            term_score += 8;
+        }
+
+        if name_segments.iter().any(|s| s == term) {
+            term_score += 10;
+        } else if name.contains(term.as_str()) {
+            term_score += 6;
+        }
+
+        if entry.description_terms.iter().any(|w| w == term) {
+            term_score += 4;
+        } else if entry
+            .description_terms
+            .iter()
+            .any(|w| w.starts_with(term.as_str()))
+        {
+            term_score += 2;
+        }
+
+        if term_score == 0 {
+            return None;
+        }
+        total += term_score;
+    }
+
+    Some(total)
+}
+
+/// Lower-cased alphanumeric words; `-` and `_` separate name segments.
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
@@ -1,15 +1,16 @@
-use crate::routers::crates::CRATES_STORAGE_ROOT;
 use actix_web::{HttpResponse, Responder, get, web};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+pub mod index;
+
 // ---------------------------------------------------------------------------
 // Query parameters
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Deserialize)]
 pub struct SearchQuery {
-    /// Search query string (matches against crate name)
+    /// Search query string (matches crate name, description and keywords)
     q: String,
     /// Results per page (1–100, default 10)
     #[serde(default = "default_per_page")]
@@ -81,41 +82,14 @@ pub async fn handle(query: web::Query<SearchQuery>) -> impl Responder {
     let per_page = query.per_page.clamp(1, 100);
     let page = query.page.max(1);
 
-    // Collect all matching crates by scanning the storage directory.
-    // Each crate lives at <root>/<name>/ ; we match names containing `q`.
-    let crate_root = std::path::PathBuf::from(CRATES_STORAGE_ROOT.as_str());
-    let mut matches: Vec<SearchCrate> = Vec::new();
-
-    if let Ok(mut entries) = tokio::fs::read_dir(&crate_root).await {
-        while let Ok(Some(entry)) = entries.next_entry().await {
-            let file_name = entry.file_name();
-            let name = file_name.to_string_lossy().to_ascii_lowercase();
-
-            // Skip the index directory
-            if name == "index" {
-                continue;
-            }
-
-            if !name.contains(q.as_str()) {
-                continue;
-            }
-
-            // The max version is the lexicographically greatest version
-            // sub-directory present — good enough for a private registry.
-            let max_version = find_max_version(&entry.path()).await;
-
-            if let Some(version) = max_version {
-                matches.push(SearchCrate {
-                    name: name.clone(),
-                    max_version: version,
-                    description: None, // We don't store description separately
-                });
-            }
-        }
-    }
-
-    // Sort alphabetically for stable results
-    matches.sort_by(|a, b| a.name.cmp(&b.name));
+    let matches: Vec<SearchCrate> = index::search(&q)
+        .into_iter()
+        .map(|entry| SearchCrate {
+            name: entry.name,
+            max_version: entry.max_version,
+            description: entry.description,
+        })
+        .collect();
 
     let total = matches.len();
     let offset = (page - 1) * per_page;
@@ -128,38 +102,23 @@ pub async fn handle(query: web::Query<SearchQuery>) -> impl Responder {
 }
 
 // ---------------------------------------------------------------------------
-// Helper: find the highest version sub-directory under a crate directory
+// Version ordering
 // ---------------------------------------------------------------------------
 
-async fn find_max_version(crate_dir: &std::path::Path) -> Option<String> {
-    let mut versions: Vec<String> = Vec::new();
-
-    let mut entries = tokio::fs::read_dir(crate_dir).await.ok()?;
-    while let Ok(Some(entry)) = entries.next_entry().await {
-        let meta = entry.metadata().await;
-        if meta.map(|m| m.is_dir()).unwrap_or(false) {
-            versions.push(entry.file_name().to_string_lossy().into_owned());
-        }
-    }
-
-    if versions.is_empty() {
-        return None;
-    }
-
-    // Use semver parsing when available; fall back to lexicographic order.
-    versions.sort_by(|a, b| compare_versions(a, b));
-    versions.into_iter().last()
-}
-
 /// Compares two version strings using semver semantics when both parse
 /// successfully, otherwise falls back to lexicographic comparison.
-fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+pub(crate) fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
     match (parse_semver(a), parse_semver(b)) {
         (Some(av), Some(bv)) => av.cmp(&bv),
         _ => a.cmp(b),
     }
 }
 
+/// Whether `v` is a `major.minor.patch[-pre][+build]` version.
+pub(crate) fn is_semver(v: &str) -> bool {
+    parse_semver(v).is_some()
+}
+
 /// Parses a `major.minor.patch[-pre][+build]` string into a comparable tuple.
 /// Returns `None` for anything that doesn't fit the pattern.
 fn parse_semver(v: &str) -> Option<(u64, u64, u64, String)> {
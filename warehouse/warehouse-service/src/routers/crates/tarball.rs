@@ -0,0 +1,287 @@
+//! Validation of published `.crate` tarballs.
+//!
+//! A `.crate` file is a gzipped tarball whose entries all live under
+//! `<name>-<version>/`, holding at least the normalized
+//! `<name>-<version>/Cargo.toml` that `cargo package` writes.
+//!
+//! Configuration:
+//! - `CRATES_MAX_UNPACKED_BYTES` (default 536870912, 512 MiB): upper bound
+//!   for the total size of the unpacked files
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+
+static MAX_UNPACKED_BYTES: LazyLock<u64> = LazyLock::new(|| {
+    envmnt::get_or("CRATES_MAX_UNPACKED_BYTES", "536870912")
+        .parse()
+        .unwrap_or(512 * 1024 * 1024)
+});
+
+/// Upper bound for `Cargo.toml`; normalized manifests are a few kilobytes.
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+/// Checks that `archive` is a well-formed `.crate` for `name` `version`.
+/// Errors are human-readable and returned to cargo as-is.
+pub(super) fn validate(archive: &[u8], name: &str, version: &str) -> Result<(), String> {
+    validate_with_limit(archive, name, version, *MAX_UNPACKED_BYTES)
+}
+
+fn validate_with_limit(
+    archive: &[u8],
+    name: &str,
+    version: &str,
+    max_unpacked: u64,
+) -> Result<(), String> {
+    let top = format!("{name}-{version}");
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    let entries = tar
+        .entries()
+        .map_err(|e| format!("invalid crate tarball: {e}"))?;
+
+    let mut unpacked: u64 = 0;
+    let mut manifest: Option<Vec<u8>> = None;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("invalid crate tarball: {e}"))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("invalid crate tarball: {e}"))?
+            .into_owned();
+
+        // Entry paths must be plain relative paths; `..` is never needed.
+        if !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "invalid crate tarball: entry `{}` escapes the package directory",
+                path.display()
+            ));
+        }
+        let parts = normalize(&path).unwrap_or_default();
+        if parts.first() != Some(&top) {
+            return Err(format!(
+                "invalid crate tarball: entry `{}` is outside the `{top}/` directory",
+                path.display()
+            ));
+        }
+
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let target = entry
+                .link_name()
+                .map_err(|e| format!("invalid crate tarball: {e}"))?;
+            // Link targets resolve relative to the entry's directory.
+            let escapes = target.as_deref().is_none_or(|target| {
+                let resolved = path.parent().unwrap_or(Path::new("")).join(target);
+                normalize(&resolved).is_none_or(|parts| parts.first() != Some(&top))
+            });
+            if escapes {
+                return Err(format!(
+                    "invalid crate tarball: link `{}` points outside the package directory",
+                    path.display()
+                ));
+            }
+        }
+
+        unpacked = unpacked.saturating_add(entry.header().size().unwrap_or(0));
+        if unpacked > max_unpacked {
+            return Err(format!(
+                "crate tarball unpacks to more than the allowed {max_unpacked} bytes"
+            ));
+        }
+
+        let is_manifest = parts.len() == 2 && parts[1] == "Cargo.toml";
+        if is_manifest && entry_type.is_file() {
+            let mut data = Vec::new();
+            (&mut entry)
+                .take(MAX_MANIFEST_BYTES + 1)
+                .read_to_end(&mut data)
+                .map_err(|e| format!("invalid crate tarball: {e}"))?;
+            if data.len() as u64 > MAX_MANIFEST_BYTES {
+                return Err("invalid crate tarball: Cargo.toml is too large".to_string());
+            }
+            manifest = Some(data);
+        }
+    }
+
+    let Some(data) = manifest else {
+        return Err(format!(
+            "invalid crate tarball: `{top}/Cargo.toml` is missing"
+        ));
+    };
+    let manifest: Manifest = std::str::from_utf8(&data)
+        .map_err(|e| e.to_string())
+        .and_then(|s| toml::from_str(s).map_err(|e| e.to_string()))
+        .map_err(|e| format!("invalid Cargo.toml in crate tarball: {e}"))?;
+    let package = manifest
+        .package
+        .ok_or("invalid Cargo.toml in crate tarball: missing `[package]`")?;
+
+    if package.name.as_deref() != Some(name) {
+        return Err(format!(
+            "Cargo.toml package name `{}` does not match the published name `{name}`",
+            package.name.unwrap_or_default()
+        ));
+    }
+    if package.version.as_deref() != Some(version) {
+        return Err(format!(
+            "Cargo.toml package version `{}` does not match the published version `{version}`",
+            package.version.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves `.` and `..` in a relative path. `None` for absolute paths and
+/// paths that climb above their root.
+fn normalize(path: &Path) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_with_limit;
+    use flate2::{Compression, write::GzEncoder};
+    use tar::{EntryType, Header};
+
+    const LIMIT: u64 = 1024 * 1024;
+
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+    }
+
+    /// Builds a gzipped tarball. Paths are written into the header as-is,
+    /// since `tar::Builder` refuses the malicious ones under test.
+    fn archive(entries: &[Entry]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for entry in entries {
+            let mut header = Header::new_gnu();
+            let (path, data): (&str, &[u8]) = match entry {
+                Entry::File(path, data) => {
+                    header.set_entry_type(EntryType::Regular);
+                    (path, data)
+                }
+                Entry::Symlink(path, target) => {
+                    header.set_entry_type(EntryType::Symlink);
+                    header.set_link_name(target).expect("link name should fit");
+                    (path, &[])
+                }
+            };
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, data).expect("entry should append");
+        }
+        builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .expect("archive should build")
+    }
+
+    fn manifest(name: &str, version: &str) -> String {
+        format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n")
+    }
+
+    #[test]
+    fn well_formed_crate_is_accepted() {
+        let toml = manifest("demo", "0.1.0");
+        let data = archive(&[
+            Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes()),
+            Entry::File("demo-0.1.0/src/lib.rs", b"pub fn f() {}\n"),
+            Entry::Symlink("demo-0.1.0/src/alias.rs", "lib.rs"),
+        ]);
+        assert_eq!(validate_with_limit(&data, "demo", "0.1.0", LIMIT), Ok(()));
+    }
+
+    #[test]
+    fn parent_dir_entries_are_rejected() {
+        let toml = manifest("demo", "0.1.0");
+        let data = archive(&[
+            Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes()),
+            Entry::File("demo-0.1.0/../../etc/cron.d/x", b"x"),
+        ]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+        assert!(err.contains("escapes the package directory"), "{err}");
+    }
+
+    #[test]
+    fn links_escaping_the_package_are_rejected() {
+        let toml = manifest("demo", "0.1.0");
+        for target in ["../../etc/passwd", "/etc/passwd", "../../other-0.1.0/src"] {
+            let data = archive(&[
+                Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes()),
+                Entry::Symlink("demo-0.1.0/src/evil.rs", target),
+            ]);
+            let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+            assert!(err.contains("points outside"), "{target}: {err}");
+        }
+    }
+
+    #[test]
+    fn wrong_top_directory_is_rejected() {
+        let toml = manifest("demo", "0.1.0");
+        let data = archive(&[Entry::File("demo-0.2.0/Cargo.toml", toml.as_bytes())]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+        assert!(err.contains("outside the `demo-0.1.0/` directory"), "{err}");
+    }
+
+    #[test]
+    fn unpacked_size_is_limited() {
+        let toml = manifest("demo", "0.1.0");
+        let padding = vec![0u8; 4096];
+        let data = archive(&[
+            Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes()),
+            Entry::File("demo-0.1.0/data.bin", &padding),
+        ]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", 4096).unwrap_err();
+        assert!(err.contains("more than the allowed 4096 bytes"), "{err}");
+        assert_eq!(validate_with_limit(&data, "demo", "0.1.0", 8192), Ok(()));
+    }
+
+    #[test]
+    fn manifest_must_match_published_name_and_version() {
+        let toml = manifest("other", "0.1.0");
+        let data = archive(&[Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes())]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+        assert!(err.contains("package name `other`"), "{err}");
+
+        let toml = manifest("demo", "0.9.9");
+        let data = archive(&[Entry::File("demo-0.1.0/Cargo.toml", toml.as_bytes())]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+        assert!(err.contains("package version `0.9.9`"), "{err}");
+    }
+
+    #[test]
+    fn missing_manifest_is_rejected() {
+        let data = archive(&[Entry::File("demo-0.1.0/src/lib.rs", b"")]);
+        let err = validate_with_limit(&data, "demo", "0.1.0", LIMIT).unwrap_err();
+        assert!(err.contains("`demo-0.1.0/Cargo.toml` is missing"), "{err}");
+    }
+}
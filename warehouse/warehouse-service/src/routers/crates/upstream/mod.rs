@@ -0,0 +1,489 @@
+//! Optional pull-through cache for an upstream sparse registry (crates.io).
+//!
+//! Enabled by setting `CRATES_UPSTREAM_URL` to the upstream sparse index,
+//! e.g. `https://index.crates.io`. Crates that do not exist locally are then
+//! resolved upstream: index files are cached for
+//! `CRATES_UPSTREAM_INDEX_TTL_SECONDS` (default 300) and served stale when
+//! the upstream is unreachable; `.crate` files are downloaded through the
+//! upstream `config.json` `dl` template, verified against the index `cksum`
+//! and cached permanently.
+//!
+//! Cache keys in the crates store (the leading `.` keeps them clear of crate
+//! names):
+//! - `.upstream/index/<prefix>/<n>`
+//! - `.upstream/crates/<n>/<version>/<n>-<version>.crate`
+
+use crate::routers::CRATES_STORE;
+use crate::routers::crates::{index_prefix, validate_crate_name, validate_version};
+use crate::storage::ObjectStore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+struct UpstreamConfig {
+    index_url: String,
+    index_ttl: Duration,
+}
+
+static UPSTREAM: LazyLock<Option<UpstreamConfig>> = LazyLock::new(|| {
+    let index_url = envmnt::get_or("CRATES_UPSTREAM_URL", "");
+    let index_url = index_url.trim().trim_end_matches('/');
+    if index_url.is_empty() {
+        return None;
+    }
+
+    let ttl_secs = envmnt::get_or("CRATES_UPSTREAM_INDEX_TTL_SECONDS", "300")
+        .parse()
+        .unwrap_or(300);
+
+    Some(UpstreamConfig {
+        index_url: index_url.to_string(),
+        index_ttl: Duration::from_secs(ttl_secs),
+    })
+});
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .user_agent(concat!("warehouse/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(60))
+        .build()
+        .unwrap_or_default()
+});
+
+/// Upstream `dl` template, read once from the upstream `config.json`.
+static UPSTREAM_DL: OnceCell<String> = OnceCell::const_new();
+
+#[derive(Deserialize)]
+struct UpstreamIndexConfig {
+    dl: String,
+}
+
+#[derive(Deserialize)]
+struct IndexLine {
+    vers: String,
+    cksum: String,
+}
+
+// ---------------------------------------------------------------------------
+// Storage keys
+// ---------------------------------------------------------------------------
+
+fn cached_index_key(name: &str) -> String {
+    format!(".upstream/index/{}/{name}", index_prefix(name))
+}
+
+fn cached_crate_key(name: &str, version: &str) -> String {
+    format!(".upstream/crates/{name}/{version}/{name}-{version}.crate")
+}
+
+// ---------------------------------------------------------------------------
+// Lookups
+// ---------------------------------------------------------------------------
+
+/// An upstream together with the store caching it.
+struct PullThrough<'a> {
+    config: &'a UpstreamConfig,
+    store: &'a dyn ObjectStore,
+    dl: &'a OnceCell<String>,
+}
+
+/// The configured upstream, cached in the crates store; `None` when
+/// upstream mode is off.
+fn pull_through() -> Option<PullThrough<'static>> {
+    Some(PullThrough {
+        config: UPSTREAM.as_ref()?,
+        store: CRATES_STORE.as_ref(),
+        dl: &UPSTREAM_DL,
+    })
+}
+
+/// Returns the upstream index file for `name`, refreshing the cached copy
+/// once it is older than the TTL. `None` when upstream mode is off or the
+/// crate is unknown upstream.
+pub(super) async fn index_file(name: &str) -> Option<Vec<u8>> {
+    pull_through()?.index_file(name).await
+}
+
+/// Returns the `.crate` for `name`/`version` from the cache, downloading and
+/// verifying it on a miss. `Ok(None)` means the version is unknown upstream.
+pub(super) async fn crate_file(name: &str, version: &str) -> Result<Option<Vec<u8>>, String> {
+    match pull_through() {
+        Some(upstream) => upstream.crate_file(name, version).await,
+        None => Ok(None),
+    }
+}
+
+impl PullThrough<'_> {
+    async fn read_cached(&self, key: &str) -> Option<Vec<u8>> {
+        self.store.get(key).await.ok().flatten()
+    }
+
+    async fn index_file(&self, name: &str) -> Option<Vec<u8>> {
+        let config = self.config;
+        if !validate_crate_name(name) {
+            return None;
+        }
+
+        let name = name.to_ascii_lowercase();
+        let key = cached_index_key(&name);
+
+        let fresh = self
+            .store
+            .modified(&key)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age < config.index_ttl);
+        if fresh && let Some(data) = self.read_cached(&key).await {
+            return Some(data);
+        }
+
+        let url = format!("{}/{}/{name}", config.index_url, index_prefix(&name));
+        match HTTP_CLIENT.get(&url).send().await {
+            Ok(response) if response.status().is_success() => match response.bytes().await {
+                Ok(body) => {
+                    if let Err(e) = self.store.put(&key, body.to_vec()).await {
+                        tracing::warn!("failed to cache upstream index for {name}: {e}");
+                    }
+                    Some(body.to_vec())
+                }
+                Err(e) => {
+                    tracing::warn!("failed to read upstream index for {name}: {e}");
+                    self.read_cached(&key).await
+                }
+            },
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE
+                ) =>
+            {
+                let _ = self.store.delete(&key).await;
+                None
+            }
+            Ok(response) => {
+                tracing::warn!("upstream index for {name} returned {}", response.status());
+                self.read_cached(&key).await
+            }
+            Err(e) => {
+                // Offline: serve whatever was cached last.
+                tracing::warn!("upstream index for {name} unreachable: {e}");
+                self.read_cached(&key).await
+            }
+        }
+    }
+
+    async fn crate_file(&self, name: &str, version: &str) -> Result<Option<Vec<u8>>, String> {
+        if !validate_crate_name(name) || !validate_version(version) {
+            return Ok(None);
+        }
+
+        let name = name.to_ascii_lowercase();
+        let key = cached_crate_key(&name, version);
+        if let Some(data) = self.read_cached(&key).await {
+            return Ok(Some(data));
+        }
+
+        let Some(index) = self.index_file(&name).await else {
+            return Ok(None);
+        };
+        let Some(cksum) = String::from_utf8_lossy(&index)
+            .lines()
+            .filter_map(|l| serde_json::from_str::<IndexLine>(l.trim()).ok())
+            .find(|l| l.vers == version)
+            .map(|l| l.cksum)
+        else {
+            return Ok(None);
+        };
+
+        let template = self.download_template().await?;
+        let url = download_url(template, &name, version, &cksum);
+
+        let response = HTTP_CLIENT
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("upstream download failed: {e}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("upstream download returned {}", response.status()));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("upstream download failed: {e}"))?;
+
+        let actual = format!("{:x}", Sha256::digest(&body));
+        if actual != cksum {
+            return Err(format!(
+                "checksum mismatch for {name}-{version}: index has {cksum}, upstream sent {actual}"
+            ));
+        }
+
+        if let Err(e) = self.store.put(&key, body.to_vec()).await {
+            tracing::warn!("failed to cache {name}-{version}.crate: {e}");
+        }
+
+        Ok(Some(body.to_vec()))
+    }
+
+    async fn download_template(&self) -> Result<&String, String> {
+        self.dl
+            .get_or_try_init(|| async {
+                let url = format!("{}/config.json", self.config.index_url);
+                let response = HTTP_CLIENT
+                    .get(&url)
+                    .send()
+                    .await
+                    .map_err(|e| format!("failed to fetch upstream config: {e}"))?;
+                if !response.status().is_success() {
+                    return Err(format!("upstream config returned {}", response.status()));
+                }
+                let config: UpstreamIndexConfig = response
+                    .json()
+                    .await
+                    .map_err(|e| format!("invalid upstream config: {e}"))?;
+                Ok(config.dl)
+            })
+            .await
+    }
+}
+
+/// Expands a cargo `dl` value: either a template with `{crate}`, `{version}`,
+/// `{prefix}`, `{lowerprefix}` and `{sha256-checksum}` markers, or a base
+/// URL that gets `/{crate}/{version}/download` appended.
+fn download_url(template: &str, name: &str, version: &str, cksum: &str) -> String {
+    const MARKERS: [&str; 5] = [
+        "{crate}",
+        "{version}",
+        "{prefix}",
+        "{lowerprefix}",
+        "{sha256-checksum}",
+    ];
+
+    if !MARKERS.iter().any(|m| template.contains(m)) {
+        return format!(
+            "{}/{name}/{version}/download",
+            template.trim_end_matches('/')
+        );
+    }
+
+    let prefix = index_prefix(name);
+    template
+        .replace("{crate}", name)
+        .replace("{version}", version)
+        .replace("{prefix}", &prefix)
+        .replace("{lowerprefix}", &prefix)
+        .replace("{sha256-checksum}", cksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PullThrough, UpstreamConfig, cached_crate_key, cached_index_key, download_url};
+    use crate::storage::ObjectStore;
+    use crate::storage::memory::MemoryStore;
+    use actix_web::{App, HttpRequest, HttpResponse, HttpServer, web};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use tokio::sync::OnceCell;
+
+    const TARBALL: &[u8] = b"demo-0.1.0 tarball";
+
+    /// What the stand-in upstream serves, and the paths it was asked for.
+    #[derive(Default)]
+    struct Upstream {
+        base_url: Mutex<String>,
+        index: Mutex<Option<String>>,
+        tarball: Mutex<Vec<u8>>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl Upstream {
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        fn set_index(&self, versions: &[&str]) {
+            let lines: Vec<String> = versions
+                .iter()
+                .map(|vers| {
+                    json!({
+                        "name": "demo",
+                        "vers": vers,
+                        "deps": [],
+                        "cksum": format!("{:x}", Sha256::digest(TARBALL)),
+                        "features": {},
+                        "yanked": false,
+                    })
+                    .to_string()
+                })
+                .collect();
+            *self.index.lock().unwrap() = Some(lines.join("\n"));
+        }
+    }
+
+    async fn serve(req: HttpRequest, upstream: web::Data<Upstream>) -> HttpResponse {
+        upstream
+            .requests
+            .lock()
+            .unwrap()
+            .push(req.path().to_string());
+        match req.path() {
+            "/config.json" => {
+                let base_url = upstream.base_url.lock().unwrap().clone();
+                HttpResponse::Ok().json(json!({ "dl": format!("{base_url}/dl") }))
+            }
+            "/de/mo/demo" => match upstream.index.lock().unwrap().clone() {
+                Some(index) => HttpResponse::Ok().body(index),
+                None => HttpResponse::NotFound().finish(),
+            },
+            "/dl/demo/0.1.0/download" => {
+                HttpResponse::Ok().body(upstream.tarball.lock().unwrap().clone())
+            }
+            _ => HttpResponse::NotFound().finish(),
+        }
+    }
+
+    /// Starts a stand-in sparse registry serving `demo-0.1.0`.
+    fn start_upstream() -> (web::Data<Upstream>, UpstreamConfig) {
+        let upstream = web::Data::new(Upstream::default());
+        upstream.set_index(&["0.1.0"]);
+        *upstream.tarball.lock().unwrap() = TARBALL.to_vec();
+
+        let data = upstream.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(data.clone())
+                .default_service(web::to(serve))
+        })
+        .workers(1)
+        .disable_signals()
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let base_url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+
+        *upstream.base_url.lock().unwrap() = base_url.clone();
+        let config = UpstreamConfig {
+            index_url: base_url,
+            index_ttl: Duration::from_secs(300),
+        };
+        (upstream, config)
+    }
+
+    #[actix_web::test]
+    async fn miss_downloads_and_hit_serves_the_cache() {
+        let (upstream, config) = start_upstream();
+        let store = MemoryStore::default();
+        let dl = OnceCell::new();
+        let cache = PullThrough {
+            config: &config,
+            store: &store,
+            dl: &dl,
+        };
+
+        let file = cache.crate_file("demo", "0.1.0").await.unwrap();
+        assert_eq!(file.as_deref(), Some(TARBALL));
+        assert_eq!(
+            upstream.requests(),
+            ["/de/mo/demo", "/config.json", "/dl/demo/0.1.0/download"]
+        );
+        let cached = store.get(&cached_crate_key("demo", "0.1.0")).await.unwrap();
+        assert_eq!(cached.as_deref(), Some(TARBALL));
+        assert!(
+            store
+                .get(&cached_index_key("demo"))
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // Within the TTL nothing is requested again, even though the
+        // upstream no longer knows the crate.
+        *upstream.index.lock().unwrap() = None;
+        assert!(cache.index_file("Demo").await.is_some());
+        let file = cache.crate_file("demo", "0.1.0").await.unwrap();
+        assert_eq!(file.as_deref(), Some(TARBALL));
+        assert_eq!(cache.crate_file("demo", "9.9.9").await.unwrap(), None);
+        assert_eq!(upstream.requests().len(), 3);
+    }
+
+    #[actix_web::test]
+    async fn expired_index_is_refreshed_or_served_stale() {
+        let (upstream, mut config) = start_upstream();
+        config.index_ttl = Duration::ZERO;
+        let store = MemoryStore::default();
+        let dl = OnceCell::new();
+        let cache = PullThrough {
+            config: &config,
+            store: &store,
+            dl: &dl,
+        };
+
+        let index = cache.index_file("demo").await.unwrap();
+        assert!(!String::from_utf8_lossy(&index).contains("0.2.0"));
+        upstream.set_index(&["0.1.0", "0.2.0"]);
+        let index = cache.index_file("demo").await.unwrap();
+        assert!(String::from_utf8_lossy(&index).contains("0.2.0"));
+        assert_eq!(upstream.requests(), ["/de/mo/demo", "/de/mo/demo"]);
+
+        // An unreachable upstream leaves the last copy in service.
+        let offline = UpstreamConfig {
+            index_url: "http://127.0.0.1:1".to_string(),
+            index_ttl: Duration::ZERO,
+        };
+        let stale = PullThrough {
+            config: &offline,
+            store: &store,
+            dl: &dl,
+        };
+        assert_eq!(stale.index_file("demo").await, Some(index));
+
+        // A crate removed upstream is dropped from the cache.
+        *upstream.index.lock().unwrap() = None;
+        assert_eq!(cache.index_file("demo").await, None);
+        assert_eq!(store.get(&cached_index_key("demo")).await.unwrap(), None);
+    }
+
+    #[actix_web::test]
+    async fn tampered_download_is_rejected_and_not_cached() {
+        let (upstream, config) = start_upstream();
+        *upstream.tarball.lock().unwrap() = b"tampered".to_vec();
+        let store = MemoryStore::default();
+        let dl = OnceCell::new();
+        let cache = PullThrough {
+            config: &config,
+            store: &store,
+            dl: &dl,
+        };
+
+        let error = cache.crate_file("demo", "0.1.0").await.unwrap_err();
+        assert!(error.contains("checksum mismatch"), "{error}");
+        let cached = store.get(&cached_crate_key("demo", "0.1.0")).await.unwrap();
+        assert_eq!(cached, None);
+    }
+
+    #[test]
+    fn download_templates_are_expanded() {
+        assert_eq!(
+            download_url("https://dl.example/api/v1/crates/", "serde", "1.0.0", "ab"),
+            "https://dl.example/api/v1/crates/serde/1.0.0/download"
+        );
+        assert_eq!(
+            download_url(
+                "https://dl.example/{prefix}/{crate}/{crate}-{version}.crate?sum={sha256-checksum}",
+                "serde",
+                "1.0.0",
+                "ab"
+            ),
+            "https://dl.example/se/rd/serde/serde-1.0.0.crate?sum=ab"
+        );
+    }
+}
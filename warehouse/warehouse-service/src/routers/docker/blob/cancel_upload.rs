@@ -1,5 +1,6 @@
 use crate::domain::docker_error;
-use crate::routers::docker::upload_path;
+use crate::routers::docker::uploads::Session;
+use crate::routers::docker::validate_repository_name;
 use actix_web::{HttpResponse, Responder, delete, web};
 
 #[utoipa::path(
@@ -23,23 +24,26 @@ use actix_web::{HttpResponse, Responder, delete, web};
 pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
     let (name, uuid) = path.into_inner();
 
-    let Some(upload_path) = upload_path(&name, &uuid) else {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
-    };
-    if !upload_path.exists() {
-        return docker_error::response(
-            actix_web::http::StatusCode::NOT_FOUND,
-            docker_error::BLOB_UNKNOWN,
-            "blob upload unknown to registry",
-        );
     }
+    let session = match Session::new(&name, &uuid) {
+        Some(session) if session.parts().await.is_ok_and(|parts| !parts.is_empty()) => session,
+        _ => {
+            return docker_error::response(
+                actix_web::http::StatusCode::NOT_FOUND,
+                docker_error::BLOB_UNKNOWN,
+                "blob upload unknown to registry",
+            );
+        }
+    };
 
-    match tokio::fs::remove_file(&upload_path).await {
-        Ok(_) => HttpResponse::NoContent().finish(),
+    match session.remove().await {
+        Ok(()) => HttpResponse::NoContent().finish(),
         Err(_) => docker_error::response(
             actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             docker_error::UNSUPPORTED,
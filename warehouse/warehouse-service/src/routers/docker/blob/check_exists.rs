@@ -1,5 +1,6 @@
 use crate::domain::docker_error;
-use crate::routers::docker::{blob_path, validate_digest};
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::{blob_key, refresh_blob, upstream, validate_digest};
 use actix_web::{HttpResponse, Responder, head, web};
 
 #[utoipa::path(
@@ -24,11 +25,12 @@ use actix_web::{HttpResponse, Responder, head, web};
         (status = 403, description = "Access denied"),
         (status = 404, description = "Blob not found"),
         (status = 429, description = "Too many requests"),
+        (status = 502, description = "Upstream registry unavailable (pull-through repositories)"),
     )
 )]
 #[head("/{repo:.*}/blobs/{digest}")]
 pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
-    let (_, digest) = path.into_inner();
+    let (repo, digest) = path.into_inner();
 
     // Validate digest format
     if !validate_digest(&digest) {
@@ -39,24 +41,54 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
         );
     }
 
-    let Some(blob_path) = blob_path(&digest) else {
+    let Some(blob_key) = blob_key(&digest) else {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::UNSUPPORTED,
             "invalid digest",
         );
     };
-    match std::fs::metadata(&blob_path) {
-        Ok(metadata) => HttpResponse::Ok()
+
+    let mut size = DOCKER_STORE.size(&blob_key).await;
+    if matches!(size, Ok(None)) {
+        match upstream::fetch_blob(&repo, &digest).await {
+            Ok(true) => size = DOCKER_STORE.size(&blob_key).await,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!("upstream fetch of {repo}@{digest} failed: {e}");
+                return docker_error::response(
+                    actix_web::http::StatusCode::BAD_GATEWAY,
+                    docker_error::UNSUPPORTED,
+                    "upstream registry unavailable",
+                );
+            }
+        }
+    }
+
+    // A push checks for existing layers this way before skipping them.
+    if matches!(size, Ok(Some(_))) {
+        refresh_blob(&digest).await;
+    }
+
+    match size {
+        Ok(Some(size)) => HttpResponse::Ok()
             .append_header(("Content-Type", "application/octet-stream"))
             .append_header(("Docker-Content-Digest", digest))
-            .append_header(("Content-Length", metadata.len()))
+            .append_header(("Content-Length", size))
             .append_header(("Accept-Ranges", "bytes"))
             .finish(),
-        Err(_) => docker_error::response(
+        Ok(None) => docker_error::response(
             actix_web::http::StatusCode::NOT_FOUND,
             docker_error::BLOB_UNKNOWN,
             "blob unknown to registry",
         ),
+        Err(e) => {
+            tracing::error!("failed to stat blob {digest}: {e}");
+            docker_error::response(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                docker_error::UNSUPPORTED,
+                "internal server error",
+            )
+        }
     }
 }
@@ -1,7 +1,10 @@
 use crate::domain::docker_error;
-use crate::routers::docker::{DigestQuery, blob_path, upload_path, validate_digest};
+use crate::routers::docker::uploads::{self, Session};
+use crate::routers::docker::{
+    DigestQuery, blob_exists, blob_key, refresh_blob, validate_digest, validate_repository_name,
+};
+use crate::routers::quotas;
 use actix_web::{HttpResponse, Responder, put, web};
-use sha2::{Digest, Sha256};
 
 #[utoipa::path(
     put,
@@ -22,7 +25,7 @@ use sha2::{Digest, Sha256};
         (status = 201, description = "Upload completed successfully"),
         (status = 400, description = "Bad request"),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Access denied"),
+        (status = 403, description = "Access denied or storage quota exceeded"),
         (status = 404, description = "Upload session not found"),
         (status = 416, description = "Requested range not satisfiable"),
         (status = 429, description = "Too many requests"),
@@ -45,14 +48,14 @@ pub async fn handle(
         );
     }
 
-    let Some(upload_file) = upload_path(&name, &uuid) else {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
-    };
-    let Some(final_path) = blob_path(digest) else {
+    }
+    let Some(blob_key) = blob_key(digest) else {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::UNSUPPORTED,
@@ -60,79 +63,70 @@ pub async fn handle(
         );
     };
 
-    if tokio::fs::metadata(&final_path).await.is_ok() {
-        let _ = tokio::fs::remove_file(&upload_file).await;
-        return HttpResponse::Created()
+    let created = || {
+        HttpResponse::Created()
             .append_header(("Location", format!("/v2/{name}/blobs/{digest}")))
             .append_header(("Docker-Content-Digest", digest.clone()))
-            .finish();
-    }
-
-    if !upload_file.exists() {
-        return docker_error::response(
+            .finish()
+    };
+    let unknown = || {
+        docker_error::response(
             actix_web::http::StatusCode::NOT_FOUND,
             docker_error::BLOB_UNKNOWN,
             "blob upload unknown to registry",
-        );
+        )
+    };
+    let internal_error = || {
+        docker_error::response(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            docker_error::UNSUPPORTED,
+            "internal server error",
+        )
+    };
+
+    let Some(session) = Session::new(&name, &uuid) else {
+        return unknown();
+    };
+
+    if blob_exists(digest).await {
+        let _ = session.remove().await;
+        refresh_blob(digest).await;
+        return created();
     }
 
+    let mut parts = match session.parts().await {
+        Ok(parts) if parts.is_empty() => return unknown(),
+        Ok(parts) => parts,
+        Err(e) => {
+            tracing::error!("failed to read upload session {uuid}: {e}");
+            return internal_error();
+        }
+    };
+
     // Append final chunk if present
     if !body.is_empty() {
-        use tokio::io::AsyncWriteExt;
-        let mut file = match tokio::fs::OpenOptions::new()
-            .append(true)
-            .open(&upload_file)
-            .await
-        {
-            Ok(f) => f,
-            Err(_) => {
-                return docker_error::response(
-                    actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    docker_error::UNSUPPORTED,
-                    "internal server error",
-                );
+        let appended = session.append(uploads::received(&parts), body.to_vec());
+        parts = match appended.await {
+            Ok(()) => session.parts().await.unwrap_or_default(),
+            Err(e) => {
+                tracing::error!("failed to store chunk of upload session {uuid}: {e}");
+                return internal_error();
             }
         };
-
-        if file.write_all(&body).await.is_err() {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
-        }
-        if file.sync_data().await.is_err() {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
-        }
     }
 
-    // Read entire file
-    let data = match tokio::fs::read(&upload_file).await {
-        Ok(d) => d,
-        Err(_) => {
-            if tokio::fs::metadata(&final_path).await.is_ok() {
-                return HttpResponse::Created()
-                    .append_header(("Location", format!("/v2/{name}/blobs/{digest}")))
-                    .append_header(("Docker-Content-Digest", digest.clone()))
-                    .finish();
-            }
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
+    // Verify digest
+    let computed = match uploads::content_digest(&parts).await {
+        Ok(Some(computed)) => computed,
+        // The session was completed by a concurrent request.
+        Ok(None) if blob_exists(digest).await => return created(),
+        Ok(None) => return unknown(),
+        Err(e) => {
+            tracing::error!("failed to read upload session {uuid}: {e}");
+            return internal_error();
         }
     };
 
-    // Verify digest
-    let mut hasher = Sha256::new();
-    hasher.update(&data);
-    let computed = format!("sha256:{:x}", hasher.finalize());
-
     if &computed != digest {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
@@ -141,45 +135,33 @@ pub async fn handle(
         );
     }
 
-    let Some(final_parent) = final_path.parent() else {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
-    };
-    if tokio::fs::create_dir_all(final_parent).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
+    if blob_exists(digest).await {
+        let _ = session.remove().await;
+        refresh_blob(digest).await;
+        return created();
     }
 
-    if tokio::fs::metadata(&final_path).await.is_ok() {
-        let _ = tokio::fs::remove_file(&upload_file).await;
-        return HttpResponse::Created()
-            .append_header(("Location", format!("/v2/{name}/blobs/{digest}")))
-            .append_header(("Docker-Content-Digest", digest.clone()))
-            .finish();
+    match quotas::check_docker_blob(&name, uploads::received(&parts)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(exceeded)) => {
+            let _ = session.remove().await;
+            return exceeded.docker_response();
+        }
+        Err(e) => {
+            tracing::error!("failed to compute storage usage for {name}: {e}");
+            return internal_error();
+        }
     }
 
-    // Atomic move
-    if let Err(err) = tokio::fs::rename(&upload_file, &final_path).await {
-        if tokio::fs::metadata(&final_path).await.is_ok() {
-            let _ = tokio::fs::remove_file(&upload_file).await;
+    // Compose the parts into the blob; the session is removed once stored.
+    if let Err(err) = session.finish(&blob_key, &parts).await {
+        if blob_exists(digest).await {
+            let _ = session.remove().await;
         } else {
-            let _ = err;
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
+            tracing::error!("failed to store blob {digest}: {err}");
+            return internal_error();
         }
     }
 
-    HttpResponse::Created()
-        .append_header(("Location", format!("/v2/{name}/blobs/{digest}")))
-        .append_header(("Docker-Content-Digest", digest.clone()))
-        .finish()
+    created()
 }
@@ -1,5 +1,6 @@
 use crate::domain::docker_error;
-use crate::routers::docker::upload_path;
+use crate::routers::docker::uploads::{self, Session};
+use crate::routers::docker::validate_repository_name;
 use actix_web::{HttpResponse, Responder, get, web};
 
 #[utoipa::path(
@@ -23,25 +24,26 @@ use actix_web::{HttpResponse, Responder, get, web};
 pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
     let (name, uuid) = path.into_inner();
 
-    let Some(upload_path) = upload_path(&name, &uuid) else {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
+    }
+    let parts = match Session::new(&name, &uuid) {
+        Some(session) => session.parts().await.unwrap_or_default(),
+        None => Vec::new(),
     };
-    let metadata = match tokio::fs::metadata(&upload_path).await {
-        Ok(m) => m,
-        Err(_) => {
-            return docker_error::response(
-                actix_web::http::StatusCode::NOT_FOUND,
-                docker_error::BLOB_UNKNOWN,
-                "blob upload unknown to registry",
-            );
-        }
-    };
+    if parts.is_empty() {
+        return docker_error::response(
+            actix_web::http::StatusCode::NOT_FOUND,
+            docker_error::BLOB_UNKNOWN,
+            "blob upload unknown to registry",
+        );
+    }
 
-    let size = metadata.len();
+    let size = uploads::received(&parts);
     let range = if size == 0 {
         "0-0".to_string()
     } else {
@@ -1,10 +1,7 @@
 use crate::domain::docker_error;
-use crate::routers::docker::{blob_path, validate_digest};
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::{blob_key, upstream, validate_digest};
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
-use std::io::SeekFrom;
-use std::path::PathBuf;
-use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
 #[utoipa::path(
     get,
@@ -54,11 +51,12 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt};
         (status = 404, description = "Blob not found"),
         (status = 416, description = "Requested range not satisfiable"),
         (status = 429, description = "Too many requests"),
+        (status = 502, description = "Upstream registry unavailable (pull-through repositories)"),
     )
 )]
 #[get("/{name:.+}/blobs/{digest}")]
 pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
-    let (_, digest) = path.into_inner();
+    let (name, digest) = path.into_inner();
 
     if !validate_digest(&digest) {
         return docker_error::response(
@@ -68,26 +66,47 @@ pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl
         );
     }
 
-    let Some(blob_path) = blob_path(&digest) else {
+    let Some(blob_key) = blob_key(&digest) else {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::UNSUPPORTED,
             "invalid digest",
         );
     };
-    if !blob_path.exists() {
-        return docker_error::response(
-            actix_web::http::StatusCode::NOT_FOUND,
-            docker_error::BLOB_UNKNOWN,
-            "blob unknown to registry",
-        );
-    }
+    let total_size = match DOCKER_STORE.size(&blob_key).await {
+        Ok(Some(size)) => size,
+        Ok(None) => match upstream::fetch_blob(&name, &digest).await {
+            Ok(true) => match DOCKER_STORE.size(&blob_key).await {
+                Ok(Some(size)) => size,
+                _ => return internal_error(),
+            },
+            Ok(false) => {
+                return docker_error::response(
+                    actix_web::http::StatusCode::NOT_FOUND,
+                    docker_error::BLOB_UNKNOWN,
+                    "blob unknown to registry",
+                );
+            }
+            Err(e) => {
+                tracing::warn!("upstream fetch of {name}@{digest} failed: {e}");
+                return docker_error::response(
+                    actix_web::http::StatusCode::BAD_GATEWAY,
+                    docker_error::UNSUPPORTED,
+                    "upstream registry unavailable",
+                );
+            }
+        },
+        Err(e) => {
+            tracing::error!("failed to stat blob {digest}: {e}");
+            return internal_error();
+        }
+    };
 
     if let Some(response) = maybe_redirect(&digest) {
         return response;
     }
 
-    serve_with_range(req, blob_path, digest).await
+    serve_with_range(req, &blob_key, total_size, digest).await
 }
 
 fn maybe_redirect(digest: &str) -> Option<HttpResponse> {
@@ -110,36 +129,17 @@ fn maybe_redirect(digest: &str) -> Option<HttpResponse> {
     )
 }
 
-async fn serve_with_range(req: HttpRequest, blob_path: PathBuf, digest: String) -> HttpResponse {
-    let file = match File::open(&blob_path).await {
-        Ok(f) => f,
-        Err(_) => {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
-        }
-    };
-
-    let metadata = match file.metadata().await {
-        Ok(m) => m,
-        Err(_) => {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
-        }
-    };
-
-    let total_size = metadata.len();
-
+async fn serve_with_range(
+    req: HttpRequest,
+    blob_key: &str,
+    total_size: u64,
+    digest: String,
+) -> HttpResponse {
     if let Some(range_header) = req.headers().get("Range")
         && let Ok(range_str) = range_header.to_str()
     {
         if let Some((start, end)) = parse_range(range_str, total_size) {
-            return serve_partial(file, start, end, total_size, &digest).await;
+            return serve_partial(blob_key, start, end, total_size, &digest).await;
         }
         return docker_error::response(
             actix_web::http::StatusCode::RANGE_NOT_SATISFIABLE,
@@ -148,11 +148,11 @@ async fn serve_with_range(req: HttpRequest, blob_path: PathBuf, digest: String)
         );
     }
 
-    serve_full(file, total_size, &digest).await
+    serve_full(blob_key, total_size, &digest).await
 }
 
 async fn serve_partial(
-    mut file: File,
+    blob_key: &str,
     start: u64,
     end: u64,
     total_size: u64,
@@ -160,22 +160,10 @@ async fn serve_partial(
 ) -> HttpResponse {
     let length = end - start + 1;
 
-    if file.seek(SeekFrom::Start(start)).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
-    }
-
-    let mut buffer = vec![0u8; length as usize];
-    if file.read_exact(&mut buffer).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
-    }
+    let buffer = match DOCKER_STORE.get_range(blob_key, start, end).await {
+        Ok(Some(buffer)) => buffer,
+        _ => return internal_error(),
+    };
 
     HttpResponse::PartialContent()
         .append_header(("Content-Type", "application/octet-stream"))
@@ -189,16 +177,11 @@ async fn serve_partial(
         .body(buffer)
 }
 
-async fn serve_full(mut file: File, total_size: u64, digest: &str) -> HttpResponse {
-    let mut buffer = Vec::with_capacity(total_size as usize);
-
-    if file.read_to_end(&mut buffer).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
-    }
+async fn serve_full(blob_key: &str, total_size: u64, digest: &str) -> HttpResponse {
+    let buffer = match DOCKER_STORE.get(blob_key).await {
+        Ok(Some(buffer)) => buffer,
+        _ => return internal_error(),
+    };
 
     HttpResponse::Ok()
         .append_header(("Content-Type", "application/octet-stream"))
@@ -208,6 +191,14 @@ async fn serve_full(mut file: File, total_size: u64, digest: &str) -> HttpRespon
         .body(buffer)
 }
 
+fn internal_error() -> HttpResponse {
+    docker_error::response(
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+        docker_error::UNSUPPORTED,
+        "internal server error",
+    )
+}
+
 fn parse_range(header: &str, total: u64) -> Option<(u64, u64)> {
     if !header.starts_with("bytes=") {
         return None;
@@ -1,6 +1,10 @@
 use crate::domain::docker_error;
-use crate::routers::docker::{blob_exists, repository_path, validate_digest};
-use actix_web::{HttpResponse, Responder, post, web};
+use crate::domain::jwt::Claims;
+use crate::routers::docker::uploads::Session;
+use crate::routers::docker::{
+    blob_exists, refresh_blob, validate_digest, validate_repository_name,
+};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, post, web};
 use serde::Deserialize;
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -24,7 +28,7 @@ pub struct MountQuery {
     responses(
         (
             status = 201,
-            description = "Upload completed",
+            description = "Blob mounted from the source repository",
             headers(
                 ("Location" = String),
                 ("Docker-Content-Digest" = String),
@@ -48,9 +52,13 @@ pub struct MountQuery {
     )
 )]
 #[post("/{name:.*}/blobs/uploads/")]
-pub async fn handle(path: web::Path<String>, query: web::Query<MountQuery>) -> impl Responder {
+pub async fn handle(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<MountQuery>,
+) -> impl Responder {
     let name = path.into_inner();
-    if repository_path(&name).is_none() {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
@@ -58,8 +66,11 @@ pub async fn handle(path: web::Path<String>, query: web::Query<MountQuery>) -> i
         );
     }
 
-    // Attempt cross-repository mount
-    if let (Some(digest), Some(_)) = (&query.mount, &query.from) {
+    // Attempt cross-repository mount. Blobs are stored globally, so mounting
+    // only needs the blob to exist and the caller to be able to pull the
+    // source repository; otherwise fall back to a regular upload as the
+    // distribution spec requires.
+    if let (Some(digest), Some(from)) = (&query.mount, &query.from) {
         if !validate_digest(digest) {
             return docker_error::response(
                 actix_web::http::StatusCode::BAD_REQUEST,
@@ -68,7 +79,14 @@ pub async fn handle(path: web::Path<String>, query: web::Query<MountQuery>) -> i
             );
         }
 
-        if blob_exists(digest).await {
+        let can_pull_source = validate_repository_name(from)
+            && req
+                .extensions()
+                .get::<Claims>()
+                .is_some_and(|claims| claims.allows("repository", from, "pull"));
+
+        if can_pull_source && blob_exists(digest).await {
+            refresh_blob(digest).await;
             return HttpResponse::Created()
                 .append_header(("Location", format!("/v2/{}/blobs/{}", name, digest)))
                 .append_header(("Docker-Content-Digest", digest.clone()))
@@ -82,26 +100,16 @@ pub async fn handle(path: web::Path<String>, query: web::Query<MountQuery>) -> i
 async fn start_regular_upload(name: String) -> HttpResponse {
     let uuid = Uuid::new_v4().to_string();
 
-    let Some(repo_path) = repository_path(&name) else {
+    let Some(session) = Session::new(&name, &uuid) else {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
     };
-    let upload_dir = repo_path.join("_uploads");
-
-    if tokio::fs::create_dir_all(&upload_dir).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
-    }
-
-    let file_path = upload_dir.join(&uuid);
 
-    if tokio::fs::File::create(&file_path).await.is_err() {
+    if let Err(e) = session.create().await {
+        tracing::error!("failed to create upload session for {name}: {e}");
         return docker_error::response(
             actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             docker_error::UNSUPPORTED,
@@ -1,7 +1,7 @@
 use crate::domain::docker_error;
-use crate::routers::docker::upload_path;
+use crate::routers::docker::uploads::{self, Session};
+use crate::routers::docker::validate_repository_name;
 use actix_web::{HttpResponse, Responder, patch, web};
-use tokio::io::AsyncWriteExt;
 
 #[utoipa::path(
     patch,
@@ -31,66 +31,55 @@ use tokio::io::AsyncWriteExt;
 pub async fn handle(path: web::Path<(String, String)>, body: web::Bytes) -> impl Responder {
     let (name, uuid) = path.into_inner();
 
-    let Some(file_path) = upload_path(&name, &uuid) else {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
-    };
+    }
 
-    if !file_path.exists() {
-        return docker_error::response(
+    let unknown = || {
+        docker_error::response(
             actix_web::http::StatusCode::NOT_FOUND,
             docker_error::BLOB_UNKNOWN,
             "blob upload unknown to registry",
-        );
-    }
-
-    if body.is_empty() {
-        return docker_error::response(
-            actix_web::http::StatusCode::BAD_REQUEST,
+        )
+    };
+    let internal_error = || {
+        docker_error::response(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             docker_error::UNSUPPORTED,
-            "empty upload chunk",
-        );
-    }
-
-    let metadata = match tokio::fs::metadata(&file_path).await {
-        Ok(m) => m,
-        Err(_) => {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
-        }
+            "internal server error",
+        )
     };
 
-    let current_size = metadata.len();
-
-    let mut file = match tokio::fs::OpenOptions::new()
-        .append(true)
-        .open(&file_path)
-        .await
-    {
-        Ok(f) => f,
-        Err(_) => {
-            return docker_error::response(
-                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-                docker_error::UNSUPPORTED,
-                "internal server error",
-            );
+    let Some(session) = Session::new(&name, &uuid) else {
+        return unknown();
+    };
+    let parts = match session.parts().await {
+        Ok(parts) if parts.is_empty() => return unknown(),
+        Ok(parts) => parts,
+        Err(e) => {
+            tracing::error!("failed to read upload session {uuid}: {e}");
+            return internal_error();
         }
     };
 
-    if file.write_all(&body).await.is_err() {
+    if body.is_empty() {
         return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::UNSUPPORTED,
-            "internal server error",
+            "empty upload chunk",
         );
     }
 
+    let current_size = uploads::received(&parts);
+    if let Err(e) = session.append(current_size, body.to_vec()).await {
+        tracing::error!("failed to store chunk of upload session {uuid}: {e}");
+        return internal_error();
+    }
+
     let new_size = current_size + body.len() as u64;
 
     HttpResponse::Accepted()
@@ -22,7 +22,7 @@ use actix_web::{HttpRequest, HttpResponse, Responder, head, web};
             )
         ),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Access denied"),
+        (status = 403, description = "Access denied or signature policy not satisfied"),
         (status = 404, description = "Manifest not found"),
         (status = 429, description = "Too many requests"),
     )
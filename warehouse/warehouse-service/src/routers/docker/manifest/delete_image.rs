@@ -1,6 +1,10 @@
-use crate::domain::docker_error;
-use crate::routers::docker::{manifest_path, repository_path, validate_digest};
-use actix_web::{HttpResponse, Responder, delete, web};
+use super::referrers;
+use crate::domain::{docker_error, webhooks};
+use crate::routers::docker::{
+    manifest_key, manifest_link_key, tag_protection, tags_prefix, validate_digest,
+};
+use crate::routers::{DOCKER_STORE, quotas};
+use actix_web::{HttpRequest, HttpResponse, Responder, delete, web};
 
 #[utoipa::path(
     delete,
@@ -14,14 +18,14 @@ use actix_web::{HttpResponse, Responder, delete, web};
     responses(
         (status = 202, description = "Manifest deleted successfully. No content returned."),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Access denied. The manifest may still be referenced."),
+        (status = 403, description = "Access denied, or the manifest is referenced by a protected tag."),
         (status = 404, description = "Manifest or repository not found"),
         (status = 405, description = "Only digest-based deletion is allowed."),
         (status = 429, description = "Too many requests"),
     )
 )]
 #[delete("/{name:.+}/manifests/{reference}")]
-pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
     let (name, reference) = path.into_inner();
 
     // Must delete by digest only
@@ -33,7 +37,7 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
         );
     }
 
-    let Some(repo_path) = repository_path(&name) else {
+    let Some(tags_prefix) = tags_prefix(&name) else {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
@@ -41,41 +45,62 @@ pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
         );
     };
 
-    let Some(manifest_path) = manifest_path(&reference) else {
+    let Some(manifest_key) = manifest_key(&reference) else {
         return docker_error::response(
             actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
             docker_error::UNSUPPORTED,
             "manifest deletion requires a digest reference",
         );
     };
-    if !manifest_path.exists() {
-        return docker_error::response(
-            actix_web::http::StatusCode::NOT_FOUND,
-            docker_error::MANIFEST_UNKNOWN,
-            "manifest unknown",
-        );
+    let manifest = match DOCKER_STORE.get(&manifest_key).await {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            return docker_error::response(
+                actix_web::http::StatusCode::NOT_FOUND,
+                docker_error::MANIFEST_UNKNOWN,
+                "manifest unknown",
+            );
+        }
+        Err(e) => {
+            tracing::error!("failed to read manifest {reference}: {e}");
+            return internal_error();
+        }
+    };
+
+    if let Err(denied) = tag_protection::check_delete(&req, &name, &reference).await {
+        return denied;
     }
 
     // Remove manifest file
-    if tokio::fs::remove_file(&manifest_path).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
+    if let Err(e) = DOCKER_STORE.delete(&manifest_key).await {
+        tracing::error!("failed to delete manifest {reference}: {e}");
+        return internal_error();
     }
 
     // Optional: remove tag references pointing to this digest
-    let tags_dir = repo_path.join("tags");
-    if let Ok(mut entries) = tokio::fs::read_dir(&tags_dir).await {
-        while let Ok(Some(entry)) = entries.next_entry().await
-            && let Ok(content) = tokio::fs::read_to_string(entry.path()).await
+    for tag_key in DOCKER_STORE.list(&tags_prefix).await.unwrap_or_default() {
+        if let Ok(Some(content)) = DOCKER_STORE.get(&tag_key).await
+            && String::from_utf8_lossy(&content).trim() == reference
         {
-            if content.trim() == reference {
-                let _ = tokio::fs::remove_file(entry.path()).await;
-            }
+            let _ = DOCKER_STORE.delete(&tag_key).await;
         }
     }
 
+    if let Some(link_key) = manifest_link_key(&name, &reference) {
+        let _ = DOCKER_STORE.delete(&link_key).await;
+    }
+    quotas::docker_usage_changed();
+
+    referrers::forget(&name, &reference, &manifest).await;
+    webhooks::docker_delete(&req, &name, &reference).await;
+
     HttpResponse::Accepted().finish()
 }
+
+fn internal_error() -> HttpResponse {
+    docker_error::response(
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+        docker_error::UNSUPPORTED,
+        "internal server error",
+    )
+}
@@ -1,6 +1,8 @@
 use crate::domain::docker_error;
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::signatures::{self, Operation};
 use crate::routers::docker::{
-    manifest_path, repository_path, validate_digest, validate_tag_reference,
+    manifest_key, tag_key, upstream, validate_digest, validate_repository_name,
 };
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
 use serde::Deserialize;
@@ -33,9 +35,10 @@ use sha2::{Digest, Sha256};
         ),
         (status = 400, description = "Invalid name or reference"),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Access denied"),
+        (status = 403, description = "Access denied or signature policy not satisfied"),
         (status = 404, description = "Repository or manifest not found"),
         (status = 429, description = "Too many requests"),
+        (status = 502, description = "Upstream registry unavailable (pull-through repositories)"),
     )
 )]
 #[get("/{name:.+}/manifests/{reference}")]
@@ -65,35 +68,50 @@ pub(super) async fn resolve_manifest_response(
     name: &str,
     reference: &str,
 ) -> Result<ResolvedManifestResponse, HttpResponse> {
-    let repo_path = repository_path(name).ok_or_else(|| {
-        docker_error::response(
+    if !validate_repository_name(name) {
+        return Err(docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
-        )
-    })?;
+        ));
+    }
 
     // Resolve reference → digest
     let digest = if reference.starts_with("sha256:") {
         reference.to_string()
     } else {
-        if !validate_tag_reference(reference) {
+        let Some(tag_key) = tag_key(name, reference) else {
             return Err(docker_error::response(
                 actix_web::http::StatusCode::BAD_REQUEST,
                 docker_error::UNSUPPORTED,
                 "invalid manifest reference",
             ));
-        }
-        let tag_path = repo_path.join("tags").join(reference);
-        match tokio::fs::read_to_string(&tag_path).await {
-            Ok(d) => d.trim().to_string(),
-            Err(_) => {
-                return Err(docker_error::response(
-                    actix_web::http::StatusCode::NOT_FOUND,
-                    docker_error::MANIFEST_UNKNOWN,
-                    "manifest unknown",
-                ));
+        };
+        let cached = match DOCKER_STORE.get(&tag_key).await {
+            Ok(d) => d.map(|d| String::from_utf8_lossy(&d).trim().to_string()),
+            Err(e) => {
+                tracing::error!("failed to read tag {name}:{reference}: {e}");
+                return Err(internal_error());
             }
+        };
+        match cached {
+            Some(d) if !upstream::is_proxied(name) || !upstream::tag_is_stale(&tag_key).await => d,
+            // Missing or stale tag of a proxied repository: ask the upstream,
+            // falling back to whatever was cached.
+            cached => match upstream::fetch_manifest(name, reference).await {
+                Ok(Some(d)) => d,
+                Ok(None) => cached.ok_or_else(|| {
+                    docker_error::response(
+                        actix_web::http::StatusCode::NOT_FOUND,
+                        docker_error::MANIFEST_UNKNOWN,
+                        "manifest unknown",
+                    )
+                })?,
+                Err(e) => {
+                    tracing::warn!("upstream lookup of {name}:{reference} failed: {e}");
+                    cached.ok_or_else(upstream_unavailable)?
+                }
+            },
         }
     };
 
@@ -105,23 +123,17 @@ pub(super) async fn resolve_manifest_response(
         ));
     }
 
-    let Some(manifest_path) = manifest_path(&digest) else {
+    if !reference.starts_with("sha256:") {
+        signatures::check(name, reference, &digest, Operation::Pull).await?;
+    }
+
+    let Some(data) = read_manifest(name, &digest).await? else {
         return Err(docker_error::response(
             actix_web::http::StatusCode::NOT_FOUND,
             docker_error::MANIFEST_UNKNOWN,
             "manifest unknown",
         ));
     };
-    let data = match tokio::fs::read(&manifest_path).await {
-        Ok(d) => d,
-        Err(_) => {
-            return Err(docker_error::response(
-                actix_web::http::StatusCode::NOT_FOUND,
-                docker_error::MANIFEST_UNKNOWN,
-                "manifest unknown",
-            ));
-        }
-    };
 
     // Detect stored media type from JSON
     let stored_media_type = match detect_manifest_media_type(&data) {
@@ -149,7 +161,7 @@ pub(super) async fn resolve_manifest_response(
     // If a manifest list/index is requested, resolve and return the matching image manifest.
     if is_index_media_type(stored_media_type) && accept_requests_index(accept) {
         let platform = client_platform(req);
-        let resolved = match resolve_platform_manifest(&response_data, &platform).await {
+        let resolved = match resolve_platform_manifest(name, &response_data, &platform).await {
             Ok(Some(v)) => v,
             Ok(None) => {
                 return Err(docker_error::response(
@@ -433,6 +445,7 @@ fn parse_platform(value: &str) -> Option<ClientPlatform> {
 }
 
 async fn resolve_platform_manifest(
+    name: &str,
     index_data: &[u8],
     platform: &ClientPlatform,
 ) -> Result<Option<ResolvedIndexManifest>, ()> {
@@ -453,12 +466,58 @@ async fn resolve_platform_manifest(
     }
 
     let media_type = detect_media_type_value(&descriptor.media_type).ok_or(())?;
-    let manifest_path = manifest_path(&descriptor.digest).ok_or(())?;
-    let data = tokio::fs::read(manifest_path).await.map_err(|_| ())?;
+    let data = read_manifest(name, &descriptor.digest)
+        .await
+        .map_err(|_| ())?
+        .ok_or(())?;
 
     Ok(Some(ResolvedIndexManifest { data, media_type }))
 }
 
+/// Reads a stored manifest, pulling it through from the upstream of a
+/// proxied repository when it is not stored yet.
+async fn read_manifest(name: &str, digest: &str) -> Result<Option<Vec<u8>>, HttpResponse> {
+    let Some(key) = manifest_key(digest) else {
+        return Ok(None);
+    };
+    match DOCKER_STORE.get(&key).await {
+        Ok(Some(data)) => return Ok(Some(data)),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("failed to read manifest {digest}: {e}");
+            return Err(internal_error());
+        }
+    }
+
+    match upstream::fetch_manifest(name, digest).await {
+        Ok(Some(_)) => DOCKER_STORE.get(&key).await.map_err(|e| {
+            tracing::error!("failed to read manifest {digest}: {e}");
+            internal_error()
+        }),
+        Ok(None) => Ok(None),
+        Err(e) => {
+            tracing::warn!("upstream fetch of {name}@{digest} failed: {e}");
+            Err(upstream_unavailable())
+        }
+    }
+}
+
+fn internal_error() -> HttpResponse {
+    docker_error::response(
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+        docker_error::UNSUPPORTED,
+        "internal server error",
+    )
+}
+
+fn upstream_unavailable() -> HttpResponse {
+    docker_error::response(
+        actix_web::http::StatusCode::BAD_GATEWAY,
+        docker_error::UNSUPPORTED,
+        "upstream registry unavailable",
+    )
+}
+
 fn descriptor_matches_platform(desc: &ManifestDescriptor, target: &ClientPlatform) -> bool {
     let platform = match &desc.platform {
         Some(p) => p,
@@ -1,7 +1,12 @@
-use crate::domain::docker_error;
+use super::referrers;
+use crate::domain::{docker_error, webhooks};
+use crate::routers::docker::signatures::{self, Operation};
+use crate::routers::docker::tag_protection;
 use crate::routers::docker::{
-    manifest_path, repository_path, validate_digest, validate_tag_reference,
+    MANIFEST_WRITES, lock_tag, manifest_key, manifest_link_key, tag_key, validate_digest,
+    validate_repository_name,
 };
+use crate::routers::{DOCKER_STORE, quotas, replication};
 use actix_web::{HttpRequest, HttpResponse, Responder, put, web};
 
 const DOCKER_MANIFEST_V2: &str = "application/vnd.docker.distribution.manifest.v2+json";
@@ -29,10 +34,17 @@ const OCI_IMAGE_INDEX_V1: &str = "application/vnd.oci.image.index.v1+json";
         description = "Docker/OCI manifest payload",
     ),
     responses(
-        (status = 201, description = "Manifest created successfully"),
+        (
+            status = 201,
+            description = "Manifest created successfully",
+            headers(
+                ("Docker-Content-Digest" = String),
+                ("OCI-Subject" = Option<String>, description = "Subject digest, when the manifest declares one"),
+            )
+        ),
         (status = 400, description = "Invalid name, reference, or manifest."),
         (status = 401, description = "Authentication required"),
-        (status = 403, description = "Access denied"),
+        (status = 403, description = "Access denied, immutable tag, storage quota exceeded or signature policy not satisfied"),
         (status = 404, description = "Repository not found"),
         (status = 405, description = "Operation not allowed"),
         (status = 429, description = "Too many requests"),
@@ -69,64 +81,120 @@ pub async fn handle(
     hasher.update(&body);
     let digest = format!("sha256:{:x}", hasher.finalize());
 
-    let Some(repo_path) = repository_path(&name) else {
+    if !validate_repository_name(&name) {
         return docker_error::response(
             actix_web::http::StatusCode::BAD_REQUEST,
             docker_error::NAME_UNKNOWN,
             "invalid repository name",
         );
+    }
+
+    // Tag reference only when reference is a tag (not a digest).
+    let tag_key = if validate_digest(&reference) {
+        None
+    } else {
+        match tag_key(&name, &reference) {
+            Some(key) => Some(key),
+            None => {
+                return docker_error::response(
+                    actix_web::http::StatusCode::BAD_REQUEST,
+                    docker_error::UNSUPPORTED,
+                    "invalid manifest reference",
+                );
+            }
+        }
     };
 
-    if tokio::fs::create_dir_all(&repo_path).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
+    // Held until the tag is written: concurrent pushes of a protected tag
+    // must not both pass the immutability check.
+    let tag_guard = match &tag_key {
+        Some(key) => Some(lock_tag(key).await),
+        None => None,
+    };
+    if tag_key.is_some() {
+        if let Err(denied) = tag_protection::check_push(&req, &name, &reference, &digest).await {
+            return denied;
+        }
+        if let Err(denied) = signatures::check(&name, &reference, &digest, Operation::Push).await {
+            return denied;
+        }
+    }
+
+    match quotas::check_docker_manifest(&name, &digest, &body).await {
+        Ok(Ok(())) => {}
+        Ok(Err(exceeded)) => return exceeded.docker_response(),
+        Err(e) => {
+            tracing::error!("failed to compute storage usage for {name}: {e}");
+            return internal_error();
+        }
     }
 
     // Save manifest by digest
-    let Some(manifest_path) = manifest_path(&digest) else {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
+    let Some(manifest_key) = manifest_key(&digest) else {
+        return internal_error();
     };
-    if let Some(parent) = manifest_path.parent() {
-        let _ = tokio::fs::create_dir_all(parent).await;
+    let writing = MANIFEST_WRITES.read().await;
+    if let Err(e) = DOCKER_STORE.put(&manifest_key, body.to_vec()).await {
+        tracing::error!("failed to store manifest {digest}: {e}");
+        return internal_error();
     }
 
-    if tokio::fs::write(&manifest_path, &body).await.is_err() {
-        return docker_error::response(
-            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            docker_error::UNSUPPORTED,
-            "internal server error",
-        );
+    // Index manifests that declare a subject for the referrers API.
+    if let Err(e) = referrers::record(&name, &digest, &body, content_type).await {
+        tracing::error!("failed to index referrer {digest} in {name}: {e}");
+        return internal_error();
     }
 
-    // Save tag reference only when reference is a tag (not a digest).
-    if !validate_digest(&reference) {
-        if !validate_tag_reference(&reference) {
-            return docker_error::response(
-                actix_web::http::StatusCode::BAD_REQUEST,
-                docker_error::UNSUPPORTED,
-                "invalid manifest reference",
-            );
-        }
-        let tag_path = repo_path.join("tags").join(&reference);
-
-        if let Some(parent) = tag_path.parent() {
-            let _ = tokio::fs::create_dir_all(parent).await;
-        }
-
-        let _ = tokio::fs::write(&tag_path, digest.as_bytes()).await;
+    let tagged = tag_key.is_some();
+    if let Some(tag_key) = tag_key
+        && let Err(e) = DOCKER_STORE
+            .put(&tag_key, digest.clone().into_bytes())
+            .await
+    {
+        tracing::error!("failed to store tag {name}:{reference}: {e}");
+        return internal_error();
     }
+    // Records the push in the repository, so untagged manifests count
+    // against its quota.
+    if let Some(link_key) = manifest_link_key(&name, &digest)
+        && let Err(e) = DOCKER_STORE.put(&link_key, Vec::new()).await
+    {
+        tracing::error!("failed to link manifest {digest} to {name}: {e}");
+        return internal_error();
+    }
+    drop(writing);
+    drop(tag_guard);
+    quotas::docker_manifest_stored(&name, &digest, &body).await;
+
+    webhooks::docker_push(
+        &req,
+        &name,
+        &digest,
+        content_type,
+        body.len() as u64,
+        tagged.then_some(reference.as_str()),
+    )
+    .await;
+    replication::docker_push(&req, &name, &digest, tagged.then_some(reference.as_str())).await;
 
-    HttpResponse::Created()
+    let mut response = HttpResponse::Created();
+    response
         .append_header(("Location", format!("/v2/{name}/manifests/{reference}")))
-        .append_header(("Docker-Content-Digest", digest))
-        .finish()
+        .append_header(("Docker-Content-Digest", digest));
+    // Tells clients the referrers API is available, so they need not fall
+    // back to the `sha256-<digest>` tag schema.
+    if let Some(subject) = referrers::subject_digest(&body) {
+        response.append_header(("OCI-Subject", subject));
+    }
+    response.finish()
+}
+
+fn internal_error() -> HttpResponse {
+    docker_error::response(
+        actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+        docker_error::UNSUPPORTED,
+        "internal server error",
+    )
 }
 
 fn normalize_media_type(raw: &str) -> Option<&str> {
@@ -0,0 +1,226 @@
+use crate::domain::docker_error;
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::{manifest_key, validate_digest, validate_repository_name};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const OCI_IMAGE_MANIFEST_V1: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_IMAGE_INDEX_V1: &str = "application/vnd.oci.image.index.v1+json";
+
+/// Descriptor of a manifest whose `subject` points at another manifest.
+///
+/// Stored per repository under the key
+/// `<repo>/_referrers/<subject hex>/<referrer hex>`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferrerDescriptor {
+    media_type: String,
+    digest: String,
+    size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    annotations: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferrersIndex {
+    schema_version: u32,
+    media_type: &'static str,
+    manifests: Vec<ReferrerDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferrersQuery {
+    artifact_type: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_referrers",
+    tags = ["docker - manifest"],
+    path = "/{name}/referrers/{digest}",
+    params(
+        ("name" = String, Path, description = "Repository name (may contain slashes)"),
+        ("digest" = String, Path, description = "Digest of the subject manifest"),
+        ("artifactType" = Option<String>, Query, description = "Only return referrers of this artifact type"),
+    ),
+    responses(
+        (
+            status = 200,
+            description = "OCI image index listing the manifests that refer to the subject",
+            content(("application/vnd.oci.image.index.v1+json")),
+            headers(
+                ("OCI-Filters-Applied" = String, description = "Set to `artifactType` when the result was filtered"),
+            )
+        ),
+        (status = 400, description = "Invalid name or digest"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 429, description = "Too many requests"),
+    )
+)]
+#[get("/{name:.+}/referrers/{digest}")]
+pub async fn handle(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
+    let (name, digest) = path.into_inner();
+
+    if !validate_repository_name(&name) {
+        return docker_error::response(
+            actix_web::http::StatusCode::BAD_REQUEST,
+            docker_error::NAME_UNKNOWN,
+            "invalid repository name",
+        );
+    }
+    let Some(prefix) = referrers_prefix(&name, &digest) else {
+        return docker_error::response(
+            actix_web::http::StatusCode::BAD_REQUEST,
+            docker_error::UNSUPPORTED,
+            "invalid digest",
+        );
+    };
+
+    let artifact_type = web::Query::<ReferrersQuery>::from_query(req.query_string())
+        .ok()
+        .and_then(|q| q.into_inner().artifact_type)
+        .filter(|t| !t.is_empty());
+
+    let mut manifests = Vec::new();
+    for key in DOCKER_STORE.list(&prefix).await.unwrap_or_default() {
+        let Ok(Some(data)) = DOCKER_STORE.get(&key).await else {
+            continue;
+        };
+        let Ok(descriptor) = serde_json::from_slice::<ReferrerDescriptor>(&data) else {
+            continue;
+        };
+        if artifact_type
+            .as_deref()
+            .is_some_and(|t| descriptor.artifact_type.as_deref() != Some(t))
+        {
+            continue;
+        }
+        // Manifests are shared between repositories and may have been
+        // deleted through another one.
+        let Some(manifest_key) = manifest_key(&descriptor.digest) else {
+            continue;
+        };
+        if matches!(DOCKER_STORE.size(&manifest_key).await, Ok(Some(_))) {
+            manifests.push(descriptor);
+        }
+    }
+    manifests.sort_by(|a, b| a.digest.cmp(&b.digest));
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(OCI_IMAGE_INDEX_V1);
+    if artifact_type.is_some() {
+        response.append_header(("OCI-Filters-Applied", "artifactType"));
+    }
+    response.json(ReferrersIndex {
+        schema_version: 2,
+        media_type: OCI_IMAGE_INDEX_V1,
+        manifests,
+    })
+}
+
+fn referrers_prefix(name: &str, subject: &str) -> Option<String> {
+    if !validate_repository_name(name) || !validate_digest(subject) {
+        return None;
+    }
+    let hex = subject.strip_prefix("sha256:")?;
+    Some(format!("{name}/_referrers/{hex}/"))
+}
+
+/// Returns the digests of the manifests referring to `subject` in `name`.
+pub(in crate::routers::docker) async fn referrer_digests(
+    name: &str,
+    subject: &str,
+) -> std::io::Result<Vec<String>> {
+    let Some(prefix) = referrers_prefix(name, subject) else {
+        return Ok(Vec::new());
+    };
+    Ok(DOCKER_STORE
+        .list(&prefix)
+        .await?
+        .iter()
+        .filter_map(|key| key.strip_prefix(&prefix))
+        .map(|hex| format!("sha256:{hex}"))
+        .collect())
+}
+
+/// Returns the subject digest of a manifest, if it declares a valid one.
+pub(super) fn subject_digest(manifest: &[u8]) -> Option<String> {
+    let value: Value = serde_json::from_slice(manifest).ok()?;
+    let digest = value.get("subject")?.get("digest")?.as_str()?;
+    validate_digest(digest).then(|| digest.to_string())
+}
+
+/// Adds manifest `digest` to the referrers index of its subject.
+pub(super) async fn record(
+    name: &str,
+    digest: &str,
+    manifest: &[u8],
+    content_type: Option<&str>,
+) -> std::io::Result<()> {
+    let Some(subject) = subject_digest(manifest) else {
+        return Ok(());
+    };
+    let Ok(value) = serde_json::from_slice::<Value>(manifest) else {
+        return Ok(());
+    };
+    let (Some(prefix), Some(hex)) = (
+        referrers_prefix(name, &subject),
+        digest.strip_prefix("sha256:"),
+    ) else {
+        return Ok(());
+    };
+
+    let media_type = value
+        .get("mediaType")
+        .and_then(|m| m.as_str())
+        .or(content_type)
+        .unwrap_or(OCI_IMAGE_MANIFEST_V1)
+        .to_string();
+    // Per the OCI spec the config media type stands in for a missing
+    // `artifactType` on image manifests.
+    let artifact_type = value
+        .get("artifactType")
+        .and_then(|t| t.as_str())
+        .or_else(|| {
+            value
+                .get("config")
+                .and_then(|c| c.get("mediaType"))
+                .and_then(|t| t.as_str())
+        })
+        .map(str::to_string);
+    let annotations = value
+        .get("annotations")
+        .and_then(|a| serde_json::from_value(a.clone()).ok());
+
+    let descriptor = ReferrerDescriptor {
+        media_type,
+        digest: digest.to_string(),
+        size: manifest.len() as u64,
+        artifact_type,
+        annotations,
+    };
+
+    DOCKER_STORE
+        .put(&format!("{prefix}{hex}"), serde_json::to_vec(&descriptor)?)
+        .await
+}
+
+/// Removes manifest `digest` from the referrers index of its subject.
+pub(super) async fn forget(name: &str, digest: &str, manifest: &[u8]) {
+    let Some(subject) = subject_digest(manifest) else {
+        return;
+    };
+    if let (Some(prefix), Some(hex)) = (
+        referrers_prefix(name, &subject),
+        digest.strip_prefix("sha256:"),
+    ) {
+        let _ = DOCKER_STORE.delete(&format!("{prefix}{hex}")).await;
+    }
+}
@@ -1,45 +1,114 @@
-use crate::routers::DOCKER_STORAGE_ROOT;
+use crate::routers::DOCKER_STORE;
+use crate::routers::admin::gc;
 use actix_web::dev::HttpServiceFactory;
 use actix_web::web;
 use serde::Deserialize;
-use std::path::{Component, Path, PathBuf};
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use tokio::sync::OwnedMutexGuard;
 use utoipa::OpenApi;
 
 mod blob;
 mod manifest;
 pub(crate) mod registry;
+pub(crate) mod signatures;
+pub(crate) mod tag_protection;
 pub mod token;
+pub(crate) mod uploads;
+mod upstream;
+
+/// Held shared while a manifest and its tag are written, and exclusively by
+/// retention while it deletes tags and manifests, so a tag pushed during a
+/// retention run is never left pointing at a deleted manifest.
+pub(crate) static MANIFEST_WRITES: tokio::sync::RwLock<()> = tokio::sync::RwLock::const_new(());
+
+type TagLocks = Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>;
+
+static TAG_LOCKS: LazyLock<TagLocks> = LazyLock::new(Default::default);
+
+/// Serialises pushes of one tag (keyed by its store key) within this
+/// process, so the immutability check and the tag write cannot interleave
+/// with another push of the same tag. Released when the guard is dropped.
+pub(crate) async fn lock_tag(key: &str) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = TAG_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        // Drop locks nobody holds or waits for.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(key.to_string()).or_default().clone()
+    };
+    lock.lock_owned().await
+}
 
-fn upload_path(name: &str, uuid: &str) -> Option<PathBuf> {
-    let repo = repository_path(name)?;
-    Some(repo.join("_uploads").join(uuid))
+/// Store key of a blob: `blobs/sha256/<hex>`.
+pub(crate) fn blob_key(digest: &str) -> Option<String> {
+    Some(format!("blobs/sha256/{}", digest_hex(digest)?))
 }
 
-fn blob_path(digest: &str) -> Option<PathBuf> {
-    let hex = digest_hex(digest)?;
-    Some(
-        PathBuf::from(DOCKER_STORAGE_ROOT.as_str())
-            .join("blobs")
-            .join("sha256")
-            .join(hex),
-    )
+/// Store key of a manifest: `manifests/sha256/<hex>`.
+pub(crate) fn manifest_key(digest: &str) -> Option<String> {
+    Some(format!("manifests/sha256/{}", digest_hex(digest)?))
 }
 
-fn manifest_path(digest: &str) -> Option<PathBuf> {
-    let hex = digest_hex(digest)?;
-    Some(
-        PathBuf::from(DOCKER_STORAGE_ROOT.as_str())
-            .join("manifests")
-            .join("sha256")
-            .join(hex),
-    )
+/// Store key of a tag, holding the tagged manifest digest:
+/// `<name>/tags/<tag>`.
+pub(crate) fn tag_key(name: &str, tag: &str) -> Option<String> {
+    if !validate_repository_name(name) || !validate_tag_reference(tag) {
+        return None;
+    }
+    Some(format!("{name}/tags/{tag}"))
+}
+
+/// Store key prefix of all tags of a repository.
+pub(crate) fn tags_prefix(name: &str) -> Option<String> {
+    validate_repository_name(name).then(|| format!("{name}/tags/"))
+}
+
+/// Store key marking manifest `digest` as pushed to repository `name`,
+/// tagged or not: `<name>/_manifests/<hex>`.
+pub(crate) fn manifest_link_key(name: &str, digest: &str) -> Option<String> {
+    if !validate_repository_name(name) {
+        return None;
+    }
+    Some(format!("{name}/_manifests/{}", digest_hex(digest)?))
+}
+
+/// Store prefix holding every repository a `resource_matches` pattern can
+/// match. Listings start at a `/`, so `team*` lists from the root.
+pub(crate) fn pattern_prefix(pattern: &str) -> String {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => prefix
+            .rfind('/')
+            .map_or("", |end| &prefix[..=end])
+            .to_string(),
+        None => format!("{pattern}/"),
+    }
+}
+
+/// Marks an existing blob as just written when a push reuses it instead of
+/// uploading it, so a GC sweep before the manifest PUT does not take it for
+/// an unreferenced leftover. Touching costs a write (a copy on S3), so blobs
+/// well within the GC grace period are left alone.
+async fn refresh_blob(digest: &str) {
+    let Some(key) = blob_key(digest) else {
+        return;
+    };
+    let stale = match DOCKER_STORE.modified(&key).await {
+        Ok(Some(modified)) => modified
+            .elapsed()
+            .is_ok_and(|age| age > gc::grace_period() / 2),
+        _ => false,
+    };
+    if stale && let Err(e) = DOCKER_STORE.touch(&key).await {
+        tracing::warn!("failed to refresh blob {digest}: {e}");
+    }
 }
 
 async fn blob_exists(digest: &str) -> bool {
-    let Some(path) = blob_path(digest) else {
+    let Some(key) = blob_key(digest) else {
         return false;
     };
-    tokio::fs::metadata(path).await.is_ok()
+    matches!(DOCKER_STORE.size(&key).await, Ok(Some(_)))
 }
 
 fn validate_digest(digest: &str) -> bool {
@@ -56,13 +125,6 @@ fn digest_hex(digest: &str) -> Option<&str> {
     digest.strip_prefix("sha256:")
 }
 
-fn repository_path(name: &str) -> Option<PathBuf> {
-    if !validate_repository_name(name) {
-        return None;
-    }
-    Some(PathBuf::from(DOCKER_STORAGE_ROOT.as_str()).join(name))
-}
-
 fn validate_repository_name(name: &str) -> bool {
     if name.is_empty() || name.contains('\\') {
         return false;
@@ -93,6 +155,7 @@ pub struct DigestQuery {
         registry::catalog::handle,
         registry::check::handle_get,
         registry::check::handle_head,
+        registry::inspect::handle,
         registry::tags::handle,
         blob::check_exists::handle,
         blob::retrieve::handle,
@@ -105,6 +168,7 @@ pub struct DigestQuery {
         manifest::get_image::handle,
         manifest::put_image::handle,
         manifest::delete_image::handle,
+        manifest::referrers::handle,
     ),
     tags(
         (name = "docker - blob", description = "Docker blob endpoints"),
@@ -141,4 +205,7 @@ pub fn scope() -> impl HttpServiceFactory {
         .service(manifest::get_image::handle)
         .service(manifest::put_image::handle)
         .service(manifest::delete_image::handle)
+        .service(manifest::referrers::handle)
+        // Inspection
+        .service(registry::inspect::handle)
 }
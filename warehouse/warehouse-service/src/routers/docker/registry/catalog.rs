@@ -38,7 +38,7 @@ pub async fn handle(req: HttpRequest) -> impl Responder {
     let n = query.as_ref().and_then(|q| q.n).unwrap_or(100);
     let last = query.as_ref().and_then(|q| q.last.clone());
 
-    let repos = list_repositories();
+    let repos = list_repositories().await;
 
     let start = last
         .as_ref()
@@ -0,0 +1,372 @@
+//! Image inspection: the manifest of a tag resolved down to its platform
+//! images, each with its layers and the contents of its config blob.
+//!
+//! Only stored content is inspected; children of an index that were never
+//! pushed (or pulled through) are listed with `stored: false`.
+
+use crate::domain::docker_error;
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::{
+    blob_key, manifest_key, tag_key, validate_digest, validate_repository_name,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, get, web};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Upper bound for a config blob read into memory; real ones are a few
+/// kilobytes, but the blob store holds whatever was pushed.
+const MAX_CONFIG_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageInspection {
+    pub name: String,
+    pub reference: String,
+    /// Digest of the manifest the reference points to.
+    pub digest: String,
+    pub media_type: Option<String>,
+    /// One entry for a single-platform image, one per child of an index.
+    pub platforms: Vec<ImagePlatform>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImagePlatform {
+    pub os: Option<String>,
+    pub architecture: Option<String>,
+    pub variant: Option<String>,
+    /// Digest of the platform image manifest.
+    pub digest: String,
+    pub media_type: Option<String>,
+    /// `false` when the manifest is listed in the index but not stored.
+    pub stored: bool,
+    /// Sum of the compressed layer sizes.
+    pub compressed_size: u64,
+    pub layers: Vec<ImageLayer>,
+    /// Contents of the config blob; absent for artifacts or a missing blob.
+    pub config: Option<ImageConfig>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageLayer {
+    pub digest: String,
+    pub media_type: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImageConfig {
+    pub digest: String,
+    pub created: Option<String>,
+    pub author: Option<String>,
+    pub env: Vec<String>,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    pub exposed_ports: Vec<String>,
+    pub history: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HistoryEntry {
+    pub created: Option<String>,
+    pub created_by: Option<String>,
+    pub comment: Option<String>,
+    /// The step did not produce a layer (e.g. `ENV`, `CMD`).
+    pub empty_layer: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InspectError {
+    InvalidName,
+    InvalidReference,
+    NotFound,
+    Storage,
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "inspect_image",
+    tag = "docker - registry",
+    path = "/{name}/inspect/{reference}",
+    params(
+        ("name" = String, Path, description = "Repository name (may contain slashes)"),
+        ("reference" = String, Path, description = "Tag or digest"),
+    ),
+    responses(
+        (status = 200, description = "Platforms, layers, config and history of the image", body = ImageInspection),
+        (status = 400, description = "Invalid name or reference"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Repository or manifest not found"),
+        (status = 500, description = "Storage failure"),
+    )
+)]
+#[get("/{name:.+}/inspect/{reference}")]
+pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, reference) = path.into_inner();
+    match inspect_image(&name, &reference).await {
+        Ok(inspection) => HttpResponse::Ok().json(inspection),
+        Err(InspectError::InvalidName) => docker_error::response(
+            StatusCode::BAD_REQUEST,
+            docker_error::NAME_UNKNOWN,
+            "invalid repository name",
+        ),
+        Err(InspectError::InvalidReference) => docker_error::response(
+            StatusCode::BAD_REQUEST,
+            docker_error::UNSUPPORTED,
+            "invalid manifest reference",
+        ),
+        Err(InspectError::NotFound) => docker_error::response(
+            StatusCode::NOT_FOUND,
+            docker_error::MANIFEST_UNKNOWN,
+            "manifest unknown",
+        ),
+        Err(InspectError::Storage) => docker_error::response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            docker_error::UNSUPPORTED,
+            "internal server error",
+        ),
+    }
+}
+
+/// Resolves `reference` (tag or digest) in repository `name` and reads the
+/// manifests and config blobs below it.
+pub(crate) async fn inspect_image(
+    name: &str,
+    reference: &str,
+) -> Result<ImageInspection, InspectError> {
+    if !validate_repository_name(name) {
+        return Err(InspectError::InvalidName);
+    }
+
+    let digest = if reference.starts_with("sha256:") {
+        if !validate_digest(reference) {
+            return Err(InspectError::InvalidReference);
+        }
+        reference.to_string()
+    } else {
+        let key = tag_key(name, reference).ok_or(InspectError::InvalidReference)?;
+        let data = get(&key).await?.ok_or(InspectError::NotFound)?;
+        String::from_utf8_lossy(&data).trim().to_string()
+    };
+
+    let manifest = read_manifest(&digest)
+        .await?
+        .ok_or(InspectError::NotFound)?;
+    let media_type = string(&manifest, "mediaType");
+
+    let platforms = match manifest.get("manifests").and_then(Value::as_array) {
+        Some(children) => {
+            let mut platforms = Vec::new();
+            for child in children {
+                if is_attestation(child) {
+                    continue;
+                }
+                let Some(child_digest) = child
+                    .get("digest")
+                    .and_then(Value::as_str)
+                    .filter(|d| validate_digest(d))
+                else {
+                    continue;
+                };
+                let platform = child.get("platform");
+                let field = |key| platform.and_then(|p| string(p, key));
+
+                let mut entry = match read_manifest(child_digest).await? {
+                    Some(child_manifest) => inspect_platform(child_digest, &child_manifest).await?,
+                    None => ImagePlatform {
+                        os: None,
+                        architecture: None,
+                        variant: None,
+                        digest: child_digest.to_string(),
+                        media_type: string(child, "mediaType"),
+                        stored: false,
+                        compressed_size: 0,
+                        layers: Vec::new(),
+                        config: None,
+                    },
+                };
+                // The index is authoritative for the platform of a child.
+                entry.os = field("os").or(entry.os);
+                entry.architecture = field("architecture").or(entry.architecture);
+                entry.variant = field("variant").or(entry.variant);
+                platforms.push(entry);
+            }
+            platforms
+        }
+        None => vec![inspect_platform(&digest, &manifest).await?],
+    };
+
+    Ok(ImageInspection {
+        name: name.to_string(),
+        reference: reference.to_string(),
+        digest,
+        media_type,
+        platforms,
+    })
+}
+
+async fn inspect_platform(digest: &str, manifest: &Value) -> Result<ImagePlatform, InspectError> {
+    let layers: Vec<ImageLayer> = manifest
+        .get("layers")
+        .and_then(Value::as_array)
+        .map(|layers| {
+            layers
+                .iter()
+                .filter_map(|layer| {
+                    Some(ImageLayer {
+                        digest: string(layer, "digest")?,
+                        media_type: string(layer, "mediaType"),
+                        size: layer.get("size").and_then(Value::as_u64).unwrap_or(0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let config_digest = manifest
+        .get("config")
+        .and_then(|c| string(c, "digest"))
+        .filter(|d| validate_digest(d));
+    let config = match config_digest {
+        Some(config_digest) => read_config(&config_digest).await?,
+        None => None,
+    };
+    let platform = config.as_ref().map(|(_, raw)| raw);
+
+    Ok(ImagePlatform {
+        os: platform.and_then(|p| string(p, "os")),
+        architecture: platform.and_then(|p| string(p, "architecture")),
+        variant: platform.and_then(|p| string(p, "variant")),
+        digest: digest.to_string(),
+        media_type: string(manifest, "mediaType"),
+        stored: true,
+        compressed_size: layers.iter().map(|l| l.size).sum(),
+        layers,
+        config: config.map(|(config, _)| config),
+    })
+}
+
+/// Reads and parses a config blob, returning it with the raw JSON (which
+/// carries the platform fields). `None` when the blob is missing or is not
+/// an image config.
+async fn read_config(digest: &str) -> Result<Option<(ImageConfig, Value)>, InspectError> {
+    let Some(key) = blob_key(digest) else {
+        return Ok(None);
+    };
+    match DOCKER_STORE.size(&key).await {
+        Ok(Some(size)) if size <= MAX_CONFIG_BYTES => {}
+        Ok(_) => return Ok(None),
+        Err(e) => {
+            tracing::error!("failed to stat config blob {digest}: {e}");
+            return Err(InspectError::Storage);
+        }
+    }
+    let Some(data) = get(&key).await? else {
+        return Ok(None);
+    };
+    let Ok(raw) = serde_json::from_slice::<Value>(&data) else {
+        return Ok(None);
+    };
+    if !raw.is_object() {
+        return Ok(None);
+    }
+
+    let container = raw.get("config");
+    let strings = |key: &str| -> Vec<String> {
+        container
+            .and_then(|c| c.get(key))
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let config = ImageConfig {
+        digest: digest.to_string(),
+        created: string(&raw, "created"),
+        author: string(&raw, "author"),
+        env: strings("Env"),
+        entrypoint: strings("Entrypoint"),
+        cmd: strings("Cmd"),
+        working_dir: container.and_then(|c| string(c, "WorkingDir")),
+        user: container.and_then(|c| string(c, "User")),
+        labels: container
+            .and_then(|c| c.get("Labels"))
+            .and_then(Value::as_object)
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        exposed_ports: container
+            .and_then(|c| c.get("ExposedPorts"))
+            .and_then(Value::as_object)
+            .map(|ports| ports.keys().cloned().collect())
+            .unwrap_or_default(),
+        history: raw
+            .get("history")
+            .and_then(Value::as_array)
+            .map(|history| {
+                history
+                    .iter()
+                    .map(|step| HistoryEntry {
+                        created: string(step, "created"),
+                        created_by: string(step, "created_by"),
+                        comment: string(step, "comment"),
+                        empty_layer: step
+                            .get("empty_layer")
+                            .and_then(Value::as_bool)
+                            .unwrap_or(false),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    Ok(Some((config, raw)))
+}
+
+async fn read_manifest(digest: &str) -> Result<Option<Value>, InspectError> {
+    let Some(key) = manifest_key(digest) else {
+        return Ok(None);
+    };
+    Ok(get(&key)
+        .await?
+        .and_then(|data| serde_json::from_slice(&data).ok()))
+}
+
+async fn get(key: &str) -> Result<Option<Vec<u8>>, InspectError> {
+    DOCKER_STORE.get(key).await.map_err(|e| {
+        tracing::error!("failed to read {key}: {e}");
+        InspectError::Storage
+    })
+}
+
+/// Buildx attaches provenance and SBOM attestations to an index as
+/// `unknown/unknown` manifests; they are not runnable platforms.
+fn is_attestation(descriptor: &Value) -> bool {
+    descriptor
+        .get("annotations")
+        .and_then(|a| a.get("vnd.docker.reference.type"))
+        .and_then(Value::as_str)
+        == Some("attestation-manifest")
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
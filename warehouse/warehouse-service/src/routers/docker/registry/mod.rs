@@ -1,4 +1,5 @@
 pub mod catalog;
 pub mod check;
+pub mod inspect;
 pub mod storage;
 pub mod tags;
@@ -1,7 +1,9 @@
-use crate::routers::DOCKER_STORAGE_ROOT;
-use crate::routers::docker::repository_path;
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::{
+    manifest_key, manifest_link_key, tags_prefix, validate_repository_name,
+};
 use serde_json::Value;
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeSet, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum TagListError {
@@ -9,32 +11,45 @@ pub(crate) enum TagListError {
     NotFound,
 }
 
-pub(crate) fn list_repositories() -> Vec<String> {
-    let mut repos = Vec::new();
-    let root = PathBuf::from(DOCKER_STORAGE_ROOT.as_str());
-    collect_repositories(&root, "", &mut repos);
-    repos.sort();
-    repos
+pub(crate) async fn list_repositories() -> Vec<String> {
+    // Repositories are the key prefixes holding at least one tag.
+    let keys = DOCKER_STORE.list("").await.unwrap_or_default();
+    let repos: BTreeSet<String> = keys
+        .iter()
+        .filter_map(|key| key.rsplit_once('/'))
+        .filter_map(|(dir, _)| dir.strip_suffix("/tags"))
+        .filter(|repo| validate_repository_name(repo))
+        .map(str::to_string)
+        .collect();
+    repos.into_iter().collect()
 }
 
-pub(crate) fn list_tags_for_repository(name: &str) -> Result<Vec<String>, TagListError> {
-    let Some(repo_root) = repository_path(name) else {
+/// Returns `(tag, tag key)` pairs of a repository.
+async fn tag_keys(name: &str) -> Result<Vec<(String, String)>, TagListError> {
+    let Some(prefix) = tags_prefix(name) else {
         return Err(TagListError::InvalidName);
     };
 
-    let repo_path = repo_root.join("tags");
-    if !repo_path.exists() {
+    let keys = DOCKER_STORE.list(&prefix).await.unwrap_or_default();
+    if keys.is_empty() {
         return Err(TagListError::NotFound);
     }
 
-    let mut tags = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(&repo_path) {
-        for entry in entries.flatten() {
-            if let Some(tag) = entry.file_name().to_str() {
-                tags.push(tag.to_string());
-            }
-        }
-    }
+    Ok(keys
+        .into_iter()
+        .filter_map(|key| {
+            let tag = key.strip_prefix(&prefix)?.to_string();
+            (!tag.contains('/')).then_some((tag, key))
+        })
+        .collect())
+}
+
+pub(crate) async fn list_tags_for_repository(name: &str) -> Result<Vec<String>, TagListError> {
+    let mut tags: Vec<String> = tag_keys(name)
+        .await?
+        .into_iter()
+        .map(|(tag, _)| tag)
+        .collect();
 
     tags.sort_by(|a, b| compare_tags_desc(a, b));
     Ok(tags)
@@ -48,89 +63,91 @@ pub(crate) struct TagMetadata {
     pub size_bytes: Option<u64>,
 }
 
-pub(crate) fn list_tag_metadata_for_repository(
+pub(crate) async fn list_tag_metadata_for_repository(
     name: &str,
 ) -> Result<Vec<TagMetadata>, TagListError> {
-    let Some(repo_root) = repository_path(name) else {
-        return Err(TagListError::InvalidName);
-    };
-
-    let tags_path = repo_root.join("tags");
-    if !tags_path.exists() {
-        return Err(TagListError::NotFound);
-    }
-
-    let manifests_root = PathBuf::from(DOCKER_STORAGE_ROOT.as_str())
-        .join("manifests")
-        .join("sha256");
-
     let mut items = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(&tags_path) {
-        for entry in entries.flatten() {
-            let Some(tag) = entry.file_name().to_str().map(str::to_string) else {
-                continue;
-            };
-
-            let digest = match std::fs::read_to_string(entry.path()) {
-                Ok(s) => s.trim().to_string(),
-                Err(_) => String::new(),
-            };
-
-            let mut media_type = None;
-            let mut size_bytes = None;
-            if let Some(hex) = digest.strip_prefix("sha256:") {
-                let manifest_path = manifests_root.join(hex);
-                if let Ok(bytes) = std::fs::read(&manifest_path) {
-                    size_bytes = Some(bytes.len() as u64);
-                    media_type = detect_media_type(&bytes);
-                }
-            }
-
-            items.push(TagMetadata {
-                tag,
-                digest,
-                media_type,
-                size_bytes,
-            });
+    for (tag, key) in tag_keys(name).await? {
+        let digest = match DOCKER_STORE.get(&key).await {
+            Ok(Some(d)) => String::from_utf8_lossy(&d).trim().to_string(),
+            _ => String::new(),
+        };
+
+        let mut media_type = None;
+        let mut size_bytes = None;
+        if let Some(manifest_key) = manifest_key(&digest)
+            && let Ok(Some(bytes)) = DOCKER_STORE.get(&manifest_key).await
+        {
+            size_bytes = Some(bytes.len() as u64);
+            media_type = detect_media_type(&bytes);
         }
+
+        items.push(TagMetadata {
+            tag,
+            digest,
+            media_type,
+            size_bytes,
+        });
     }
 
     items.sort_by(|a, b| compare_tags_desc(&a.tag, &b.tag));
     Ok(items)
 }
 
-fn collect_repositories(dir: &Path, prefix: &str, repos: &mut Vec<String>) {
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-
-            if prefix.is_empty()
-                && matches!(
-                    entry.file_name().to_str(),
-                    Some("blobs" | "manifests" | "_uploads")
-                )
-            {
-                continue;
-            }
+/// Returns the `(repository, digest)` of every manifest link, i.e. of every
+/// manifest pushed to a repository, whether by tag or by digest.
+pub(crate) async fn linked_manifests() -> Vec<(String, String)> {
+    let keys = DOCKER_STORE.list("").await.unwrap_or_default();
+    keys.iter()
+        .filter_map(|key| {
+            let (repository, hex) = key.rsplit_once("/_manifests/")?;
+            let digest = format!("sha256:{hex}");
+            (manifest_link_key(repository, &digest).as_deref() == Some(key.as_str()))
+                .then(|| (repository.to_string(), digest))
+        })
+        .collect()
+}
 
-            let name = entry.file_name();
-            let name = name.to_string_lossy();
-            let repo_name = if prefix.is_empty() {
-                name.to_string()
-            } else {
-                format!("{}/{}", prefix, name)
-            };
+/// Returns every stored `(repository, digest)` manifest reachable from
+/// `roots` through index children and the repository's referrers.
+pub(crate) async fn reachable_manifests(roots: Vec<(String, String)>) -> HashSet<(String, String)> {
+    let mut seen = HashSet::new();
+    let mut queue: VecDeque<(String, String)> = roots.into();
 
-            if path.join("tags").exists() {
-                repos.push(repo_name.clone());
+    while let Some((repository, digest)) = queue.pop_front() {
+        if seen.contains(&(repository.clone(), digest.clone())) {
+            continue;
+        }
+        // Skip digests without a stored manifest, e.g. blobs listed in an
+        // index.
+        let Some(key) = manifest_key(&digest) else {
+            continue;
+        };
+        let Ok(Some(data)) = DOCKER_STORE.get(&key).await else {
+            continue;
+        };
+        seen.insert((repository.clone(), digest.clone()));
+
+        if let Ok(value) = serde_json::from_slice::<Value>(&data)
+            && let Some(children) = value.get("manifests").and_then(|m| m.as_array())
+        {
+            for child in children {
+                if let Some(d) = child.get("digest").and_then(|d| d.as_str()) {
+                    queue.push_back((repository.clone(), d.to_string()));
+                }
             }
+        }
 
-            collect_repositories(&path, &repo_name, repos);
+        let hex = digest.trim_start_matches("sha256:");
+        let prefix = format!("{repository}/_referrers/{hex}/");
+        for referrer in DOCKER_STORE.list(&prefix).await.unwrap_or_default() {
+            if let Some((_, referrer_hex)) = referrer.rsplit_once('/') {
+                queue.push_back((repository.clone(), format!("sha256:{referrer_hex}")));
+            }
         }
     }
+
+    seen
 }
 
 fn detect_media_type(bytes: &[u8]) -> Option<String> {
@@ -43,7 +43,7 @@ pub async fn handle(req: HttpRequest, path: web::Path<String>) -> impl Responder
     let n = query.as_ref().and_then(|q| q.n).unwrap_or(100);
     let last = query.as_ref().and_then(|q| q.last.clone());
 
-    let tags = match list_tags_for_repository(&name) {
+    let tags = match list_tags_for_repository(&name).await {
         Ok(tags) => tags,
         Err(TagListError::InvalidName) => {
             return docker_error::response(
@@ -0,0 +1,429 @@
+//! Signature verification policies for protected repositories.
+//!
+//! Policies are read once at startup from the JSON file at
+//! `DOCKER_SIGNATURE_POLICY_PATH` (default `./signature-policy.json`):
+//!
+//! ```json
+//! {
+//!   "policies": [
+//!     {
+//!       "repository": "prod/*",
+//!       "keys": ["/etc/warehouse/cosign.pub"],
+//!       "enforce": ["pull", "push"]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The first policy whose `repository` pattern matches applies; a trailing
+//! `*` matches by prefix. `keys` are ECDSA P-256 public keys in PEM form (as
+//! written by `cosign generate-key-pair`), given inline or as file paths.
+//! `enforce` defaults to both `pull` and `push`.
+//!
+//! Tags of a protected repository are only served (`pull`) or accepted
+//! (`push`) when the tagged manifest has a cosign signature that verifies
+//! against one of the keys. Signatures are looked up through the cosign
+//! `sha256-<hex>.sig` tag and through the referrers of the manifest. Pulls
+//! and pushes by digest are not checked, so an image can be pushed by digest,
+//! signed, and then tagged.
+
+use crate::domain::docker_error;
+use crate::domain::jwt::resource_matches;
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::manifest::referrers::referrer_digests;
+use crate::routers::docker::{blob_key, manifest_key, tag_key};
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::LazyLock;
+
+const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+
+static POLICIES: LazyLock<Vec<SignaturePolicy>> = LazyLock::new(|| {
+    let path = envmnt::get_or("DOCKER_SIGNATURE_POLICY_PATH", "./signature-policy.json");
+    load_policies(&path).unwrap_or_else(|e| panic!("config error: {e}"))
+});
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operation {
+    Pull,
+    Push,
+}
+
+#[derive(Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    policies: Vec<PolicyConfig>,
+}
+
+#[derive(Deserialize)]
+struct PolicyConfig {
+    repository: String,
+    keys: Vec<String>,
+    enforce: Option<Vec<String>>,
+}
+
+struct SignaturePolicy {
+    repository: String,
+    keys: Vec<VerifyingKey>,
+    pull: bool,
+    push: bool,
+}
+
+/// Why a tag failed its signature policy.
+enum Failure {
+    Unsigned,
+    Unverified,
+}
+
+/// Loads the policies, panicking on an invalid policy file.
+pub fn init() {
+    if !POLICIES.is_empty() {
+        tracing::info!("loaded {} signature policies", POLICIES.len());
+    }
+}
+
+/// Checks tag `tag` of repository `name`, pointing at manifest `digest`,
+/// against the signature policy of the repository. Returns the `DENIED`
+/// response to send when the policy is not satisfied.
+pub(crate) async fn check(
+    name: &str,
+    tag: &str,
+    digest: &str,
+    operation: Operation,
+) -> Result<(), HttpResponse> {
+    // Signatures and attestations cannot sign themselves.
+    if is_cosign_tag(tag) {
+        return Ok(());
+    }
+    let Some(policy) = POLICIES
+        .iter()
+        .find(|p| resource_matches(&p.repository, name))
+    else {
+        return Ok(());
+    };
+    let enforced = match operation {
+        Operation::Pull => policy.pull,
+        Operation::Push => policy.push,
+    };
+    if !enforced {
+        return Ok(());
+    }
+
+    let failure = match verify(name, digest, &policy.keys).await {
+        Ok(None) => return Ok(()),
+        Ok(Some(failure)) => failure,
+        Err(e) => {
+            tracing::error!("failed to verify signatures of {name}@{digest}: {e}");
+            return Err(docker_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                docker_error::UNSUPPORTED,
+                "internal server error",
+            ));
+        }
+    };
+
+    let (message, reason) = match failure {
+        Failure::Unsigned => (
+            "image is not signed",
+            format!("no signature found for {digest}"),
+        ),
+        Failure::Unverified => (
+            "image signature could not be verified",
+            format!("no signature of {digest} verifies against the policy keys"),
+        ),
+    };
+    tracing::warn!(
+        "signature policy `{}` denied {name}:{tag}: {reason}",
+        policy.repository
+    );
+    Err(docker_error::response_with_detail(
+        StatusCode::FORBIDDEN,
+        docker_error::DENIED,
+        message,
+        json!({
+            "policy": policy.repository,
+            "repository": name,
+            "tag": tag,
+            "digest": digest,
+            "reason": reason,
+        }),
+    ))
+}
+
+/// Looks for a signature of `digest` that verifies against one of `keys`.
+async fn verify(
+    name: &str,
+    digest: &str,
+    keys: &[VerifyingKey],
+) -> std::io::Result<Option<Failure>> {
+    let mut candidates = referrer_digests(name, digest).await?;
+    let hex = digest.trim_start_matches("sha256:");
+    if let Some(key) = tag_key(name, &format!("sha256-{hex}.sig"))
+        && let Some(content) = DOCKER_STORE.get(&key).await?
+    {
+        candidates.push(String::from_utf8_lossy(&content).trim().to_string());
+    }
+
+    let mut signed = false;
+    for candidate in candidates {
+        let Some(key) = manifest_key(&candidate) else {
+            continue;
+        };
+        let Some(data) = DOCKER_STORE.get(&key).await? else {
+            continue;
+        };
+        let Ok(manifest) = serde_json::from_slice::<Value>(&data) else {
+            continue;
+        };
+
+        for layer in manifest
+            .get("layers")
+            .and_then(|l| l.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let Some(signature) = layer
+                .get("annotations")
+                .and_then(|a| a.get(SIGNATURE_ANNOTATION))
+                .and_then(|s| s.as_str())
+            else {
+                continue;
+            };
+            let Some(payload_key) = layer
+                .get("digest")
+                .and_then(|d| d.as_str())
+                .and_then(blob_key)
+            else {
+                continue;
+            };
+            let Some(payload) = DOCKER_STORE.get(&payload_key).await? else {
+                continue;
+            };
+            if !payload_signs(&payload, digest) {
+                continue;
+            }
+
+            signed = true;
+            if verifies(&payload, signature, keys) {
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(Some(if signed {
+        Failure::Unverified
+    } else {
+        Failure::Unsigned
+    }))
+}
+
+/// Whether a cosign simple-signing payload names manifest `digest`.
+fn payload_signs(payload: &[u8], digest: &str) -> bool {
+    serde_json::from_slice::<Value>(payload).is_ok_and(|value| {
+        value
+            .pointer("/critical/image/docker-manifest-digest")
+            .and_then(|d| d.as_str())
+            == Some(digest)
+    })
+}
+
+fn verifies(payload: &[u8], signature: &str, keys: &[VerifyingKey]) -> bool {
+    let Ok(bytes) = STANDARD.decode(signature.trim()) else {
+        return false;
+    };
+    // cosign writes ASN.1 DER signatures; accept raw `r || s` as well.
+    let Ok(signature) = Signature::from_der(&bytes).or_else(|_| Signature::from_slice(&bytes))
+    else {
+        return false;
+    };
+    keys.iter()
+        .any(|key| key.verify(payload, &signature).is_ok())
+}
+
+/// Whether `tag` is a cosign signature, attestation or SBOM tag
+/// (`sha256-<hex>.sig`, `.att` or `.sbom`).
+pub(crate) fn is_cosign_tag(tag: &str) -> bool {
+    cosign_subject(tag).is_some()
+}
+
+/// The digest of the manifest a cosign tag belongs to.
+pub(crate) fn cosign_subject(tag: &str) -> Option<String> {
+    let rest = tag.strip_prefix("sha256-")?;
+    let hex = [".sig", ".att", ".sbom"]
+        .iter()
+        .find_map(|suffix| rest.strip_suffix(suffix))?;
+    (hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+        .then(|| format!("sha256:{hex}"))
+}
+
+fn load_policies(path: &str) -> Result<Vec<SignaturePolicy>, String> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {path}: {e}")),
+    };
+    let file: PolicyFile = serde_json::from_slice(&data)
+        .map_err(|e| format!("invalid signature policy {path}: {e}"))?;
+
+    file.policies
+        .into_iter()
+        .map(|policy| {
+            if policy.keys.is_empty() {
+                return Err(format!(
+                    "signature policy `{}` has no keys",
+                    policy.repository
+                ));
+            }
+            let keys = policy
+                .keys
+                .iter()
+                .map(|key| load_key(key))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("signature policy `{}`: {e}", policy.repository))?;
+
+            let enforce = policy
+                .enforce
+                .unwrap_or_else(|| vec!["pull".to_string(), "push".to_string()]);
+            if let Some(other) = enforce.iter().find(|op| *op != "pull" && *op != "push") {
+                return Err(format!(
+                    "signature policy `{}`: unknown operation `{other}`",
+                    policy.repository
+                ));
+            }
+
+            Ok(SignaturePolicy {
+                pull: enforce.iter().any(|op| op == "pull"),
+                push: enforce.iter().any(|op| op == "push"),
+                repository: policy.repository,
+                keys,
+            })
+        })
+        .collect()
+}
+
+/// Parses an inline PEM public key, or reads one from a file.
+fn load_key(key: &str) -> Result<VerifyingKey, String> {
+    let pem = if key.trim_start().starts_with("-----BEGIN") {
+        key.to_string()
+    } else {
+        std::fs::read_to_string(key).map_err(|e| format!("failed to read key {key}: {e}"))?
+    };
+    VerifyingKey::from_public_key_pem(pem.trim())
+        .map_err(|e| format!("invalid ECDSA P-256 public key {key}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cosign_subject, is_cosign_tag, payload_signs, verifies};
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::{Signature, SigningKey, VerifyingKey};
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 32]).expect("seed should be a valid scalar")
+    }
+
+    fn payload(digest: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "critical": {
+                "identity": { "docker-reference": "registry.local/prod/app" },
+                "image": { "docker-manifest-digest": digest },
+                "type": "cosign container image signature"
+            },
+            "optional": null
+        }))
+        .expect("payload should serialize")
+    }
+
+    #[test]
+    fn payload_signs_matches_the_manifest_digest() {
+        assert!(payload_signs(&payload(DIGEST), DIGEST));
+        assert!(!payload_signs(&payload(DIGEST), "sha256:other"));
+        assert!(!payload_signs(b"not json", DIGEST));
+        assert!(!payload_signs(br#"{"critical":{}}"#, DIGEST));
+    }
+
+    #[test]
+    fn verifies_der_signatures() {
+        let key = signing_key(1);
+        let payload = payload(DIGEST);
+        let signature: Signature = key.sign(&payload);
+        let encoded = STANDARD.encode(signature.to_der().as_bytes());
+
+        assert!(verifies(&payload, &encoded, &[*key.verifying_key()]));
+    }
+
+    #[test]
+    fn verifies_raw_signatures() {
+        let key = signing_key(1);
+        let payload = payload(DIGEST);
+        let signature: Signature = key.sign(&payload);
+        let encoded = STANDARD.encode(signature.to_bytes());
+
+        assert!(verifies(&payload, &encoded, &[*key.verifying_key()]));
+    }
+
+    #[test]
+    fn verifies_against_any_of_the_keys() {
+        let key = signing_key(1);
+        let other: VerifyingKey = *signing_key(2).verifying_key();
+        let payload = payload(DIGEST);
+        let signature: Signature = key.sign(&payload);
+        let encoded = STANDARD.encode(signature.to_der().as_bytes());
+
+        assert!(verifies(&payload, &encoded, &[other, *key.verifying_key()]));
+        assert!(!verifies(&payload, &encoded, &[other]));
+        assert!(!verifies(&payload, &encoded, &[]));
+    }
+
+    #[test]
+    fn rejects_tampered_or_malformed_signatures() {
+        let key = signing_key(1);
+        let keys = [*key.verifying_key()];
+        let payload = payload(DIGEST);
+        let signature: Signature = key.sign(&payload);
+        let encoded = STANDARD.encode(signature.to_der().as_bytes());
+
+        assert!(!verifies(&self::payload("sha256:other"), &encoded, &keys));
+        assert!(!verifies(&payload, "not base64!", &keys));
+        assert!(!verifies(&payload, &STANDARD.encode(b"short"), &keys));
+    }
+
+    #[test]
+    fn cosign_tags_need_a_full_digest() {
+        let hex = &DIGEST["sha256:".len()..];
+        for suffix in ["sig", "att", "sbom"] {
+            assert!(is_cosign_tag(&format!("sha256-{hex}.{suffix}")));
+        }
+
+        assert!(!is_cosign_tag(&format!("sha256-{hex}.txt")));
+        assert!(!is_cosign_tag(&format!("sha256-{hex}")));
+        assert!(!is_cosign_tag(&format!("sha256-{}.sig", &hex[1..])));
+        assert!(!is_cosign_tag(&format!("sha256-{hex}0.sig")));
+        assert!(!is_cosign_tag(&format!(
+            "sha256-{}.sig",
+            hex.to_uppercase()
+        )));
+        assert!(!is_cosign_tag(&format!("sha512-{hex}.sig")));
+        assert!(!is_cosign_tag("sha256-release.sig"));
+        assert!(!is_cosign_tag("latest"));
+    }
+
+    #[test]
+    fn cosign_subject_is_the_signed_digest() {
+        let hex = &DIGEST["sha256:".len()..];
+        assert_eq!(
+            cosign_subject(&format!("sha256-{hex}.att")).as_deref(),
+            Some(DIGEST)
+        );
+        assert_eq!(cosign_subject("v1.0"), None);
+    }
+}
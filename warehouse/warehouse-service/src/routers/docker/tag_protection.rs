@@ -0,0 +1,268 @@
+//! Tag immutability rules.
+//!
+//! Rules are read once at startup from the JSON file at
+//! `DOCKER_TAG_PROTECTION_PATH` (default `./tag-protection.json`):
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "repository": "prod/*" },
+//!     { "repository": "team/*", "tags": "^v?\\d+\\.\\d+\\.\\d+$" }
+//!   ]
+//! }
+//! ```
+//!
+//! The first rule whose `repository` pattern matches applies; a trailing `*`
+//! matches by prefix. It protects the tags matching its `tags` regex, or every
+//! tag when `tags` is omitted. A protected tag cannot be re-pushed with a
+//! different digest, and the manifest it points at (or any platform manifest
+//! of the index it points at) cannot be deleted through any repository. Tokens
+//! carrying the `admin:*:*` scope bypass both checks. Cosign signature tags
+//! (`sha256-<hex>.sig` and friends) are rewritten on every signing and are
+//! never protected.
+
+use crate::domain::docker_error;
+use crate::domain::jwt::{Claims, resource_matches};
+use crate::routers::DOCKER_STORE;
+use crate::routers::docker::signatures::is_cosign_tag;
+use crate::routers::docker::{manifest_key, pattern_prefix, tag_key};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+
+static RULES: LazyLock<Vec<ProtectionRule>> = LazyLock::new(|| {
+    let path = envmnt::get_or("DOCKER_TAG_PROTECTION_PATH", "./tag-protection.json");
+    load_rules(&path).unwrap_or_else(|e| panic!("config error: {e}"))
+});
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleConfig>,
+}
+
+#[derive(Deserialize)]
+struct RuleConfig {
+    repository: String,
+    tags: Option<String>,
+}
+
+struct ProtectionRule {
+    repository: String,
+    tags: Option<Regex>,
+}
+
+/// Loads the rules, panicking on an invalid rules file.
+pub fn init() {
+    if !RULES.is_empty() {
+        tracing::info!("loaded {} tag protection rules", RULES.len());
+    }
+}
+
+/// Returns the repository pattern of the rule protecting `name:tag`, if any.
+pub(crate) fn protecting_rule(name: &str, tag: &str) -> Option<&'static str> {
+    if is_cosign_tag(tag) {
+        return None;
+    }
+    let rule = RULES
+        .iter()
+        .find(|r| resource_matches(&r.repository, name))?;
+    rule.tags
+        .as_ref()
+        .is_none_or(|re| re.is_match(tag))
+        .then_some(rule.repository.as_str())
+}
+
+/// Rejects pointing protected tag `name:tag` at a different digest than the
+/// one it already holds.
+pub(crate) async fn check_push(
+    req: &HttpRequest,
+    name: &str,
+    tag: &str,
+    digest: &str,
+) -> Result<(), HttpResponse> {
+    let Some(rule) = protecting_rule(name, tag) else {
+        return Ok(());
+    };
+    let Some(key) = tag_key(name, tag) else {
+        return Ok(());
+    };
+    let current = match DOCKER_STORE.get(&key).await {
+        Ok(Some(content)) => String::from_utf8_lossy(&content).trim().to_string(),
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            tracing::error!("failed to read tag {name}:{tag}: {e}");
+            return Err(internal_error());
+        }
+    };
+    if current == digest {
+        return Ok(());
+    }
+    if is_admin(req) {
+        tracing::warn!("admin override: re-pushing immutable tag {name}:{tag}");
+        return Ok(());
+    }
+
+    Err(docker_error::response_with_detail(
+        StatusCode::FORBIDDEN,
+        docker_error::DENIED,
+        "tag is immutable",
+        json!({
+            "rule": rule,
+            "repository": name,
+            "tag": tag,
+            "digest": current,
+            "reason": format!("{name}:{tag} already points at {current}"),
+        }),
+    ))
+}
+
+/// Rejects deleting manifest `digest` through repository `name` while a
+/// protected tag points at it, or at an index containing it. Manifests are
+/// stored once for all repositories, so the protected tags of every
+/// repository covered by a rule are checked, not only those of `name`.
+pub(crate) async fn check_delete(
+    req: &HttpRequest,
+    name: &str,
+    digest: &str,
+) -> Result<(), HttpResponse> {
+    let protected = match protected_references(digest).await {
+        Ok(protected) => protected,
+        Err(e) => {
+            tracing::error!("failed to list protected tags: {e}");
+            return Err(internal_error());
+        }
+    };
+
+    let Some((rule, _)) = protected.first() else {
+        return Ok(());
+    };
+    let tags: Vec<&str> = protected.iter().map(|(_, tag)| tag.as_str()).collect();
+    if is_admin(req) {
+        tracing::warn!(
+            "admin override: deleting {name}@{digest} tagged by protected {}",
+            tags.join(", ")
+        );
+        return Ok(());
+    }
+
+    Err(docker_error::response_with_detail(
+        StatusCode::FORBIDDEN,
+        docker_error::DENIED,
+        "manifest is referenced by a protected tag",
+        json!({
+            "rule": rule,
+            "repository": name,
+            "digest": digest,
+            "tags": tags,
+        }),
+    ))
+}
+
+/// Returns `(rule, "repository:tag")` of every protected tag pointing at
+/// `digest` directly or through an index.
+async fn protected_references(digest: &str) -> std::io::Result<Vec<(&'static str, String)>> {
+    // Only repositories under a rule can hold protected tags; list those
+    // prefixes instead of the whole store.
+    let mut keys = BTreeSet::new();
+    for rule in RULES.iter() {
+        keys.extend(DOCKER_STORE.list(&pattern_prefix(&rule.repository)).await?);
+    }
+
+    let mut indexes: HashMap<String, bool> = HashMap::new();
+    let mut protected = Vec::new();
+    for key in keys {
+        let Some((repository, tag)) = key.rsplit_once("/tags/") else {
+            continue;
+        };
+        if tag.contains('/') || tag_key(repository, tag).as_deref() != Some(key.as_str()) {
+            continue;
+        }
+        let Some(rule) = protecting_rule(repository, tag) else {
+            continue;
+        };
+        let Some(content) = DOCKER_STORE.get(&key).await? else {
+            continue;
+        };
+        let tagged = String::from_utf8_lossy(&content).trim().to_string();
+
+        let references = if tagged == digest {
+            true
+        } else if let Some(&contains) = indexes.get(&tagged) {
+            contains
+        } else {
+            let contains = index_contains(&tagged, digest).await?;
+            indexes.insert(tagged, contains);
+            contains
+        };
+        if references {
+            protected.push((rule, format!("{repository}:{tag}")));
+        }
+    }
+    Ok(protected)
+}
+
+/// Whether manifest `index` is an image index listing `digest`.
+async fn index_contains(index: &str, digest: &str) -> std::io::Result<bool> {
+    let Some(key) = manifest_key(index) else {
+        return Ok(false);
+    };
+    let Some(data) = DOCKER_STORE.get(&key).await? else {
+        return Ok(false);
+    };
+    let Ok(manifest) = serde_json::from_slice::<Value>(&data) else {
+        return Ok(false);
+    };
+    Ok(manifest
+        .get("manifests")
+        .and_then(Value::as_array)
+        .is_some_and(|children| {
+            children
+                .iter()
+                .any(|child| child.get("digest").and_then(Value::as_str) == Some(digest))
+        }))
+}
+
+fn is_admin(req: &HttpRequest) -> bool {
+    req.extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.allows("admin", "*", "*"))
+}
+
+fn internal_error() -> HttpResponse {
+    docker_error::response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        docker_error::UNSUPPORTED,
+        "internal server error",
+    )
+}
+
+fn load_rules(path: &str) -> Result<Vec<ProtectionRule>, String> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {path}: {e}")),
+    };
+    let file: RulesFile = serde_json::from_slice(&data)
+        .map_err(|e| format!("invalid tag protection rules {path}: {e}"))?;
+
+    file.rules
+        .into_iter()
+        .map(|rule| {
+            let tags = rule
+                .tags
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("invalid tags pattern for `{}`: {e}", rule.repository))?;
+            Ok(ProtectionRule {
+                repository: rule.repository,
+                tags,
+            })
+        })
+        .collect()
+}
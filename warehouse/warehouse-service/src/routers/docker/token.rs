@@ -1,3 +1,4 @@
+use crate::domain::auth_store::{AUTH_STORE, Identity};
 use crate::domain::jwt::{Claims, JwtConfig};
 use actix_web::http::header;
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
@@ -10,7 +11,8 @@ use utoipa::ToSchema;
 #[derive(Deserialize, ToSchema)]
 pub struct TokenQuery {
     pub service: String,
-    pub scope: Option<String>,
+    // `scope` may repeat (e.g. when mounting from another repository), which
+    // a plain field cannot deserialize; see `requested_scope`.
     pub account: Option<String>,
     pub client_id: Option<String>,
     pub offline_token: Option<bool>,
@@ -30,7 +32,8 @@ pub struct TokenResponse {
     tags = ["docker"],
     params(
         ("service" = String, Query, description = "Registry service name"),
-        ("scope" = String, Query, description = "Requested repository scope")
+        ("scope" = String, Query, description = "Requested repository or crate scope; may be repeated"),
+        ("offline_token" = Option<bool>, Query, description = "Issue a long-lived token (e.g. for cargo)")
     ),
     responses(
         (status = 200, description = "JWT token issued", body = TokenResponse),
@@ -44,10 +47,19 @@ pub async fn handle(
     config: web::Data<JwtConfig>,
     query: web::Query<TokenQuery>,
 ) -> impl Responder {
-    // Validate Basic authentication (or allow anonymous if disabled)
-    let username = match validate_basic(&req, &config) {
-        Some(u) => u,
-        None => {
+    // Validate Basic authentication (or allow anonymous if disabled). Password
+    // hashing is slow, so it runs on the blocking pool.
+    let candidates = basic_credentials(&req);
+    let verify_config = config.clone();
+    let identity = web::block(move || {
+        candidates
+            .iter()
+            .find_map(|encoded| validate_basic_encoded(encoded, &verify_config))
+    })
+    .await;
+    let identity = match identity {
+        Ok(Some(identity)) => identity,
+        _ => {
             return HttpResponse::Unauthorized()
                 .append_header(("WWW-Authenticate", "Basic realm=\"registry\""))
                 .finish();
@@ -59,13 +71,25 @@ pub async fn handle(
         return HttpResponse::BadRequest().finish();
     }
 
+    // Offline tokens are long-lived so they can be stored by `cargo login`
+    // or `warehouse crates login`.
+    let ttl = if query.offline_token.unwrap_or(false) {
+        config.offline_token_ttl
+    } else {
+        600
+    };
+
     let now = Utc::now();
-    let exp = now + Duration::minutes(10);
+    let exp = now + Duration::seconds(ttl);
+
+    // Only the requested actions the account (or API token) holds end up in
+    // the issued token; the registry answers 403 for anything else.
+    let requested = requested_scope(&req).unwrap_or("docker".to_string());
 
     let claims = Claims {
-        sub: username,
+        scope: identity.grant(&requested),
+        sub: identity.login,
         service: query.service.clone(),
-        scope: query.scope.clone().unwrap_or("docker".to_string()),
         iat: now.timestamp() as usize,
         exp: exp.timestamp() as usize,
     };
@@ -79,42 +103,48 @@ pub async fn handle(
 
     HttpResponse::Ok().json(TokenResponse {
         token,
-        expires_in: 600,
+        expires_in: ttl as usize,
         issued_at: now.to_rfc3339(),
     })
 }
 
-fn validate_basic(req: &HttpRequest, config: &JwtConfig) -> Option<String> {
+/// Joins every `scope` query parameter into one space-separated scope.
+fn requested_scope(req: &HttpRequest) -> Option<String> {
+    let params = web::Query::<Vec<(String, String)>>::from_query(req.query_string()).ok()?;
+    let scopes: Vec<&str> = params
+        .iter()
+        .filter(|(key, _)| key == "scope")
+        .map(|(_, value)| value.as_str())
+        .collect();
+    (!scopes.is_empty()).then(|| scopes.join(" "))
+}
+
+/// Encoded Basic credentials to try, in order.
+fn basic_credentials(req: &HttpRequest) -> Vec<String> {
+    let mut candidates = Vec::new();
+
     // 1. Try Authorization header
     if let Some(header_value) = req
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
         && let Some(encoded) = header_value.strip_prefix("Basic ")
-        && let Some(username) = validate_basic_encoded(encoded, config)
     {
-        return Some(username);
+        candidates.push(encoded.to_string());
     }
 
     // 2. Fallback to HttpOnly cookie
-    if let Some(cookie) = req.cookie("warehouse_ui_session")
-        && let Some(username) = validate_basic_encoded(cookie.value(), config)
-    {
-        return Some(username);
+    if let Some(cookie) = req.cookie("warehouse_ui_session") {
+        candidates.push(cookie.value().to_string());
     }
 
-    None
+    candidates
 }
 
-fn validate_basic_encoded(encoded: &str, config: &JwtConfig) -> Option<String> {
+fn validate_basic_encoded(encoded: &str, config: &JwtConfig) -> Option<Identity> {
     let decoded = STANDARD.decode(encoded).ok()?;
     let creds = String::from_utf8(decoded).ok()?;
     let (username, password) = creds.split_once(':')?;
 
-    if config.username.as_deref() == Some(username) && config.password.as_deref() == Some(password)
-    {
-        Some(username.to_string())
-    } else {
-        None
-    }
+    AUTH_STORE.authenticate_password(config, username, password)
 }
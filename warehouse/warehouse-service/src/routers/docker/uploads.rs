@@ -0,0 +1,235 @@
+//! Blob upload sessions, staged in the Docker store.
+//!
+//! A session is created by `POST /v2/<name>/blobs/uploads/` and removed when
+//! the upload completes or is cancelled. Every chunk is stored as a part
+//! named after its offset, `_uploads/<repository>/<uuid>/<offset>`, and the
+//! parts are composed into the blob once its digest is verified. Sessions
+//! live in the store like the blobs, so any replica can serve any request
+//! of a push.
+//!
+//! Interrupted pushes leave their session behind, so a background reaper
+//! removes sessions that have been idle for longer than
+//! `DOCKER_UPLOAD_TTL_SECONDS` (default 86400; 0 keeps them forever). It
+//! runs every `DOCKER_UPLOAD_REAP_INTERVAL_SECONDS` (default 300).
+//!
+//! Session times come from the parts: the write time of the first part
+//! (an empty one until the first chunk arrives) as creation, and that of
+//! the last part as last activity.
+
+use super::validate_repository_name;
+use crate::routers::DOCKER_STORE;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+static UPLOAD_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(
+        envmnt::get_or("DOCKER_UPLOAD_TTL_SECONDS", "86400")
+            .parse()
+            .unwrap_or(86400),
+    )
+});
+
+const UPLOADS_PREFIX: &str = "_uploads/";
+
+/// Parts are hashed this many bytes at a time.
+const HASH_BLOCK_BYTES: u64 = 8 * 1024 * 1024;
+
+/// One upload session of a repository.
+pub(crate) struct Session {
+    prefix: String,
+}
+
+impl Session {
+    /// `None` when the repository name or the uuid is invalid.
+    pub(crate) fn new(name: &str, uuid: &str) -> Option<Self> {
+        if !validate_repository_name(name) || Uuid::parse_str(uuid).is_err() {
+            return None;
+        }
+        Some(Self {
+            prefix: format!("{UPLOADS_PREFIX}{name}/{uuid}/"),
+        })
+    }
+
+    /// Creates the session with an empty first part.
+    pub(crate) async fn create(&self) -> io::Result<()> {
+        DOCKER_STORE.put(&self.part_key(0), Vec::new()).await
+    }
+
+    /// `(key, size)` of every part in offset order; empty when the session
+    /// does not exist.
+    pub(crate) async fn parts(&self) -> io::Result<Vec<(String, u64)>> {
+        DOCKER_STORE.list_sizes(&self.prefix).await
+    }
+
+    /// Adds `data` at `offset`, the number of bytes received so far.
+    pub(crate) async fn append(&self, offset: u64, data: Vec<u8>) -> io::Result<()> {
+        DOCKER_STORE.put(&self.part_key(offset), data).await
+    }
+
+    /// Stores the content of `parts` as object `key` and ends the session.
+    pub(crate) async fn finish(&self, key: &str, parts: &[(String, u64)]) -> io::Result<()> {
+        let keys: Vec<String> = parts.iter().map(|(key, _)| key.clone()).collect();
+        DOCKER_STORE.compose(key, &keys).await?;
+        self.remove().await
+    }
+
+    /// Deletes every part.
+    pub(crate) async fn remove(&self) -> io::Result<()> {
+        for (key, _) in self.parts().await? {
+            DOCKER_STORE.delete(&key).await?;
+        }
+        Ok(())
+    }
+
+    fn part_key(&self, offset: u64) -> String {
+        // Zero-padded so that listing returns the parts in offset order.
+        format!("{}{offset:020}", self.prefix)
+    }
+}
+
+/// Total size of `parts`.
+pub(crate) fn received(parts: &[(String, u64)]) -> u64 {
+    parts.iter().map(|(_, size)| size).sum()
+}
+
+/// `sha256:<hex>` of the content of `parts`; `None` when a part vanished,
+/// e.g. because the session was completed concurrently.
+pub(crate) async fn content_digest(parts: &[(String, u64)]) -> io::Result<Option<String>> {
+    let mut hasher = Sha256::new();
+    for (key, size) in parts {
+        let mut start = 0;
+        while start < *size {
+            let end = (start + HASH_BLOCK_BYTES).min(*size) - 1;
+            let Some(block) = DOCKER_STORE.get_range(key, start, end).await? else {
+                return Ok(None);
+            };
+            hasher.update(&block);
+            start = end + 1;
+        }
+    }
+    Ok(Some(format!("sha256:{:x}", hasher.finalize())))
+}
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct UploadSession {
+    pub(crate) repository: String,
+    pub(crate) uuid: String,
+    /// Bytes received so far.
+    pub(crate) size_bytes: u64,
+    pub(crate) created_at: String,
+    pub(crate) last_activity_at: String,
+    pub(crate) age_seconds: u64,
+    pub(crate) idle_seconds: u64,
+}
+
+/// Idle time after which sessions are reaped; `None` when reaping is off.
+pub(crate) fn ttl() -> Option<Duration> {
+    (!UPLOAD_TTL.is_zero()).then_some(*UPLOAD_TTL)
+}
+
+/// Lists every staged upload session, oldest first.
+pub(crate) async fn list_sessions() -> io::Result<Vec<UploadSession>> {
+    // (repository, uuid) -> (size, first part, last part)
+    let mut staged: BTreeMap<(String, String), (u64, String, String)> = BTreeMap::new();
+    for (key, size) in DOCKER_STORE.list_sizes(UPLOADS_PREFIX).await? {
+        let Some((session, _)) = key
+            .strip_prefix(UPLOADS_PREFIX)
+            .and_then(|rest| rest.rsplit_once('/'))
+        else {
+            continue;
+        };
+        let Some((repository, uuid)) = session.rsplit_once('/') else {
+            continue;
+        };
+        if Session::new(repository, uuid).is_none() {
+            continue;
+        }
+        staged
+            .entry((repository.to_string(), uuid.to_string()))
+            .and_modify(|(total, _, last)| {
+                *total += size;
+                last.clone_from(&key);
+            })
+            .or_insert_with(|| (size, key.clone(), key.clone()));
+    }
+
+    let now = SystemTime::now();
+    let mut sessions = Vec::new();
+    for ((repository, uuid), (size_bytes, first, last)) in staged {
+        // Completed or cancelled while listing.
+        let (Some(created), Some(modified)) = (
+            DOCKER_STORE.modified(&first).await?,
+            DOCKER_STORE.modified(&last).await?,
+        ) else {
+            continue;
+        };
+        sessions.push(UploadSession {
+            repository,
+            uuid,
+            size_bytes,
+            created_at: DateTime::<Utc>::from(created).to_rfc3339(),
+            last_activity_at: DateTime::<Utc>::from(modified).to_rfc3339(),
+            age_seconds: elapsed(now, created),
+            idle_seconds: elapsed(now, modified),
+        });
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.age_seconds));
+    Ok(sessions)
+}
+
+/// Removes sessions idle for longer than `ttl`; returns how many were removed.
+pub(crate) async fn reap(ttl: Duration) -> io::Result<usize> {
+    let mut removed = 0;
+    for session in list_sessions().await? {
+        if session.idle_seconds < ttl.as_secs() {
+            continue;
+        }
+        let Some(staged) = Session::new(&session.repository, &session.uuid) else {
+            continue;
+        };
+        staged.remove().await?;
+        tracing::info!(
+            "reaped upload session {} of {} ({} bytes, idle {}s)",
+            session.uuid,
+            session.repository,
+            session.size_bytes,
+            session.idle_seconds
+        );
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Starts the stale upload reaper unless the TTL is 0.
+pub fn start() {
+    let Some(ttl) = ttl() else {
+        return;
+    };
+    let interval: u64 = envmnt::get_or("DOCKER_UPLOAD_REAP_INTERVAL_SECONDS", "300")
+        .parse()
+        .unwrap_or(300);
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+        loop {
+            ticker.tick().await;
+            match reap(ttl).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("reaped {removed} stale upload sessions"),
+                Err(e) => tracing::error!("failed to reap stale upload sessions: {e}"),
+            }
+        }
+    });
+}
+
+fn elapsed(now: SystemTime, since: SystemTime) -> u64 {
+    now.duration_since(since).unwrap_or_default().as_secs()
+}
@@ -0,0 +1,305 @@
+//! Generic file repositories for artifacts that are neither images nor
+//! crates: release tarballs, firmware, test fixtures ...
+//!
+//! A repository is created by the first upload into it. Files are addressed
+//! by a `/`-separated path inside the repository; directories only exist as
+//! prefixes of stored files.
+//!
+//! Store layout:
+//! - `<repo>/objects/<path>`: file content
+//! - `<repo>/metadata/<sha256 of path>.json`: checksum, content type and
+//!   `X-Meta-*` headers recorded at upload time
+
+use crate::routers::FILES_STORE;
+use actix_web::dev::HttpServiceFactory;
+use actix_web::web;
+use ops::{delete, download, list, upload};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use tokio::sync::OwnedMutexGuard;
+use utoipa::{OpenApi, ToSchema};
+
+pub mod ops;
+
+// ---------------------------------------------------------------------------
+// Storage keys
+// ---------------------------------------------------------------------------
+
+/// Store key of a file's content.
+///
+/// Layout: `<repo>/objects/<path>`
+pub(super) fn object_key(repository: &str, path: &str) -> Option<String> {
+    if !validate_repository(repository) || !validate_path(path) {
+        return None;
+    }
+    Some(format!("{repository}/objects/{path}"))
+}
+
+/// Store prefix of every file below directory `dir` (empty for the
+/// repository root).
+///
+/// Layout: `<repo>/objects/[<dir>/]`
+pub(super) fn objects_prefix(repository: &str, dir: &str) -> Option<String> {
+    if !validate_repository(repository) {
+        return None;
+    }
+    if dir.is_empty() {
+        return Some(format!("{repository}/objects/"));
+    }
+    object_key(repository, dir).map(|key| format!("{key}/"))
+}
+
+/// Store key of a file's upload metadata. Paths are hashed so that metadata
+/// never collides with a directory of the same name.
+///
+/// Layout: `<repo>/metadata/<sha256 of path>.json`
+pub(super) fn metadata_key(repository: &str, path: &str) -> Option<String> {
+    object_key(repository, path)?;
+    let hash = Sha256::digest(path.as_bytes());
+    Some(format!("{repository}/metadata/{hash:x}.json"))
+}
+
+/// Validates a repository name: 1–64 chars of lowercase ASCII alphanumerics,
+/// `.`, `_` or `-`, starting with an alphanumeric.
+pub(super) fn validate_repository(name: &str) -> bool {
+    if name.is_empty() || name.len() > 64 {
+        return false;
+    }
+    name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'.' | b'_' | b'-')
+        })
+}
+
+/// Validates a file path: at most 1024 bytes of non-empty segments other than
+/// `.` and `..`, without control characters.
+pub(super) fn validate_path(path: &str) -> bool {
+    path.len() <= 1024
+        && crate::storage::valid_key(path)
+        && !path.chars().any(char::is_control)
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+// ---------------------------------------------------------------------------
+// Stored metadata
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileMetadata {
+    pub repository: String,
+    pub path: String,
+    /// Hex-encoded sha256 of the content.
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub content_type: String,
+    /// Login of the uploading account.
+    #[serde(default)]
+    pub uploaded_by: Option<String>,
+    /// RFC 3339 upload timestamp.
+    pub created_at: String,
+    /// `X-Meta-<key>` headers sent with the upload, keyed by lowercase
+    /// `<key>`.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Reads the upload metadata of a file; `None` when it is missing or
+/// unreadable.
+pub(crate) async fn load_metadata(repository: &str, path: &str) -> Option<FileMetadata> {
+    let key = metadata_key(repository, path)?;
+    let data = FILES_STORE.get(&key).await.ok()??;
+    serde_json::from_slice(&data).ok()
+}
+
+// ---------------------------------------------------------------------------
+// Listings
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RepositorySummary {
+    pub name: String,
+    pub files: u64,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FileEntry {
+    /// Last path segment.
+    pub name: String,
+    /// Path relative to the repository root.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    /// File size, or the total size of every file below a directory.
+    pub size_bytes: u64,
+    pub sha256: Option<String>,
+    pub content_type: Option<String>,
+    pub modified_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DirectoryListing {
+    pub repository: String,
+    /// Directory path relative to the repository root; empty for the root.
+    pub path: String,
+    pub entries: Vec<FileEntry>,
+}
+
+/// Returns every repository holding at least one file, sorted by name.
+pub(crate) async fn list_repositories() -> std::io::Result<Vec<RepositorySummary>> {
+    let mut repositories: BTreeMap<String, RepositorySummary> = BTreeMap::new();
+    for (key, size) in FILES_STORE.list_sizes("").await? {
+        let Some((name, rest)) = key.split_once('/') else {
+            continue;
+        };
+        if !rest.starts_with("objects/") || !validate_repository(name) {
+            continue;
+        }
+        let summary = repositories
+            .entry(name.to_string())
+            .or_insert_with(|| RepositorySummary {
+                name: name.to_string(),
+                files: 0,
+                size_bytes: 0,
+            });
+        summary.files += 1;
+        summary.size_bytes += size;
+    }
+    Ok(repositories.into_values().collect())
+}
+
+/// Lists directory `dir` of a repository (empty for the root): its files and
+/// immediate subdirectories, or every file below it when `recursive` is set.
+/// Returns `Ok(None)` when no file lives below `dir`.
+pub(crate) async fn list_directory(
+    repository: &str,
+    dir: &str,
+    recursive: bool,
+) -> std::io::Result<Option<DirectoryListing>> {
+    let Some(prefix) = objects_prefix(repository, dir) else {
+        return Ok(None);
+    };
+    let objects = FILES_STORE.list_sizes(&prefix).await?;
+    if objects.is_empty() {
+        return Ok(None);
+    }
+
+    let base = if dir.is_empty() {
+        String::new()
+    } else {
+        format!("{dir}/")
+    };
+    let mut directories: BTreeMap<String, u64> = BTreeMap::new();
+    let mut entries = Vec::new();
+    for (key, size) in objects {
+        let Some(relative) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+        match relative.split_once('/') {
+            Some((child, _)) if !recursive => {
+                *directories.entry(child.to_string()).or_default() += size;
+            }
+            _ => {
+                let path = format!("{base}{relative}");
+                let metadata = load_metadata(repository, &path).await;
+                entries.push(FileEntry {
+                    name: relative.rsplit('/').next().unwrap_or(relative).to_string(),
+                    path,
+                    kind: EntryKind::File,
+                    size_bytes: size,
+                    sha256: metadata.as_ref().map(|m| m.sha256.clone()),
+                    content_type: metadata.as_ref().map(|m| m.content_type.clone()),
+                    modified_at: metadata.map(|m| m.created_at),
+                });
+            }
+        }
+    }
+
+    let mut listing: Vec<FileEntry> = directories
+        .into_iter()
+        .map(|(name, size_bytes)| FileEntry {
+            path: format!("{base}{name}"),
+            name,
+            kind: EntryKind::Directory,
+            size_bytes,
+            sha256: None,
+            content_type: None,
+            modified_at: None,
+        })
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    listing.extend(entries);
+
+    Ok(Some(DirectoryListing {
+        repository: repository.to_string(),
+        path: dir.to_string(),
+        entries: listing,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Per-file locking
+// ---------------------------------------------------------------------------
+
+type FileLocks = Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>;
+
+static FILE_LOCKS: LazyLock<FileLocks> = LazyLock::new(Default::default);
+
+/// Serialises uploads and deletes of one repository within this process, so
+/// content, metadata and the file/directory conflict checks stay consistent.
+/// The lock is released when the returned guard is dropped.
+pub(super) async fn lock_repository(repository: &str) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = FILE_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        // Drop locks nobody holds or waits for.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(repository.to_string()).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+// ---------------------------------------------------------------------------
+// OpenAPI
+// ---------------------------------------------------------------------------
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list::handle,
+        upload::handle,
+        download::handle,
+        download::head,
+        delete::handle,
+    ),
+    tags(
+        (name = "files", description = "Generic file repositories: upload, download, listing and delete"),
+    )
+)]
+pub struct FilesApiDoc;
+
+// ---------------------------------------------------------------------------
+// Actix scope
+// ---------------------------------------------------------------------------
+
+/// Not wrapped in `NormalizePath`: a trailing slash asks for a directory
+/// listing.
+pub fn scope() -> impl HttpServiceFactory {
+    web::scope("/api/v1/files")
+        .guard(actix_web::guard::fn_guard(|_| super::files_enabled()))
+        .service(list::handle)
+        .service(upload::handle)
+        .service(download::handle)
+        .service(download::head)
+        .service(delete::handle)
+}
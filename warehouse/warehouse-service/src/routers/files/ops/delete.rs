@@ -0,0 +1,70 @@
+use crate::domain::crates_error;
+use crate::routers::FILES_STORE;
+use crate::routers::files::{
+    lock_repository, metadata_key, object_key, validate_path, validate_repository,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, delete, web};
+
+#[utoipa::path(
+    delete,
+    operation_id = "delete_file",
+    tags = ["files"],
+    path = "/{repository}/{path}",
+    params(
+        ("repository" = String, Path, description = "Repository name"),
+        ("path" = String, Path, description = "File path inside the repository"),
+    ),
+    responses(
+        (status = 204, description = "File deleted"),
+        (status = 400, description = "Invalid repository name or path"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "File not found"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    ),
+    security(("bearerAuth" = []))
+)]
+#[delete("/{repository}/{path:.+}")]
+pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+    let (repository, path) = path.into_inner();
+
+    if !validate_repository(&repository) || !validate_path(&path) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid repository name or path");
+    }
+    let (Some(key), Some(meta_key)) = (
+        object_key(&repository, &path),
+        metadata_key(&repository, &path),
+    ) else {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid repository name or path");
+    };
+
+    let _guard = lock_repository(&repository).await;
+
+    match FILES_STORE.size(&key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return crates_error::response(StatusCode::NOT_FOUND, "file not found"),
+        Err(e) => {
+            tracing::error!("failed to stat {repository}/{path}: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal server error",
+            );
+        }
+    }
+
+    // Content first: a file without metadata is still served, metadata
+    // without content is never looked at.
+    let result = match FILES_STORE.delete(&key).await {
+        Ok(()) => FILES_STORE.delete(&meta_key).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            tracing::error!("failed to delete {repository}/{path}: {e}");
+            crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "failed to delete file")
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::domain::crates_error;
+use crate::routers::FILES_STORE;
+use crate::routers::files::{
+    DirectoryListing, FileMetadata, list_directory, load_metadata, object_key, validate_path,
+    validate_repository,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, Responder, get, head, web};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListingQuery {
+    /// List every file below the directory instead of its immediate entries.
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "download_file",
+    tags = ["files"],
+    path = "/{repository}/{path}",
+    params(
+        ("repository" = String, Path, description = "Repository name"),
+        ("path" = String, Path, description = "File path; an empty path or a trailing `/` lists the directory"),
+        ("Range" = Option<String>, Header, description = "Optional byte range, e.g. bytes=0-1023, bytes=1024- or bytes=-512"),
+        ListingQuery,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "File content, or a directory listing (JSON) for directories",
+            body = DirectoryListing,
+            headers(
+                ("X-Checksum-Sha256" = String, description = "Hex sha256 of the file"),
+                ("ETag" = String, description = "Quoted hex sha256 of the file"),
+                ("Content-Length" = u64, description = "File size in bytes"),
+                ("Accept-Ranges" = String, description = "Indicates support for byte ranges"),
+                ("X-Meta-*" = String, description = "Metadata headers sent with the upload")
+            )
+        ),
+        (
+            status = 206,
+            description = "Partial file content",
+            headers(
+                ("Content-Range" = String, description = "Returned byte range, e.g. bytes 0-1023/2048")
+            )
+        ),
+        (status = 400, description = "Invalid repository name or path"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "File or directory not found"),
+        (status = 416, description = "Requested range not satisfiable"),
+        (status = 429, description = "Too many requests"),
+    )
+)]
+#[get("/{repository}/{path:.*}")]
+pub async fn handle(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<ListingQuery>,
+) -> impl Responder {
+    let (repository, path) = path.into_inner();
+    respond(&req, &repository, &path, query.recursive, true).await
+}
+
+#[utoipa::path(
+    head,
+    operation_id = "check_file",
+    tags = ["files"],
+    path = "/{repository}/{path}",
+    params(
+        ("repository" = String, Path, description = "Repository name"),
+        ("path" = String, Path, description = "File path inside the repository"),
+    ),
+    responses(
+        (status = 200, description = "File or directory exists; file headers as for GET"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "File or directory not found"),
+        (status = 429, description = "Too many requests"),
+    )
+)]
+#[head("/{repository}/{path:.*}")]
+pub async fn head(req: HttpRequest, path: web::Path<(String, String)>) -> impl Responder {
+    let (repository, path) = path.into_inner();
+    respond(&req, &repository, &path, false, false).await
+}
+
+async fn respond(
+    req: &HttpRequest,
+    repository: &str,
+    path: &str,
+    recursive: bool,
+    with_body: bool,
+) -> HttpResponse {
+    if !validate_repository(repository) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid repository name");
+    }
+
+    let dir = path.trim_end_matches('/');
+    if !dir.is_empty() && !validate_path(dir) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid file path");
+    }
+
+    if !path.is_empty() && !path.ends_with('/') {
+        let Some(key) = object_key(repository, path) else {
+            return not_found();
+        };
+        match FILES_STORE.size(&key).await {
+            Ok(Some(size)) => {
+                return serve_file(req, repository, path, &key, size, with_body).await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!("failed to stat {repository}/{path}: {e}");
+                return internal_error();
+            }
+        }
+    }
+
+    match list_directory(repository, dir, recursive).await {
+        Ok(Some(listing)) if with_body => HttpResponse::Ok().json(listing),
+        Ok(Some(_)) => HttpResponse::Ok().content_type("application/json").finish(),
+        Ok(None) => not_found(),
+        Err(e) => {
+            tracing::error!("failed to list {repository}/{dir}: {e}");
+            internal_error()
+        }
+    }
+}
+
+async fn serve_file(
+    req: &HttpRequest,
+    repository: &str,
+    path: &str,
+    key: &str,
+    total_size: u64,
+    with_body: bool,
+) -> HttpResponse {
+    let metadata = load_metadata(repository, path).await;
+    let range = match req.headers().get("Range").and_then(|h| h.to_str().ok()) {
+        Some(header) => match parse_range(header, total_size) {
+            Some(range) => Some(range),
+            None => {
+                return HttpResponse::RangeNotSatisfiable()
+                    .append_header(("Content-Range", format!("bytes */{total_size}")))
+                    .finish();
+            }
+        },
+        None => None,
+    };
+
+    let mut response = match range {
+        Some(_) => HttpResponse::PartialContent(),
+        None => HttpResponse::Ok(),
+    };
+    response
+        .append_header(("Accept-Ranges", "bytes"))
+        .append_header(("Content-Disposition", content_disposition(path)));
+    if let Some(metadata) = &metadata {
+        append_metadata_headers(&mut response, metadata);
+    } else {
+        response.append_header(("Content-Type", "application/octet-stream"));
+    }
+
+    let content = match range {
+        Some((start, end)) => {
+            response
+                .append_header(("Content-Range", format!("bytes {start}-{end}/{total_size}")))
+                .append_header(("Content-Length", end - start + 1));
+            if !with_body {
+                return response.finish();
+            }
+            FILES_STORE.get_range(key, start, end).await
+        }
+        None => {
+            response.append_header(("Content-Length", total_size));
+            if !with_body {
+                return response.finish();
+            }
+            FILES_STORE.get(key).await
+        }
+    };
+
+    match content {
+        Ok(Some(content)) => response.body(content),
+        Ok(None) => not_found(),
+        Err(e) => {
+            tracing::error!("failed to read {repository}/{path}: {e}");
+            internal_error()
+        }
+    }
+}
+
+fn append_metadata_headers(response: &mut actix_web::HttpResponseBuilder, metadata: &FileMetadata) {
+    response
+        .append_header(("Content-Type", metadata.content_type.as_str()))
+        .append_header(("X-Checksum-Sha256", metadata.sha256.as_str()))
+        .append_header(("ETag", format!("\"{}\"", metadata.sha256)));
+    if let Ok(created_at) = chrono::DateTime::parse_from_rfc3339(&metadata.created_at) {
+        response.append_header((
+            "Last-Modified",
+            created_at
+                .with_timezone(&chrono::Utc)
+                .format("%a, %d %b %Y %H:%M:%S GMT")
+                .to_string(),
+        ));
+    }
+    for (key, value) in &metadata.metadata {
+        response.append_header((format!("X-Meta-{key}"), value.as_str()));
+    }
+}
+
+fn content_disposition(path: &str) -> String {
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or(path)
+        .chars()
+        .map(|c| if c == '"' || c == '\\' { '_' } else { c })
+        .collect();
+    format!("attachment; filename=\"{name}\"")
+}
+
+/// Parses a single `bytes=` range: `a-b`, `a-` or the suffix form `-n`.
+fn parse_range(header: &str, total: u64) -> Option<(u64, u64)> {
+    let (start, end) = header.strip_prefix("bytes=")?.trim().split_once('-')?;
+    if total == 0 {
+        return None;
+    }
+
+    if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        return Some((total.saturating_sub(suffix), total - 1));
+    }
+
+    let start: u64 = start.parse().ok()?;
+    let end: u64 = if end.is_empty() {
+        total - 1
+    } else {
+        end.parse::<u64>().ok()?.min(total - 1)
+    };
+
+    (start <= end).then_some((start, end))
+}
+
+fn not_found() -> HttpResponse {
+    crates_error::response(StatusCode::NOT_FOUND, "file or directory not found")
+}
+
+fn internal_error() -> HttpResponse {
+    crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+}
@@ -0,0 +1,46 @@
+use crate::domain::crates_error;
+use crate::domain::jwt::Claims;
+use crate::routers::files::{RepositorySummary, list_repositories};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, get};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct RepositoryList {
+    repositories: Vec<RepositorySummary>,
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "list_file_repositories",
+    tags = ["files"],
+    path = "",
+    responses(
+        (status = 200, description = "Repositories holding at least one file", body = RepositoryList),
+        (status = 401, description = "Authentication required"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    )
+)]
+#[get("")]
+pub async fn handle(req: HttpRequest) -> impl Responder {
+    let mut repositories = match list_repositories().await {
+        Ok(repositories) => repositories,
+        Err(e) => {
+            tracing::error!("failed to list file repositories: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to list repositories",
+            );
+        }
+    };
+
+    // Claims are only present when reads require authentication; hide the
+    // repositories the caller cannot read.
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        repositories.retain(|r| claims.allows("files", &r.name, "pull"));
+    }
+
+    HttpResponse::Ok().json(RepositoryList { repositories })
+}
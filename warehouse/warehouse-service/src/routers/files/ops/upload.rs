@@ -0,0 +1,175 @@
+use crate::domain::crates_error;
+use crate::domain::jwt::Claims;
+use crate::routers::FILES_STORE;
+use crate::routers::files::{
+    FileMetadata, lock_repository, metadata_key, object_key, objects_prefix, validate_path,
+    validate_repository,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, put, web};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+#[utoipa::path(
+    put,
+    operation_id = "upload_file",
+    tags = ["files"],
+    path = "/{repository}/{path}",
+    params(
+        ("repository" = String, Path, description = "Repository name"),
+        ("path" = String, Path, description = "File path inside the repository (may contain slashes)"),
+        ("Content-Type" = Option<String>, Header, description = "Stored and served back on download; defaults to application/octet-stream"),
+        ("X-Checksum-Sha256" = Option<String>, Header, description = "Expected hex sha256 of the body; the upload is rejected on mismatch"),
+        ("X-Meta-*" = Option<String>, Header, description = "Arbitrary metadata stored with the file and echoed on download"),
+    ),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Existing file replaced", body = FileMetadata),
+        (status = 201, description = "File created", body = FileMetadata,
+            headers(("Location" = String, description = "Download URL of the file"))),
+        (status = 400, description = "Invalid repository name or path, or checksum mismatch"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 409, description = "Path conflicts with an existing file or directory"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    ),
+    security(("bearerAuth" = []))
+)]
+#[put("/{repository}/{path:.+}")]
+pub async fn handle(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> impl Responder {
+    let (repository, path) = path.into_inner();
+
+    if !validate_repository(&repository) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid repository name");
+    }
+    if !validate_path(&path) {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid file path");
+    }
+    let (Some(key), Some(meta_key), Some(dir_prefix)) = (
+        object_key(&repository, &path),
+        metadata_key(&repository, &path),
+        objects_prefix(&repository, &path),
+    ) else {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid file path");
+    };
+
+    let sha256 = format!("{:x}", Sha256::digest(&body));
+    if let Some(expected) = header_str(&req, "X-Checksum-Sha256") {
+        let expected = expected.trim().trim_start_matches("sha256:");
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            return crates_error::response(
+                StatusCode::BAD_REQUEST,
+                format!("checksum mismatch: expected {expected}, got {sha256}"),
+            );
+        }
+    }
+
+    let metadata = FileMetadata {
+        repository: repository.clone(),
+        path: path.clone(),
+        sha256: sha256.clone(),
+        size_bytes: body.len() as u64,
+        content_type: header_str(&req, "Content-Type")
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or("application/octet-stream")
+            .to_string(),
+        uploaded_by: req.extensions().get::<Claims>().map(|c| c.sub.clone()),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        metadata: meta_headers(&req),
+    };
+    let metadata_json = match serde_json::to_vec(&metadata) {
+        Ok(json) => json,
+        Err(e) => {
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to serialize metadata: {e}"),
+            );
+        }
+    };
+
+    let _guard = lock_repository(&repository).await;
+
+    if let Err(response) = check_conflicts(&repository, &path, &dir_prefix).await {
+        return response;
+    }
+    let existed = matches!(FILES_STORE.size(&key).await, Ok(Some(_)));
+
+    if let Err(e) = FILES_STORE.put(&key, body.to_vec()).await {
+        tracing::error!("failed to store {repository}/{path}: {e}");
+        return crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "failed to store file");
+    }
+    if let Err(e) = FILES_STORE.put(&meta_key, metadata_json).await {
+        tracing::error!("failed to store metadata of {repository}/{path}: {e}");
+        return crates_error::response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to store file metadata",
+        );
+    }
+
+    let mut response = if existed {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::Created()
+    };
+    response
+        .append_header(("Location", format!("/api/v1/files/{repository}/{path}")))
+        .append_header(("X-Checksum-Sha256", sha256))
+        .json(metadata)
+}
+
+/// Rejects storing a file at `path` when one of its parents is a file, or
+/// when `path` itself is a directory.
+async fn check_conflicts(
+    repository: &str,
+    path: &str,
+    dir_prefix: &str,
+) -> Result<(), HttpResponse> {
+    for (end, _) in path.match_indices('/') {
+        let parent = &path[..end];
+        let Some(key) = object_key(repository, parent) else {
+            continue;
+        };
+        if matches!(FILES_STORE.size(&key).await, Ok(Some(_))) {
+            return Err(crates_error::response(
+                StatusCode::CONFLICT,
+                format!("`{parent}` is a file"),
+            ));
+        }
+    }
+
+    match FILES_STORE.list(dir_prefix).await {
+        Ok(keys) if keys.is_empty() => Ok(()),
+        Ok(_) => Err(crates_error::response(
+            StatusCode::CONFLICT,
+            format!("`{path}` is a directory"),
+        )),
+        Err(e) => {
+            tracing::error!("failed to list {repository}/{path}: {e}");
+            Err(crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to check existing files",
+            ))
+        }
+    }
+}
+
+/// Collects `X-Meta-<key>` headers, keyed by lowercase `<key>`.
+fn meta_headers(req: &HttpRequest) -> BTreeMap<String, String> {
+    req.headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            let key = name.as_str().strip_prefix("x-meta-")?;
+            let value = value.to_str().ok()?;
+            (!key.is_empty()).then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn header_str<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|v| v.to_str().ok())
+}
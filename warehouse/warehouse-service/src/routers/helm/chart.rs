@@ -0,0 +1,256 @@
+//! Reading and validating uploaded chart archives.
+//!
+//! A chart archive is a gzipped tarball with a single top-level directory
+//! named after the chart, holding at least `<name>/Chart.yaml`.
+
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Component;
+
+/// Upper bound for `Chart.yaml`; real ones are a few kilobytes.
+const MAX_CHART_YAML_BYTES: u64 = 1024 * 1024;
+
+/// Upper bound for the unpacked archive, so a small upload cannot inflate
+/// into gigabytes while it is scanned.
+const MAX_UNPACKED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// The `Chart.yaml` fields the repository relies on, plus the whole file.
+#[derive(Debug)]
+pub struct ChartYaml {
+    pub api_version: String,
+    pub name: String,
+    pub version: String,
+    pub app_version: Option<String>,
+    pub description: Option<String>,
+    pub chart_type: Option<String>,
+    pub deprecated: bool,
+    /// Every field of `Chart.yaml`, converted to JSON.
+    pub raw: serde_json::Value,
+}
+
+/// Extracts and validates `Chart.yaml` from a chart archive. Errors are
+/// human-readable and meant to be returned to the uploader. Unpacking is
+/// CPU-bound; async callers run it on the blocking pool.
+pub(super) fn read_chart(archive: &[u8]) -> Result<ChartYaml, String> {
+    read_chart_with_limit(archive, MAX_UNPACKED_BYTES)
+}
+
+fn read_chart_with_limit(archive: &[u8], max_unpacked: u64) -> Result<ChartYaml, String> {
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    let entries = tar
+        .entries()
+        .map_err(|e| format!("invalid chart archive: {e}"))?;
+
+    let mut unpacked: u64 = 0;
+    let mut top_dir: Option<String> = None;
+    let mut chart_yaml: Option<Vec<u8>> = None;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("invalid chart archive: {e}"))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("invalid chart archive: {e}"))?
+            .into_owned();
+
+        let mut components = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => components.push(part.to_string_lossy().into_owned()),
+                Component::CurDir => {}
+                _ => {
+                    return Err(format!(
+                        "archive entry `{}` escapes the chart directory",
+                        path.display()
+                    ));
+                }
+            }
+        }
+        unpacked = unpacked.saturating_add(entry.header().size().unwrap_or(0));
+        if unpacked > max_unpacked {
+            return Err(format!(
+                "chart archive unpacks to more than the allowed {max_unpacked} bytes"
+            ));
+        }
+
+        let Some(first) = components.first() else {
+            continue;
+        };
+        match &top_dir {
+            Some(top) if top != first => {
+                return Err("chart archive must contain a single top-level directory".to_string());
+            }
+            Some(_) => {}
+            None => top_dir = Some(first.clone()),
+        }
+
+        if components.len() == 2
+            && components[1] == "Chart.yaml"
+            && entry.header().entry_type().is_file()
+        {
+            let mut data = Vec::new();
+            (&mut entry)
+                .take(MAX_CHART_YAML_BYTES + 1)
+                .read_to_end(&mut data)
+                .map_err(|e| format!("invalid chart archive: {e}"))?;
+            if data.len() as u64 > MAX_CHART_YAML_BYTES {
+                return Err("Chart.yaml is too large".to_string());
+            }
+            chart_yaml = Some(data);
+        }
+    }
+
+    let Some(data) = chart_yaml else {
+        return Err("chart archive has no `<name>/Chart.yaml`".to_string());
+    };
+    let chart = parse_chart_yaml(&data)?;
+
+    if top_dir.as_deref() != Some(chart.name.as_str()) {
+        return Err(format!(
+            "chart directory `{}` does not match chart name `{}`",
+            top_dir.unwrap_or_default(),
+            chart.name
+        ));
+    }
+    Ok(chart)
+}
+
+fn parse_chart_yaml(data: &[u8]) -> Result<ChartYaml, String> {
+    let mut raw: serde_json::Value =
+        serde_yaml::from_slice(data).map_err(|e| format!("invalid Chart.yaml: {e}"))?;
+    let Some(map) = raw.as_object_mut() else {
+        return Err("invalid Chart.yaml: expected a mapping".to_string());
+    };
+    // Helm reads these as strings and rejects numbers in `index.yaml`.
+    for key in ["apiVersion", "name", "version", "appVersion", "kubeVersion"] {
+        if let Some(serde_json::Value::Number(n)) = map.get(key) {
+            let value = n.to_string();
+            map.insert(key.to_string(), value.into());
+        }
+    }
+
+    let api_version =
+        string_field(&raw, "apiVersion").ok_or("Chart.yaml: `apiVersion` is required")?;
+    if api_version != "v1" && api_version != "v2" {
+        return Err(format!(
+            "Chart.yaml: unsupported apiVersion `{api_version}`; expected v1 or v2"
+        ));
+    }
+    let name = string_field(&raw, "name").ok_or("Chart.yaml: `name` is required")?;
+    if !super::validate_chart_name(&name) {
+        return Err(format!("Chart.yaml: invalid chart name `{name}`"));
+    }
+    let version = string_field(&raw, "version").ok_or("Chart.yaml: `version` is required")?;
+    if !super::validate_version(&version) {
+        return Err(format!(
+            "Chart.yaml: version `{version}` is not a valid SemVer 2 version"
+        ));
+    }
+    let chart_type = string_field(&raw, "type");
+    if let Some(chart_type) = &chart_type
+        && chart_type != "application"
+        && chart_type != "library"
+    {
+        return Err(format!(
+            "Chart.yaml: invalid type `{chart_type}`; expected application or library"
+        ));
+    }
+
+    Ok(ChartYaml {
+        api_version,
+        name,
+        version,
+        app_version: string_field(&raw, "appVersion"),
+        description: string_field(&raw, "description"),
+        chart_type,
+        deprecated: raw
+            .get("deprecated")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false),
+        raw,
+    })
+}
+
+fn string_field(raw: &serde_json::Value, key: &str) -> Option<String> {
+    raw.get(key)?
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_chart_with_limit;
+    use flate2::{Compression, write::GzEncoder};
+    use tar::Header;
+
+    const LIMIT: u64 = 1024 * 1024;
+
+    const CHART_YAML: &[u8] = b"apiVersion: v2\nname: demo\nversion: 1.2.3\nappVersion: 4.5\n";
+
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (path, data) in entries {
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, *data)
+                .expect("entry should append");
+        }
+        builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .expect("archive should build")
+    }
+
+    #[test]
+    fn reads_chart_yaml() {
+        let chart = read_chart_with_limit(
+            &archive(&[
+                ("demo/Chart.yaml", CHART_YAML),
+                ("demo/templates/deployment.yaml", b"kind: Deployment\n"),
+            ]),
+            LIMIT,
+        )
+        .expect("chart should be accepted");
+
+        assert_eq!(chart.name, "demo");
+        assert_eq!(chart.version, "1.2.3");
+        assert_eq!(chart.app_version.as_deref(), Some("4.5"));
+    }
+
+    #[test]
+    fn unpacked_size_is_limited() {
+        let values = vec![b'#'; 4096];
+        let error = read_chart_with_limit(
+            &archive(&[
+                ("demo/Chart.yaml", CHART_YAML),
+                ("demo/values.yaml", &values),
+            ]),
+            1024,
+        )
+        .expect_err("oversized chart should be rejected");
+
+        assert!(
+            error.contains("more than the allowed 1024 bytes"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn chart_directory_must_match_the_name() {
+        let error = read_chart_with_limit(&archive(&[("other/Chart.yaml", CHART_YAML)]), LIMIT)
+            .expect_err("mismatched directory should be rejected");
+
+        assert!(error.contains("does not match chart name"), "{error}");
+    }
+
+    #[test]
+    fn chart_yaml_is_required() {
+        let error = read_chart_with_limit(&archive(&[("demo/values.yaml", b"a: 1\n")]), LIMIT)
+            .expect_err("chart without Chart.yaml should be rejected");
+
+        assert!(error.contains("has no `<name>/Chart.yaml`"), "{error}");
+    }
+}
@@ -0,0 +1,236 @@
+//! Helm chart repository.
+//!
+//! Serves the layout `helm repo add` expects (`index.yaml` plus the chart
+//! archives it links to) and a small ChartMuseum-style API under
+//! `/helm/api/charts` for uploads, lookups and deletes.
+//!
+//! Store layout:
+//! - `charts/<name>/<name>-<version>.tgz`: chart archive as uploaded
+//! - `metadata/<name>/<version>.json`: `Chart.yaml` plus digest and upload
+//!   details
+//! - `index.yaml`: rebuilt from the metadata after every upload and delete
+
+use crate::routers::HELM_STORE;
+use crate::routers::crates::search::{compare_versions, is_semver};
+use actix_web::dev::HttpServiceFactory;
+use actix_web::web;
+use ops::{delete, download, index, list, upload};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::sync::MutexGuard;
+use utoipa::{OpenApi, ToSchema};
+
+mod chart;
+pub mod ops;
+
+// ---------------------------------------------------------------------------
+// Storage keys
+// ---------------------------------------------------------------------------
+
+/// Store key of the repository index.
+pub(super) const INDEX_KEY: &str = "index.yaml";
+
+/// File name of a chart archive: `<name>-<version>.tgz`.
+pub(super) fn chart_file_name(name: &str, version: &str) -> String {
+    format!("{name}-{version}.tgz")
+}
+
+/// Store key of a chart archive; also its URL relative to the repository.
+///
+/// Layout: `charts/<name>/<name>-<version>.tgz`
+pub(super) fn chart_file_key(name: &str, version: &str) -> Option<String> {
+    if !validate_chart_name(name) || !validate_version(version) {
+        return None;
+    }
+    Some(format!("charts/{name}/{}", chart_file_name(name, version)))
+}
+
+/// Store key of a chart version's metadata.
+///
+/// Layout: `metadata/<name>/<version>.json`
+pub(super) fn metadata_key(name: &str, version: &str) -> Option<String> {
+    chart_file_key(name, version).map(|_| format!("metadata/{name}/{version}.json"))
+}
+
+/// Validates a chart name: 1–128 chars of ASCII alphanumerics, `.`, `_` or
+/// `-`, starting with an alphanumeric.
+pub(super) fn validate_chart_name(name: &str) -> bool {
+    if name.is_empty() || name.len() > 128 {
+        return false;
+    }
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// Validates a chart version: Helm requires SemVer 2 (`1.2.3[-pre][+build]`).
+pub(super) fn validate_version(version: &str) -> bool {
+    version.len() <= 64
+        && version
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'+'))
+        && is_semver(version)
+}
+
+// ---------------------------------------------------------------------------
+// Stored metadata
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChartVersion {
+    pub name: String,
+    pub version: String,
+    /// `Chart.yaml` API version: `v1` or `v2`.
+    pub api_version: String,
+    pub app_version: Option<String>,
+    pub description: Option<String>,
+    /// `application` or `library`.
+    #[serde(rename = "type")]
+    pub chart_type: Option<String>,
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Hex-encoded sha256 of the chart archive.
+    pub digest: String,
+    pub size_bytes: u64,
+    /// RFC 3339 upload timestamp.
+    pub created: String,
+    /// Login of the uploading account.
+    #[serde(default)]
+    pub uploaded_by: Option<String>,
+    /// `Chart.yaml` as uploaded; reproduced verbatim in `index.yaml`.
+    #[schema(value_type = Object)]
+    pub chart: serde_json::Value,
+}
+
+/// Reads the metadata of one chart version; `None` when it is missing or
+/// unreadable.
+pub(crate) async fn load_version(name: &str, version: &str) -> Option<ChartVersion> {
+    let key = metadata_key(name, version)?;
+    let data = HELM_STORE.get(&key).await.ok()??;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Returns every stored chart version grouped by chart name, newest version
+/// first.
+pub(crate) async fn list_charts() -> std::io::Result<BTreeMap<String, Vec<ChartVersion>>> {
+    let mut charts: BTreeMap<String, Vec<ChartVersion>> = BTreeMap::new();
+    for key in HELM_STORE.list("metadata/").await? {
+        let Some((name, file)) = key
+            .strip_prefix("metadata/")
+            .and_then(|rest| rest.split_once('/'))
+        else {
+            continue;
+        };
+        let Some(version) = file.strip_suffix(".json") else {
+            continue;
+        };
+        if let Some(meta) = load_version(name, version).await {
+            charts.entry(meta.name.clone()).or_default().push(meta);
+        }
+    }
+    for versions in charts.values_mut() {
+        versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    }
+    Ok(charts)
+}
+
+// ---------------------------------------------------------------------------
+// index.yaml
+// ---------------------------------------------------------------------------
+
+/// Renders a Helm repository index. Each entry is the uploaded `Chart.yaml`
+/// plus `created`, `digest` and a `urls` list relative to the repository, so
+/// clients resolve downloads against whatever URL they added the repo with.
+pub(super) fn render_index<'a>(
+    versions: impl IntoIterator<Item = &'a ChartVersion>,
+) -> Result<String, serde_yaml::Error> {
+    let mut entries: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+    for version in versions {
+        let mut entry = version.chart.clone();
+        if let Some(map) = entry.as_object_mut() {
+            map.insert("created".into(), version.created.clone().into());
+            map.insert("digest".into(), version.digest.clone().into());
+            map.insert(
+                "urls".into(),
+                vec![format!(
+                    "charts/{}/{}",
+                    version.name,
+                    chart_file_name(&version.name, &version.version)
+                )]
+                .into(),
+            );
+        }
+        entries.entry(&version.name).or_default().push(entry);
+    }
+
+    serde_yaml::to_string(&serde_json::json!({
+        "apiVersion": "v1",
+        "entries": entries,
+        "generated": chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Regenerates and stores `index.yaml` from the stored metadata. Callers hold
+/// [`lock_index`].
+pub(super) async fn rebuild_index() -> Result<(), String> {
+    let charts = list_charts()
+        .await
+        .map_err(|e| format!("failed to list charts: {e}"))?;
+    let index = render_index(charts.values().flatten())
+        .map_err(|e| format!("failed to render index.yaml: {e}"))?;
+    HELM_STORE
+        .put(INDEX_KEY, index.into_bytes())
+        .await
+        .map_err(|e| format!("failed to store index.yaml: {e}"))
+}
+
+// ---------------------------------------------------------------------------
+// Locking
+// ---------------------------------------------------------------------------
+
+static INDEX_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Serialises uploads and deletes within this process: every one of them
+/// rewrites the shared `index.yaml`. The lock is released when the returned
+/// guard is dropped.
+pub(super) async fn lock_index() -> MutexGuard<'static, ()> {
+    INDEX_LOCK.lock().await
+}
+
+// ---------------------------------------------------------------------------
+// OpenAPI
+// ---------------------------------------------------------------------------
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        index::handle,
+        download::handle,
+        upload::handle,
+        list::list_charts,
+        list::get_chart,
+        list::get_version,
+        delete::handle,
+    ),
+    tags(
+        (name = "helm", description = "Helm chart repository: index, chart download, upload and delete"),
+    )
+)]
+pub struct HelmApiDoc;
+
+// ---------------------------------------------------------------------------
+// Actix scope
+// ---------------------------------------------------------------------------
+
+pub fn scope() -> impl HttpServiceFactory {
+    web::scope("/helm")
+        .guard(actix_web::guard::fn_guard(|_| super::helm_enabled()))
+        .service(index::handle)
+        .service(download::handle)
+        .service(upload::handle)
+        .service(list::list_charts)
+        .service(list::get_chart)
+        .service(list::get_version)
+        .service(delete::handle)
+}
@@ -0,0 +1,75 @@
+use crate::domain::crates_error;
+use crate::routers::HELM_STORE;
+use crate::routers::helm::{chart_file_key, lock_index, metadata_key, rebuild_index};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, delete, web};
+
+#[utoipa::path(
+    delete,
+    operation_id = "delete_chart_version",
+    tags = ["helm"],
+    path = "/api/charts/{name}/{version}",
+    params(
+        ("name" = String, Path, description = "Chart name"),
+        ("version" = String, Path, description = "Chart version"),
+    ),
+    responses(
+        (status = 204, description = "Chart version deleted and removed from index.yaml"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Chart version not found"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    ),
+    security(("bearerAuth" = []))
+)]
+#[delete("/api/charts/{name}/{version}")]
+pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, version) = path.into_inner();
+
+    let (Some(archive_key), Some(meta_key)) = (
+        chart_file_key(&name, &version),
+        metadata_key(&name, &version),
+    ) else {
+        return not_found();
+    };
+
+    let _guard = lock_index().await;
+
+    match HELM_STORE.size(&meta_key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(),
+        Err(e) => {
+            tracing::error!("failed to stat chart {name} {version}: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal server error",
+            );
+        }
+    }
+
+    // Metadata first: the index is rebuilt from it, and an archive without
+    // metadata is never served from the index.
+    let result = match HELM_STORE.delete(&meta_key).await {
+        Ok(()) => HELM_STORE.delete(&archive_key).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        tracing::error!("failed to delete chart {name} {version}: {e}");
+        return crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "failed to delete chart");
+    }
+    if let Err(e) = rebuild_index().await {
+        tracing::error!("{e}");
+        return crates_error::response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to update index.yaml",
+        );
+    }
+
+    tracing::info!("deleted chart {name} {version}");
+    HttpResponse::NoContent().finish()
+}
+
+fn not_found() -> HttpResponse {
+    crates_error::response(StatusCode::NOT_FOUND, "chart version not found")
+}
@@ -0,0 +1,55 @@
+use crate::domain::crates_error;
+use crate::routers::HELM_STORE;
+use crate::routers::helm::{chart_file_key, validate_chart_name};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, Responder, get, web};
+
+#[utoipa::path(
+    get,
+    operation_id = "download_chart",
+    tags = ["helm"],
+    path = "/charts/{name}/{file}",
+    params(
+        ("name" = String, Path, description = "Chart name"),
+        ("file" = String, Path, description = "Archive file name: `<name>-<version>.tgz`"),
+    ),
+    responses(
+        (status = 200, description = "Chart archive", content_type = "application/gzip"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Chart version not found"),
+        (status = 429, description = "Too many requests"),
+    )
+)]
+#[get("/charts/{name}/{file}")]
+pub async fn handle(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, file) = path.into_inner();
+
+    let key = file
+        .strip_prefix(&format!("{name}-"))
+        .and_then(|rest| rest.strip_suffix(".tgz"))
+        .filter(|_| validate_chart_name(&name))
+        .and_then(|version| chart_file_key(&name, version));
+    let Some(key) = key else {
+        return not_found();
+    };
+
+    match HELM_STORE.get(&key).await {
+        Ok(Some(data)) => HttpResponse::Ok()
+            .content_type("application/gzip")
+            .append_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"{file}\""),
+            ))
+            .body(data),
+        Ok(None) => not_found(),
+        Err(e) => {
+            tracing::error!("failed to read chart {file}: {e}");
+            crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "internal server error")
+        }
+    }
+}
+
+fn not_found() -> HttpResponse {
+    crates_error::response(StatusCode::NOT_FOUND, "chart version not found")
+}
@@ -0,0 +1,64 @@
+use crate::domain::crates_error;
+use crate::domain::jwt::Claims;
+use crate::routers::HELM_STORE;
+use crate::routers::helm::{INDEX_KEY, list_charts, render_index};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, get};
+
+#[utoipa::path(
+    get,
+    operation_id = "helm_index",
+    tags = ["helm"],
+    path = "/index.yaml",
+    responses(
+        (status = 200, description = "Helm repository index", content_type = "application/x-yaml"),
+        (status = 401, description = "Authentication required"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    )
+)]
+#[get("/index.yaml")]
+pub async fn handle(req: HttpRequest) -> impl Responder {
+    // Claims are only present when reads require authentication. A token
+    // limited to some charts gets an index of just those.
+    let restricted = req
+        .extensions()
+        .get::<Claims>()
+        .filter(|claims| !claims.allows("chart", "*", "pull"))
+        .cloned();
+
+    let index = match restricted {
+        None => match HELM_STORE.get(INDEX_KEY).await {
+            Ok(Some(index)) => Ok(index.to_vec()),
+            // Nothing uploaded yet.
+            Ok(None) => render_index([])
+                .map(String::into_bytes)
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        Some(claims) => match list_charts().await {
+            Ok(charts) => render_index(
+                charts
+                    .values()
+                    .flatten()
+                    .filter(|v| claims.allows("chart", &v.name, "pull")),
+            )
+            .map(String::into_bytes)
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+    };
+
+    match index {
+        Ok(index) => HttpResponse::Ok()
+            .content_type("application/x-yaml")
+            .body(index),
+        Err(e) => {
+            tracing::error!("failed to read helm index: {e}");
+            crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to read index.yaml",
+            )
+        }
+    }
+}
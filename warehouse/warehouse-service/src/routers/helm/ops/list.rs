@@ -0,0 +1,95 @@
+use crate::domain::crates_error;
+use crate::domain::jwt::Claims;
+use crate::routers::helm::{ChartVersion, list_charts as load_charts, load_version};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, get, web};
+
+#[utoipa::path(
+    get,
+    operation_id = "list_charts",
+    tags = ["helm"],
+    path = "/api/charts",
+    responses(
+        (status = 200, description = "Every chart with its versions, newest first", body = std::collections::BTreeMap<String, Vec<ChartVersion>>),
+        (status = 401, description = "Authentication required"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    )
+)]
+#[get("/api/charts")]
+pub async fn list_charts(req: HttpRequest) -> impl Responder {
+    let mut charts = match load_charts().await {
+        Ok(charts) => charts,
+        Err(e) => {
+            tracing::error!("failed to list charts: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to list charts",
+            );
+        }
+    };
+
+    // Claims are only present when reads require authentication; hide the
+    // charts the caller cannot read.
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        charts.retain(|name, _| claims.allows("chart", name, "pull"));
+    }
+
+    HttpResponse::Ok().json(charts)
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_chart",
+    tags = ["helm"],
+    path = "/api/charts/{name}",
+    params(("name" = String, Path, description = "Chart name")),
+    responses(
+        (status = 200, description = "Versions of the chart, newest first", body = Vec<ChartVersion>),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Chart not found"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    )
+)]
+#[get("/api/charts/{name}")]
+pub async fn get_chart(path: web::Path<String>) -> impl Responder {
+    let name = path.into_inner();
+    match load_charts().await {
+        Ok(mut charts) => match charts.remove(&name) {
+            Some(versions) => HttpResponse::Ok().json(versions),
+            None => crates_error::response(StatusCode::NOT_FOUND, "chart not found"),
+        },
+        Err(e) => {
+            tracing::error!("failed to list charts: {e}");
+            crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "failed to list charts")
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    operation_id = "get_chart_version",
+    tags = ["helm"],
+    path = "/api/charts/{name}/{version}",
+    params(
+        ("name" = String, Path, description = "Chart name"),
+        ("version" = String, Path, description = "Chart version"),
+    ),
+    responses(
+        (status = 200, description = "Chart version metadata", body = ChartVersion),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 404, description = "Chart version not found"),
+        (status = 429, description = "Too many requests"),
+    )
+)]
+#[get("/api/charts/{name}/{version}")]
+pub async fn get_version(path: web::Path<(String, String)>) -> impl Responder {
+    let (name, version) = path.into_inner();
+    match load_version(&name, &version).await {
+        Some(version) => HttpResponse::Ok().json(version),
+        None => crates_error::response(StatusCode::NOT_FOUND, "chart version not found"),
+    }
+}
@@ -0,0 +1,5 @@
+pub mod delete;
+pub mod download;
+pub mod index;
+pub mod list;
+pub mod upload;
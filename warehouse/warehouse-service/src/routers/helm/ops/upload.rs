@@ -0,0 +1,184 @@
+use crate::domain::jwt::Claims;
+use crate::domain::{audit, crates_error};
+use crate::routers::HELM_STORE;
+use crate::routers::helm::chart::read_chart;
+use crate::routers::helm::{
+    ChartVersion, chart_file_key, chart_file_name, lock_index, metadata_key, rebuild_index,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, Responder, post, web};
+use sha2::{Digest, Sha256};
+
+#[utoipa::path(
+    post,
+    operation_id = "upload_chart",
+    tags = ["helm"],
+    path = "/api/charts",
+    request_body(
+        content = Vec<u8>,
+        content_type = "application/gzip",
+        description = "Chart archive as produced by `helm package`",
+    ),
+    responses(
+        (status = 201, description = "Chart uploaded", body = ChartVersion,
+            headers(("Location" = String, description = "Download URL of the chart archive"))),
+        (status = 400, description = "Not a valid chart archive or Chart.yaml"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Access denied"),
+        (status = 409, description = "Chart version already exists"),
+        (status = 429, description = "Too many requests"),
+        (status = 500, description = "Storage failure"),
+    ),
+    security(("bearerAuth" = []))
+)]
+#[post("/api/charts")]
+pub async fn handle(req: HttpRequest, body: web::Bytes) -> impl Responder {
+    if body.is_empty() {
+        return crates_error::response(StatusCode::BAD_REQUEST, "empty chart archive");
+    }
+
+    // The auth middleware cannot see the chart name inside the archive, so
+    // the push scope is checked here: once before unpacking, against any
+    // chart, and again for the chart found in the archive.
+    let Some(claims) = req.extensions().get::<Claims>().cloned() else {
+        return crates_error::response(
+            StatusCode::FORBIDDEN,
+            "token is not authorized to push charts",
+        );
+    };
+    if !pushes_charts(&claims) {
+        return crates_error::response(
+            StatusCode::FORBIDDEN,
+            "token is not authorized to push charts",
+        );
+    }
+
+    let archive = body.clone();
+    let chart = match web::block(move || read_chart(&archive)).await {
+        Ok(Ok(chart)) => chart,
+        Ok(Err(msg)) => return crates_error::response(StatusCode::BAD_REQUEST, msg),
+        Err(e) => {
+            tracing::error!("failed to read chart archive: {e}");
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal server error",
+            );
+        }
+    };
+    let digest = format!("{:x}", Sha256::digest(&body));
+    audit::annotate(&req, &chart.name, Some(&chart.version), Some(&digest));
+
+    if !claims.allows("chart", &chart.name, "push") {
+        return crates_error::response(
+            StatusCode::FORBIDDEN,
+            format!("token is not authorized to push chart `{}`", chart.name),
+        );
+    }
+    let uploaded_by = claims.sub;
+
+    let (Some(archive_key), Some(meta_key)) = (
+        chart_file_key(&chart.name, &chart.version),
+        metadata_key(&chart.name, &chart.version),
+    ) else {
+        return crates_error::response(StatusCode::BAD_REQUEST, "invalid chart name or version");
+    };
+
+    let version = ChartVersion {
+        name: chart.name,
+        version: chart.version,
+        api_version: chart.api_version,
+        app_version: chart.app_version,
+        description: chart.description,
+        chart_type: chart.chart_type,
+        deprecated: chart.deprecated,
+        digest,
+        size_bytes: body.len() as u64,
+        created: chrono::Utc::now().to_rfc3339(),
+        uploaded_by: Some(uploaded_by),
+        chart: chart.raw,
+    };
+    let metadata_json = match serde_json::to_vec(&version) {
+        Ok(json) => json,
+        Err(e) => {
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to serialize metadata: {e}"),
+            );
+        }
+    };
+
+    let _guard = lock_index().await;
+
+    // Chart versions are immutable, like published crates: clients cache
+    // archives by digest from the index.
+    match HELM_STORE.size(&meta_key).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return crates_error::response(
+                StatusCode::CONFLICT,
+                format!(
+                    "chart `{}` version `{}` already exists",
+                    version.name, version.version
+                ),
+            );
+        }
+        Err(e) => {
+            tracing::error!("failed to stat chart {}: {e}", version.name);
+            return crates_error::response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal server error",
+            );
+        }
+    }
+
+    if let Err(e) = HELM_STORE.put(&archive_key, body.to_vec()).await {
+        tracing::error!("failed to store chart {}: {e}", version.name);
+        return crates_error::response(StatusCode::INTERNAL_SERVER_ERROR, "failed to store chart");
+    }
+    if let Err(e) = HELM_STORE.put(&meta_key, metadata_json).await {
+        tracing::error!("failed to store metadata of chart {}: {e}", version.name);
+        let _ = HELM_STORE.delete(&archive_key).await;
+        return crates_error::response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to store chart metadata",
+        );
+    }
+    if let Err(e) = rebuild_index().await {
+        tracing::error!("{e}");
+        let _ = HELM_STORE.delete(&meta_key).await;
+        let _ = HELM_STORE.delete(&archive_key).await;
+        return crates_error::response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to update index.yaml",
+        );
+    }
+
+    tracing::info!(
+        "uploaded chart {} {} ({} bytes)",
+        version.name,
+        version.version,
+        version.size_bytes
+    );
+
+    HttpResponse::Created()
+        .append_header((
+            "Location",
+            format!(
+                "/helm/charts/{}/{}",
+                version.name,
+                chart_file_name(&version.name, &version.version)
+            ),
+        ))
+        .json(version)
+}
+
+/// Whether `claims` hold `push` on at least one chart.
+fn pushes_charts(claims: &Claims) -> bool {
+    claims.scope.split_whitespace().any(|entry| {
+        let mut parts = entry.splitn(3, ':');
+        parts.next() == Some("chart")
+            && parts
+                .nth(1)
+                .is_some_and(|actions| actions.split(',').any(|a| a == "push" || a == "*"))
+    })
+}
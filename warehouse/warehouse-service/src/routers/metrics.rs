@@ -0,0 +1,113 @@
+//! Prometheus scrape endpoint.
+//!
+//! Unauthenticated unless `METRICS_TOKEN` is set, in which case scrapers must
+//! send it as a bearer token. Storage usage is computed by listing the
+//! registry stores and cached for `METRICS_STORAGE_CACHE_SECONDS` (default 60).
+
+use crate::domain::metrics::{self, Snapshot};
+use crate::middleware::auth::recent_auth_failures;
+use crate::routers::{
+    CRATES_STORE, DOCKER_STORE, FILES_STORE, HELM_STORE, crates_enabled, docker_enabled,
+    files_enabled, helm_enabled,
+};
+use actix_web::dev::HttpServiceFactory;
+use actix_web::middleware::NormalizePath;
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use utoipa::OpenApi;
+
+static METRICS_TOKEN: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("METRICS_TOKEN", "").trim().to_string());
+
+static STORAGE_CACHE_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(
+        envmnt::get_or("METRICS_STORAGE_CACHE_SECONDS", "60")
+            .parse()
+            .unwrap_or(60),
+    )
+});
+
+type StorageUsage = Vec<(&'static str, u64, u64)>;
+
+static STORAGE_CACHE: Mutex<Option<(Instant, StorageUsage)>> = Mutex::const_new(None);
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(scrape),
+    tags((name = "metrics", description = "Prometheus metrics"))
+)]
+pub struct MetricsApiDoc;
+
+pub fn scope() -> impl HttpServiceFactory {
+    web::scope("/metrics")
+        .wrap(NormalizePath::trim())
+        .service(scrape)
+}
+
+#[utoipa::path(
+    get,
+    tags = ["metrics"],
+    path = "",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "`METRICS_TOKEN` is set and was not sent"),
+    )
+)]
+#[get("")]
+async fn scrape(req: HttpRequest) -> impl Responder {
+    if !METRICS_TOKEN.is_empty() {
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
+        if token != Some(METRICS_TOKEN.as_str()) {
+            return HttpResponse::Unauthorized().finish();
+        }
+    }
+
+    let (auth_failure_clients, auth_failures_recent) = recent_auth_failures();
+    let snapshot = Snapshot {
+        auth_failure_clients,
+        auth_failures_recent,
+        storage: storage_usage().await,
+    };
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics::render(&snapshot))
+}
+
+async fn storage_usage() -> StorageUsage {
+    let mut cache = STORAGE_CACHE.lock().await;
+    if let Some((taken, usage)) = cache.as_ref()
+        && taken.elapsed() < *STORAGE_CACHE_TTL
+    {
+        return usage.clone();
+    }
+
+    let mut usage = Vec::new();
+    for (registry, enabled, store) in [
+        ("docker", docker_enabled(), &DOCKER_STORE),
+        ("crates", crates_enabled(), &CRATES_STORE),
+        ("files", files_enabled(), &FILES_STORE),
+        ("helm", helm_enabled(), &HELM_STORE),
+    ] {
+        if !enabled {
+            continue;
+        }
+        match store.list_sizes("").await {
+            Ok(objects) => usage.push((
+                registry,
+                objects.len() as u64,
+                objects.iter().map(|(_, size)| size).sum(),
+            )),
+            Err(e) => tracing::warn!("failed to measure {registry} storage: {e}"),
+        }
+    }
+
+    *cache = Some((Instant::now(), usage.clone()));
+    usage
+}
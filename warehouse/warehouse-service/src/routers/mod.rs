@@ -1,11 +1,17 @@
+use crate::storage::{self, ObjectStore};
 use actix_web::{HttpResponse, get};
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 use utoipa::OpenApi;
 
 pub mod admin;
 pub mod crates;
 pub mod docker;
+pub mod files;
 pub mod health;
+pub mod helm;
+pub mod metrics;
+pub(crate) mod quotas;
+pub(crate) mod replication;
 pub mod ui;
 
 static CRATES_STORAGE_ROOT: LazyLock<String> =
@@ -14,14 +20,36 @@ static CRATES_STORAGE_ROOT: LazyLock<String> =
 static DOCKER_STORAGE_ROOT: LazyLock<String> =
     LazyLock::new(|| envmnt::get_or("STORAGE_PATH", "./storage/docker"));
 
+static FILES_STORAGE_ROOT: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("FILES_STORAGE_PATH", "./storage/files"));
+
+static HELM_STORAGE_ROOT: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("HELM_STORAGE_PATH", "./storage/helm"));
+
+static CRATES_STORE: LazyLock<Arc<dyn ObjectStore>> =
+    LazyLock::new(|| storage::open("crates", CRATES_STORAGE_ROOT.as_str()));
+
+static DOCKER_STORE: LazyLock<Arc<dyn ObjectStore>> =
+    LazyLock::new(|| storage::open("docker", DOCKER_STORAGE_ROOT.as_str()));
+
+static FILES_STORE: LazyLock<Arc<dyn ObjectStore>> =
+    LazyLock::new(|| storage::open("files", FILES_STORAGE_ROOT.as_str()));
+
+static HELM_STORE: LazyLock<Arc<dyn ObjectStore>> =
+    LazyLock::new(|| storage::open("helm", HELM_STORAGE_ROOT.as_str()));
+
 struct FeatureFlags {
     docker: bool,
     crates: bool,
+    files: bool,
+    helm: bool,
 }
 
 static FEATURE_FLAGS: LazyLock<FeatureFlags> = LazyLock::new(|| FeatureFlags {
     docker: feature_enabled("FEATURE_DOCKER_ENABLED", false),
     crates: feature_enabled("FEATURE_CRATES_ENABLED", false),
+    files: feature_enabled("FEATURE_FILES_ENABLED", false),
+    helm: feature_enabled("FEATURE_HELM_ENABLED", false),
 });
 
 fn feature_enabled(name: &str, default: bool) -> bool {
@@ -44,9 +72,20 @@ pub fn crates_enabled() -> bool {
     FEATURE_FLAGS.crates
 }
 
+pub fn files_enabled() -> bool {
+    FEATURE_FLAGS.files
+}
+
+pub fn helm_enabled() -> bool {
+    FEATURE_FLAGS.helm
+}
+
 #[derive(OpenApi)]
 #[openapi(
-    nest((path = "/health", api = health::HealthApiDoc),)
+    nest(
+        (path = "/health", api = health::HealthApiDoc),
+        (path = "/metrics", api = metrics::MetricsApiDoc),
+    )
 )]
 struct BaseOpenApiDoc;
 
@@ -69,6 +108,22 @@ struct DockerOpenApiDoc;
 )]
 struct CratesOpenApiDoc;
 
+#[derive(OpenApi)]
+#[openapi(
+    nest(
+        (path = "/api/v1/files", api = files::FilesApiDoc),
+    )
+)]
+struct FilesOpenApiDoc;
+
+#[derive(OpenApi)]
+#[openapi(
+    nest(
+        (path = "/helm", api = helm::HelmApiDoc),
+    )
+)]
+struct HelmOpenApiDoc;
+
 pub fn openapi() -> utoipa::openapi::OpenApi {
     let mut doc = BaseOpenApiDoc::openapi();
     if docker_enabled() {
@@ -77,6 +132,12 @@ pub fn openapi() -> utoipa::openapi::OpenApi {
     if crates_enabled() {
         doc.merge(CratesOpenApiDoc::openapi());
     }
+    if files_enabled() {
+        doc.merge(FilesOpenApiDoc::openapi());
+    }
+    if helm_enabled() {
+        doc.merge(HelmOpenApiDoc::openapi());
+    }
     doc
 }
 
@@ -0,0 +1,513 @@
+//! Storage quotas per Docker namespace and per crate.
+//!
+//! Quotas are comma-separated `<pattern>=<size>` lists; a trailing `*` in the
+//! pattern matches by prefix and the first matching entry applies:
+//!
+//! - `DOCKER_QUOTAS`, e.g. `team-a/*=10GiB,legacy/app=500MiB`
+//! - `CRATE_QUOTAS`, e.g. `internal-*=2GiB,big-crate=200MiB`
+//!
+//! Sizes take an optional `K`, `M`, `G` or `T` suffix in binary units;
+//! `M`, `MB` and `MiB` are equivalent.
+//!
+//! Docker usage is the size of every manifest and blob reachable from the
+//! tags of the matching repositories and from the manifests pushed to them by
+//! digest, each counted once. It is computed by listing the namespace and
+//! then cached per pattern: pushes through this instance are added as they
+//! are stored, deletions drop the cache, and it is recomputed after
+//! `DOCKER_QUOTA_CACHE_SECONDS` (default 300), which also picks up pushes
+//! handled by other replicas. Crate usage is the size of every stored object
+//! of the matching crates.
+
+use crate::domain::jwt::resource_matches;
+use crate::domain::{crates_error, docker_error};
+use crate::routers::crates::validate_crate_name;
+use crate::routers::docker::registry::storage::reachable_manifests;
+use crate::routers::docker::{blob_key, manifest_key, manifest_link_key, pattern_prefix, tag_key};
+use crate::routers::{CRATES_STORE, DOCKER_STORE};
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+pub(crate) struct Quota {
+    pub(crate) pattern: String,
+    pub(crate) limit: u64,
+}
+
+static DOCKER_QUOTAS: LazyLock<Vec<Quota>> = LazyLock::new(|| parse_quotas("DOCKER_QUOTAS"));
+
+static CRATE_QUOTAS: LazyLock<Vec<Quota>> = LazyLock::new(|| parse_quotas("CRATE_QUOTAS"));
+
+static DOCKER_USAGE_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(
+        envmnt::get_or("DOCKER_QUOTA_CACHE_SECONDS", "300")
+            .parse()
+            .unwrap_or(300),
+    )
+});
+
+/// Docker usage per pattern; see the module docs.
+static DOCKER_USAGE_CACHE: LazyLock<Mutex<HashMap<String, CachedObjects>>> =
+    LazyLock::new(Default::default);
+
+struct CachedObjects {
+    objects: Arc<HashMap<String, u64>>,
+    computed: Instant,
+}
+
+/// Usage of one quota.
+#[derive(Serialize, ToSchema)]
+pub(crate) struct QuotaUsage {
+    pub(crate) pattern: String,
+    pub(crate) used_bytes: u64,
+    /// Absent when no quota applies.
+    pub(crate) limit_bytes: Option<u64>,
+}
+
+/// Rejection of an upload that would exceed a quota.
+pub(crate) struct QuotaExceeded {
+    pub(crate) usage: QuotaUsage,
+    /// Bytes the rejected upload would have added.
+    pub(crate) requested_bytes: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "storage quota `{}` exceeded: {} of {} used, upload needs {} more",
+            self.usage.pattern,
+            format_bytes(self.usage.used_bytes),
+            format_bytes(self.usage.limit_bytes.unwrap_or_default()),
+            format_bytes(self.requested_bytes)
+        )
+    }
+}
+
+impl QuotaExceeded {
+    /// Docker registry rejection: `403 DENIED`.
+    pub(crate) fn docker_response(&self) -> HttpResponse {
+        docker_error::response_with_detail(
+            StatusCode::FORBIDDEN,
+            docker_error::DENIED,
+            "repository storage quota exceeded",
+            json!({
+                "quota": self.usage.pattern,
+                "used_bytes": self.usage.used_bytes,
+                "limit_bytes": self.usage.limit_bytes,
+                "requested_bytes": self.requested_bytes,
+            }),
+        )
+    }
+
+    /// Cargo registry rejection: `413` with a cargo-style error.
+    pub(crate) fn crates_response(&self) -> HttpResponse {
+        crates_error::response(StatusCode::PAYLOAD_TOO_LARGE, self.to_string())
+    }
+}
+
+fn parse_quotas(name: &str) -> Vec<Quota> {
+    envmnt::get_or(name, "")
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=').and_then(|(pattern, size)| {
+                Some(Quota {
+                    pattern: pattern.trim().to_string(),
+                    limit: parse_size(size)?,
+                })
+            });
+            if parsed.is_none() {
+                tracing::warn!("ignoring invalid {name} entry `{entry}`");
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Parses sizes such as `1048576`, `512K`, `10MiB` or `2G`.
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return None,
+    };
+    amount.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Formats a byte count with binary units, e.g. `1.5 MiB`.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Describes usage for the UI, e.g. `1.2 GiB of 10.0 GiB (12%, quota team/*)`.
+pub(crate) fn describe(usage: &QuotaUsage) -> String {
+    match usage.limit_bytes {
+        Some(limit) => format!(
+            "{} of {} ({}%, quota {})",
+            format_bytes(usage.used_bytes),
+            format_bytes(limit),
+            usage.used_bytes.saturating_mul(100) / limit.max(1),
+            usage.pattern
+        ),
+        None => format!("{} (no quota)", format_bytes(usage.used_bytes)),
+    }
+}
+
+pub(crate) fn docker_quota(repository: &str) -> Option<&'static Quota> {
+    DOCKER_QUOTAS
+        .iter()
+        .find(|q| resource_matches(&q.pattern, repository))
+}
+
+pub(crate) fn crate_quota(name: &str) -> Option<&'static Quota> {
+    CRATE_QUOTAS
+        .iter()
+        .find(|q| resource_matches(&q.pattern, name))
+}
+
+// ---------------------------------------------------------------------------
+// Docker
+// ---------------------------------------------------------------------------
+
+/// Store keys and sizes of the objects counted against `pattern`, from the
+/// cache when it is fresh enough.
+async fn docker_objects(pattern: &str) -> std::io::Result<Arc<HashMap<String, u64>>> {
+    if let Some(cached) = lock_cache().get(pattern)
+        && cached.computed.elapsed() < *DOCKER_USAGE_TTL
+    {
+        return Ok(cached.objects.clone());
+    }
+
+    let objects = Arc::new(scan_docker_objects(pattern).await?);
+    lock_cache().insert(
+        pattern.to_string(),
+        CachedObjects {
+            objects: objects.clone(),
+            computed: Instant::now(),
+        },
+    );
+    Ok(objects)
+}
+
+/// Walks the repositories matching `pattern`. Only their store prefix is
+/// listed: the tags and the manifests pushed to them by digest are the
+/// roots, so untagged images count as well.
+async fn scan_docker_objects(pattern: &str) -> std::io::Result<HashMap<String, u64>> {
+    let mut roots = Vec::new();
+    for key in DOCKER_STORE.list(&pattern_prefix(pattern)).await? {
+        if let Some((repository, hex)) = key.rsplit_once("/_manifests/") {
+            let digest = format!("sha256:{hex}");
+            if resource_matches(pattern, repository)
+                && manifest_link_key(repository, &digest).as_deref() == Some(key.as_str())
+            {
+                roots.push((repository.to_string(), digest));
+            }
+        } else if let Some((repository, tag)) = key.rsplit_once("/tags/")
+            && resource_matches(pattern, repository)
+            && tag_key(repository, tag).as_deref() == Some(key.as_str())
+            && let Some(content) = DOCKER_STORE.get(&key).await?
+        {
+            let digest = String::from_utf8_lossy(&content).trim().to_string();
+            roots.push((repository.to_string(), digest));
+        }
+    }
+
+    let mut objects = HashMap::new();
+    let mut manifests: Vec<String> = reachable_manifests(roots)
+        .await
+        .into_iter()
+        .map(|(_, digest)| digest)
+        .collect();
+    manifests.sort();
+    manifests.dedup();
+    for digest in manifests {
+        let Some(key) = manifest_key(&digest) else {
+            continue;
+        };
+        if let Some(data) = DOCKER_STORE.get(&key).await? {
+            objects.insert(key, data.len() as u64);
+            add_blobs(&mut objects, &data).await?;
+        }
+    }
+    Ok(objects)
+}
+
+/// The objects a manifest push stores or references: the manifest, its
+/// blobs and, for an index, its (already stored) children.
+async fn manifest_objects(
+    repository: &str,
+    digest: &str,
+    manifest: &[u8],
+) -> std::io::Result<HashMap<String, u64>> {
+    let mut objects = HashMap::new();
+    if let Some(key) = manifest_key(digest) {
+        objects.insert(key, manifest.len() as u64);
+    }
+    add_blobs(&mut objects, manifest).await?;
+    if let Ok(value) = serde_json::from_slice::<Value>(manifest)
+        && let Some(children) = value.get("manifests").and_then(|m| m.as_array())
+    {
+        let roots = children
+            .iter()
+            .filter_map(|c| c.get("digest").and_then(|d| d.as_str()))
+            .map(|d| (repository.to_string(), d.to_string()))
+            .collect();
+        for (_, child) in reachable_manifests(roots).await {
+            let Some(key) = manifest_key(&child) else {
+                continue;
+            };
+            if let Some(data) = DOCKER_STORE.get(&key).await? {
+                objects.insert(key, data.len() as u64);
+                add_blobs(&mut objects, &data).await?;
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Adds a stored manifest push to the cached usage of every namespace
+/// `repository` belongs to, so the next check sees it without a rescan.
+pub(crate) async fn docker_manifest_stored(repository: &str, digest: &str, manifest: &[u8]) {
+    let added = match manifest_objects(repository, digest, manifest).await {
+        Ok(added) => added,
+        Err(e) => {
+            tracing::warn!("failed to account manifest {digest} in {repository}: {e}");
+            docker_usage_changed();
+            return;
+        }
+    };
+    let mut cache = lock_cache();
+    for (pattern, cached) in cache.iter_mut() {
+        if resource_matches(pattern, repository) {
+            let objects = Arc::make_mut(&mut cached.objects);
+            objects.extend(added.iter().map(|(key, size)| (key.clone(), *size)));
+        }
+    }
+}
+
+/// Drops cached Docker usage after manifests were deleted.
+pub(crate) fn docker_usage_changed() {
+    lock_cache().clear();
+}
+
+fn lock_cache() -> MutexGuard<'static, HashMap<String, CachedObjects>> {
+    DOCKER_USAGE_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Adds the config and layer blobs referenced by a manifest.
+async fn add_blobs(objects: &mut HashMap<String, u64>, manifest: &[u8]) -> std::io::Result<()> {
+    let Ok(value) = serde_json::from_slice::<Value>(manifest) else {
+        return Ok(());
+    };
+
+    let config = value.get("config").into_iter();
+    let layers = ["layers", "blobs"]
+        .into_iter()
+        .filter_map(|field| value.get(field).and_then(|v| v.as_array()))
+        .flatten();
+    for descriptor in config.chain(layers) {
+        let Some(key) = descriptor
+            .get("digest")
+            .and_then(|d| d.as_str())
+            .and_then(blob_key)
+        else {
+            continue;
+        };
+        if objects.contains_key(&key) {
+            continue;
+        }
+        if let Some(size) = DOCKER_STORE.size(&key).await? {
+            objects.insert(key, size);
+        }
+    }
+    Ok(())
+}
+
+fn docker_usage(quota: &Quota, objects: &HashMap<String, u64>) -> QuotaUsage {
+    QuotaUsage {
+        pattern: quota.pattern.clone(),
+        used_bytes: objects.values().sum(),
+        limit_bytes: Some(quota.limit),
+    }
+}
+
+/// Current usage of the namespace `repository` belongs to, or of the
+/// repository alone when no quota applies.
+pub(crate) async fn docker_repository_usage(repository: &str) -> std::io::Result<QuotaUsage> {
+    let (pattern, limit) = match docker_quota(repository) {
+        Some(quota) => (quota.pattern.as_str(), Some(quota.limit)),
+        None => (repository, None),
+    };
+    let objects = docker_objects(pattern).await?;
+    Ok(QuotaUsage {
+        pattern: pattern.to_string(),
+        used_bytes: objects.values().sum(),
+        limit_bytes: limit,
+    })
+}
+
+/// Rejects a new blob of `size` bytes when the namespace of `repository` is
+/// already full. Blobs only count once a manifest references them, so this
+/// is an early check; [`check_docker_manifest`] is authoritative.
+pub(crate) async fn check_docker_blob(
+    repository: &str,
+    size: u64,
+) -> std::io::Result<Result<(), QuotaExceeded>> {
+    let Some(quota) = docker_quota(repository) else {
+        return Ok(Ok(()));
+    };
+    let usage = docker_usage(quota, &*docker_objects(&quota.pattern).await?);
+    if usage.used_bytes.saturating_add(size) > quota.limit {
+        return Ok(Err(QuotaExceeded {
+            usage,
+            requested_bytes: size,
+        }));
+    }
+    Ok(Ok(()))
+}
+
+/// Rejects a manifest whose own size plus newly referenced blobs and child
+/// manifests would push the namespace of `repository` over its quota.
+/// Manifests that add nothing new (e.g. re-tagging) are always accepted.
+pub(crate) async fn check_docker_manifest(
+    repository: &str,
+    digest: &str,
+    manifest: &[u8],
+) -> std::io::Result<Result<(), QuotaExceeded>> {
+    let Some(quota) = docker_quota(repository) else {
+        return Ok(Ok(()));
+    };
+    let objects = docker_objects(&quota.pattern).await?;
+    let usage = docker_usage(quota, &objects);
+
+    let requested_bytes: u64 = manifest_objects(repository, digest, manifest)
+        .await?
+        .into_iter()
+        .filter(|(key, _)| !objects.contains_key(key))
+        .map(|(_, size)| size)
+        .sum();
+    if requested_bytes > 0 && usage.used_bytes.saturating_add(requested_bytes) > quota.limit {
+        return Ok(Err(QuotaExceeded {
+            usage,
+            requested_bytes,
+        }));
+    }
+    Ok(Ok(()))
+}
+
+// ---------------------------------------------------------------------------
+// Crates
+// ---------------------------------------------------------------------------
+
+async fn crates_used_bytes(pattern: &str) -> std::io::Result<u64> {
+    let names = CRATES_STORE
+        .list("index/")
+        .await?
+        .into_iter()
+        .filter_map(|key| key.rsplit_once('/').map(|(_, name)| name.to_string()))
+        .filter(|name| validate_crate_name(name) && resource_matches(pattern, name));
+
+    let mut used = 0;
+    for name in names {
+        used += CRATES_STORE
+            .list_sizes(&format!("{name}/"))
+            .await?
+            .into_iter()
+            .map(|(_, size)| size)
+            .sum::<u64>();
+    }
+    Ok(used)
+}
+
+/// Current usage of the quota `name` falls under, or of the crate alone when
+/// no quota applies.
+pub(crate) async fn crate_usage(name: &str) -> std::io::Result<QuotaUsage> {
+    let (pattern, limit) = match crate_quota(name) {
+        Some(quota) => (quota.pattern.as_str(), Some(quota.limit)),
+        None => (name, None),
+    };
+    Ok(QuotaUsage {
+        pattern: pattern.to_string(),
+        used_bytes: crates_used_bytes(pattern).await?,
+        limit_bytes: limit,
+    })
+}
+
+/// Rejects a crate upload of `size` bytes that would exceed the quota of
+/// `name`.
+pub(crate) async fn check_crate(
+    name: &str,
+    size: u64,
+) -> std::io::Result<Result<(), QuotaExceeded>> {
+    let Some(quota) = crate_quota(name) else {
+        return Ok(Ok(()));
+    };
+    let usage = QuotaUsage {
+        pattern: quota.pattern.clone(),
+        used_bytes: crates_used_bytes(&quota.pattern).await?,
+        limit_bytes: Some(quota.limit),
+    };
+    if usage.used_bytes.saturating_add(size) > quota.limit {
+        return Ok(Err(QuotaExceeded {
+            usage,
+            requested_bytes: size,
+        }));
+    }
+    Ok(Ok(()))
+}
+
+// ---------------------------------------------------------------------------
+// Report
+// ---------------------------------------------------------------------------
+
+/// Usage of every configured quota.
+#[derive(Serialize, ToSchema)]
+pub(crate) struct QuotaReport {
+    docker: Vec<QuotaUsage>,
+    crates: Vec<QuotaUsage>,
+}
+
+pub(crate) async fn report() -> std::io::Result<QuotaReport> {
+    let mut docker = Vec::new();
+    for quota in DOCKER_QUOTAS.iter() {
+        docker.push(docker_usage(quota, &*docker_objects(&quota.pattern).await?));
+    }
+
+    let mut crates = Vec::new();
+    for quota in CRATE_QUOTAS.iter() {
+        crates.push(QuotaUsage {
+            pattern: quota.pattern.clone(),
+            used_bytes: crates_used_bytes(&quota.pattern).await?,
+            limit_bytes: Some(quota.limit),
+        });
+    }
+
+    Ok(QuotaReport { docker, crates })
+}
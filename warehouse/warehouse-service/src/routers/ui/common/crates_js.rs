@@ -6,9 +6,36 @@ pub fn ensure_crates_js() {
 }
 
 fn crates_js() -> String {
-    r#"
+    let service = envmnt::get_or("REGISTRY_SERVICE", "warehouse");
+
+    format!(
+        r#"
+// ---- token ----
+async function fetchCrateToken(crateName, action) {{
+    // Request JWT using session cookie
+    const tokenResponse = await fetch(
+        `/token?service={service}&scope=crate:${{crateName}}:${{action}}`,
+        {{
+            credentials: 'include'
+        }}
+    );
+
+    if (!tokenResponse.ok) {{
+        console.error('Failed to obtain token');
+        return null;
+    }}
+
+    const tokenData = await tokenResponse.json();
+    if (!tokenData.token) {{
+        console.error('Token missing in response');
+        return null;
+    }}
+
+    return tokenData.token;
+}}
+
 // ---- yank ----
-function handleYankClick(event) {{
+async function handleYankClick(event) {{
     const button = event.currentTarget;
     const crateName = button.getAttribute('data-crate');
     const version = button.getAttribute('data-version');
@@ -18,27 +45,33 @@ function handleYankClick(event) {{
         return;
     }}
 
-    fetch(`/api/v1/crates/${{crateName}}/${{version}}/yank`, {{
-        method: 'DELETE',
-        headers: {{
-            'Content-Type': 'application/json'
+    try {{
+        const token = await fetchCrateToken(crateName, 'yank');
+        if (!token) {{
+            return;
         }}
-    }})
-    .then(response => {{
+
+        const response = await fetch(`/api/v1/crates/${{crateName}}/${{version}}/yank`, {{
+            method: 'DELETE',
+            headers: {{
+                'Content-Type': 'application/json',
+                'Authorization': token
+            }}
+        }});
+
         if (response.ok) {{
             // Reload the page to show updated status
             location.reload();
         }} else {{
             console.error('Failed to yank crate version');
         }}
-    }})
-    .catch(error => {{
+    }} catch (error) {{
         console.error('Error yanking crate version:', error);
-    }});
+    }}
 }}
 
 // ---- unyank ----
-function handleUnyankClick(event) {{
+async function handleUnyankClick(event) {{
     const button = event.currentTarget;
     const crateName = button.getAttribute('data-crate');
     const version = button.getAttribute('data-version');
@@ -48,24 +81,30 @@ function handleUnyankClick(event) {{
         return;
     }}
 
-    fetch(`/api/v1/crates/${{crateName}}/${{version}}/unyank`, {{
-        method: 'PUT',
-        headers: {{
-            'Content-Type': 'application/json'
+    try {{
+        const token = await fetchCrateToken(crateName, 'yank');
+        if (!token) {{
+            return;
         }}
-    }})
-    .then(response => {{
+
+        const response = await fetch(`/api/v1/crates/${{crateName}}/${{version}}/unyank`, {{
+            method: 'PUT',
+            headers: {{
+                'Content-Type': 'application/json',
+                'Authorization': token
+            }}
+        }});
+
         if (response.ok) {{
             // Reload the page to show updated status
             location.reload();
         }} else {{
             console.error('Failed to unyank crate version');
         }}
-    }})
-    .catch(error => {{
+    }} catch (error) {{
         console.error('Error unyanking crate version:', error);
-    }});
+    }}
 }}
-    "#
-    .to_string()
+"#
+    )
 }
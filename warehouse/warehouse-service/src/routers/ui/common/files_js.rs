@@ -0,0 +1,122 @@
+pub fn ensure_files_js() {
+    let js = files_js();
+
+    let _ = std::fs::create_dir_all("dist/assets/js");
+    let _ = std::fs::write("dist/assets/js/files.js", js);
+}
+
+fn files_js() -> String {
+    let service = envmnt::get_or("REGISTRY_SERVICE", "warehouse");
+
+    format!(
+        r#"
+// ---- token ----
+async function fetchFilesToken(repository, action) {{
+    // Request JWT using session cookie
+    const tokenResponse = await fetch(
+        `/token?service={service}&scope=files:${{repository}}:${{action}}`,
+        {{
+            credentials: 'include'
+        }}
+    );
+
+    if (!tokenResponse.ok) {{
+        console.error('Failed to obtain token');
+        return null;
+    }}
+
+    const tokenData = await tokenResponse.json();
+    if (!tokenData.token) {{
+        console.error('Token missing in response');
+        return null;
+    }}
+
+    return tokenData.token;
+}}
+
+function fileUrl(repository, path) {{
+    const encoded = path.split('/').map(encodeURIComponent).join('/');
+    return `/api/v1/files/${{repository}}/${{encoded}}`;
+}}
+
+// ---- download ----
+// Fetched with a token rather than linked, so downloads also work when
+// file reads require authentication.
+async function handleFileDownloadClick(event) {{
+    const button = event.currentTarget;
+    const repository = button.getAttribute('data-repository');
+    const path = button.getAttribute('data-path');
+
+    if (!repository || !path) {{
+        console.error('Missing repository or path');
+        return;
+    }}
+
+    try {{
+        const token = await fetchFilesToken(repository, 'pull');
+        if (!token) {{
+            return;
+        }}
+
+        const response = await fetch(fileUrl(repository, path), {{
+            headers: {{
+                'Authorization': `Bearer ${{token}}`
+            }}
+        }});
+
+        if (!response.ok) {{
+            console.error('Failed to download file');
+            return;
+        }}
+
+        const url = URL.createObjectURL(await response.blob());
+        const link = document.createElement('a');
+        link.href = url;
+        link.download = path.split('/').pop();
+        document.body.appendChild(link);
+        link.click();
+        link.remove();
+        URL.revokeObjectURL(url);
+    }} catch (error) {{
+        console.error('Error downloading file:', error);
+    }}
+}}
+
+// ---- delete ----
+async function handleFileDeleteClick(event) {{
+    const button = event.currentTarget;
+    const repository = button.getAttribute('data-repository');
+    const path = button.getAttribute('data-path');
+
+    if (!repository || !path) {{
+        console.error('Missing repository or path');
+        return;
+    }}
+
+    try {{
+        const token = await fetchFilesToken(repository, 'delete');
+        if (!token) {{
+            return;
+        }}
+
+        const response = await fetch(fileUrl(repository, path), {{
+            method: 'DELETE',
+            headers: {{
+                'Authorization': `Bearer ${{token}}`
+            }}
+        }});
+
+        if (response.ok) {{
+            // Back to the directory that held the file
+            const parent = path.includes('/') ? path.substring(0, path.lastIndexOf('/')) : '';
+            location.href = `/ui/files/catalog?repo=${{repository}}&path=${{encodeURIComponent(parent)}}`;
+        }} else {{
+            console.error('Failed to delete file');
+        }}
+    }} catch (error) {{
+        console.error('Error deleting file:', error);
+    }}
+}}
+"#
+    )
+}
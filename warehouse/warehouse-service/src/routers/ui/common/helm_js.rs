@@ -0,0 +1,116 @@
+pub fn ensure_helm_js() {
+    let js = helm_js();
+
+    let _ = std::fs::create_dir_all("dist/assets/js");
+    let _ = std::fs::write("dist/assets/js/helm.js", js);
+}
+
+fn helm_js() -> String {
+    let service = envmnt::get_or("REGISTRY_SERVICE", "warehouse");
+
+    format!(
+        r#"
+// ---- token ----
+async function fetchChartToken(chart, action) {{
+    // Request JWT using session cookie
+    const tokenResponse = await fetch(
+        `/token?service={service}&scope=chart:${{chart}}:${{action}}`,
+        {{
+            credentials: 'include'
+        }}
+    );
+
+    if (!tokenResponse.ok) {{
+        console.error('Failed to obtain token');
+        return null;
+    }}
+
+    const tokenData = await tokenResponse.json();
+    if (!tokenData.token) {{
+        console.error('Token missing in response');
+        return null;
+    }}
+
+    return tokenData.token;
+}}
+
+// ---- download ----
+// Fetched with a token rather than linked, so downloads also work when
+// chart reads require authentication.
+async function handleChartDownloadClick(event) {{
+    const button = event.currentTarget;
+    const chart = button.getAttribute('data-chart');
+    const version = button.getAttribute('data-version');
+
+    if (!chart || !version) {{
+        console.error('Missing chart or version');
+        return;
+    }}
+
+    try {{
+        const token = await fetchChartToken(chart, 'pull');
+        if (!token) {{
+            return;
+        }}
+
+        const fileName = `${{chart}}-${{version}}.tgz`;
+        const response = await fetch(`/helm/charts/${{chart}}/${{fileName}}`, {{
+            headers: {{
+                'Authorization': `Bearer ${{token}}`
+            }}
+        }});
+
+        if (!response.ok) {{
+            console.error('Failed to download chart');
+            return;
+        }}
+
+        const url = URL.createObjectURL(await response.blob());
+        const link = document.createElement('a');
+        link.href = url;
+        link.download = fileName;
+        document.body.appendChild(link);
+        link.click();
+        link.remove();
+        URL.revokeObjectURL(url);
+    }} catch (error) {{
+        console.error('Error downloading chart:', error);
+    }}
+}}
+
+// ---- delete ----
+async function handleChartDeleteClick(event) {{
+    const button = event.currentTarget;
+    const chart = button.getAttribute('data-chart');
+    const version = button.getAttribute('data-version');
+
+    if (!chart || !version) {{
+        console.error('Missing chart or version');
+        return;
+    }}
+
+    try {{
+        const token = await fetchChartToken(chart, 'delete');
+        if (!token) {{
+            return;
+        }}
+
+        const response = await fetch(`/helm/api/charts/${{chart}}/${{version}}`, {{
+            method: 'DELETE',
+            headers: {{
+                'Authorization': `Bearer ${{token}}`
+            }}
+        }});
+
+        if (response.ok) {{
+            location.href = `/ui/helm/catalog?repo=${{chart}}`;
+        }} else {{
+            console.error('Failed to delete chart version');
+        }}
+    }} catch (error) {{
+        console.error('Error deleting chart version:', error);
+    }}
+}}
+"#
+    )
+}
@@ -1,3 +1,4 @@
+use crate::domain::auth_store::AUTH_STORE;
 use crate::domain::jwt::JwtConfig;
 use actix_web::{HttpResponse, Responder, get, http::header::ContentType, web};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
@@ -8,6 +9,8 @@ use std::sync::LazyLock;
 
 mod crates_js;
 mod docker_js;
+mod files_js;
+mod helm_js;
 mod warehouse_css;
 
 pub(super) const UI_SESSION_COOKIE: &str = "warehouse_ui_session";
@@ -52,6 +55,46 @@ static UI_SHELL_CRATES: LazyLock<AppShell> = LazyLock::new(|| {
         .build()
 });
 
+static UI_SHELL_FILES: LazyLock<AppShell> = LazyLock::new(|| {
+    warehouse_css::ensure_warehouse_css();
+    files_js::ensure_files_js();
+
+    AppShellBuilder::new()
+        .title("Warehouse — Files")
+        .supported_locales(vec!["en-US".to_string()])
+        .default_theme(Theme::BootstrapDark)
+        .supported_themes(vec![Theme::BootstrapDark])
+        .header(ui_header(Some("ui_header_files"), true))
+        .links(vec![Link::new(
+            "stylesheet",
+            "/ui/assets/css/warehouse.css",
+        )])
+        .scripts(vec![Script::new("/ui/assets/js/files.js")])
+        .with_nav(false)
+        .resources_prefix("/ui".to_string())
+        .build()
+});
+
+static UI_SHELL_HELM: LazyLock<AppShell> = LazyLock::new(|| {
+    warehouse_css::ensure_warehouse_css();
+    helm_js::ensure_helm_js();
+
+    AppShellBuilder::new()
+        .title("Warehouse — Helm")
+        .supported_locales(vec!["en-US".to_string()])
+        .default_theme(Theme::BootstrapDark)
+        .supported_themes(vec![Theme::BootstrapDark])
+        .header(ui_header(Some("ui_header_helm"), true))
+        .links(vec![Link::new(
+            "stylesheet",
+            "/ui/assets/css/warehouse.css",
+        )])
+        .scripts(vec![Script::new("/ui/assets/js/helm.js")])
+        .with_nav(false)
+        .resources_prefix("/ui".to_string())
+        .build()
+});
+
 static UI_SHELL_HOME: LazyLock<AppShell> = LazyLock::new(|| {
     warehouse_css::ensure_warehouse_css();
 
@@ -127,6 +170,8 @@ pub(super) fn render_page(
         UiPageKind::Home => &*UI_SHELL_HOME,
         UiPageKind::Docker => &*UI_SHELL_DOCKER,
         UiPageKind::Crates => &*UI_SHELL_CRATES,
+        UiPageKind::Files => &*UI_SHELL_FILES,
+        UiPageKind::Helm => &*UI_SHELL_HELM,
         UiPageKind::Auth => &*UI_SHELL_AUTH,
     };
     builder
@@ -138,6 +183,8 @@ pub(super) enum UiPageKind {
     Home,
     Docker,
     Crates,
+    Files,
+    Helm,
     Auth,
 }
 
@@ -152,13 +199,6 @@ pub(super) fn is_ui_authenticated(req: &actix_web::HttpRequest, config: &JwtConf
         return true;
     }
 
-    let Some(username) = config.username.as_deref() else {
-        return false;
-    };
-    let Some(password) = config.password.as_deref() else {
-        return false;
-    };
-
     let Some(cookie) = req.cookie(UI_SESSION_COOKIE) else {
         return false;
     };
@@ -173,7 +213,9 @@ pub(super) fn is_ui_authenticated(req: &actix_web::HttpRequest, config: &JwtConf
         return false;
     };
 
-    cookie_user == username && cookie_pass == password
+    AUTH_STORE
+        .authenticate_password(config, cookie_user, cookie_pass)
+        .is_some()
 }
 
 fn sanitize_asset_path(raw: &str) -> Option<PathBuf> {
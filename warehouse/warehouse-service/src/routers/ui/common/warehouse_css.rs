@@ -130,6 +130,46 @@ fn warehouse_css_rules() -> Vec<CssRule> {
                     .property("display", "flex")
                     .property("align-items", "center"),
             ),
+        // Docker image layers grid  – # | digest | media type | size
+        CssRule::new(".layers-grid")
+            .child(CssRule::new(".header,\n.body > .row").property("display", "grid"))
+            .child(CssRule::new(".header").property("grid-template-columns", "1fr 8fr 6fr 2fr"))
+            .child(
+                CssRule::new(".body > .row")
+                    .property("grid-template-columns", "1fr 8fr 6fr 2fr")
+                    .child(
+                        CssRule::new("&:not(:last-child)")
+                            .property("border-bottom", "0.1rem solid var(--bs-gray-700)"),
+                    ),
+            )
+            .child(
+                CssRule::new(".cell")
+                    .property("padding", "0.45rem 0.55rem")
+                    .property("display", "flex")
+                    .property("align-items", "center")
+                    .property("min-width", "0")
+                    .property("overflow-wrap", "anywhere"),
+            ),
+        // Docker image history grid  – created | created by | layer
+        CssRule::new(".history-grid")
+            .child(CssRule::new(".header,\n.body > .row").property("display", "grid"))
+            .child(CssRule::new(".header").property("grid-template-columns", "3fr 10fr 1fr"))
+            .child(
+                CssRule::new(".body > .row")
+                    .property("grid-template-columns", "3fr 10fr 1fr")
+                    .child(
+                        CssRule::new("&:not(:last-child)")
+                            .property("border-bottom", "0.1rem solid var(--bs-gray-700)"),
+                    ),
+            )
+            .child(
+                CssRule::new(".cell")
+                    .property("padding", "0.45rem 0.55rem")
+                    .property("display", "flex")
+                    .property("align-items", "center")
+                    .property("min-width", "0")
+                    .property("overflow-wrap", "anywhere"),
+            ),
         // Crates versions grid  – version | status | checksum
         CssRule::new(".versions-grid")
             .child(CssRule::new(".header,\n.body > .row").property("display", "grid"))
@@ -149,6 +189,48 @@ fn warehouse_css_rules() -> Vec<CssRule> {
                     .property("display", "flex")
                     .property("align-items", "center"),
             ),
+        // Files listing grid – name | size | modified | checksum
+        CssRule::new(".files-grid")
+            .child(CssRule::new(".header,\n.body > .row").property("display", "grid"))
+            .child(CssRule::new(".header").property("grid-template-columns", "3fr 1fr 2fr 2fr 1fr"))
+            .child(
+                CssRule::new(".body > .row")
+                    .property("grid-template-columns", "3fr 1fr 2fr 2fr 1fr")
+                    .child(CssRule::new("&.active").property("background-color", "var(--bs-gray-800)"))
+                    .child(
+                        CssRule::new("&:not(:last-child)")
+                            .property("border-bottom", "0.1rem solid var(--bs-gray-700)"),
+                    ),
+            )
+            .child(
+                CssRule::new(".cell")
+                    .property("padding", "0.45rem 0.55rem")
+                    .property("display", "flex")
+                    .property("align-items", "center")
+                    .property("min-width", "0")
+                    .property("overflow-wrap", "anywhere"),
+            ),
+        // Helm versions grid – version | app version | created | digest
+        CssRule::new(".charts-grid")
+            .child(CssRule::new(".header,\n.body > .row").property("display", "grid"))
+            .child(CssRule::new(".header").property("grid-template-columns", "2fr 2fr 2fr 3fr 1fr"))
+            .child(
+                CssRule::new(".body > .row")
+                    .property("grid-template-columns", "2fr 2fr 2fr 3fr 1fr")
+                    .child(CssRule::new("&.active").property("background-color", "var(--bs-gray-800)"))
+                    .child(
+                        CssRule::new("&:not(:last-child)")
+                            .property("border-bottom", "0.1rem solid var(--bs-gray-700)"),
+                    ),
+            )
+            .child(
+                CssRule::new(".cell")
+                    .property("padding", "0.45rem 0.55rem")
+                    .property("display", "flex")
+                    .property("align-items", "center")
+                    .property("min-width", "0")
+                    .property("overflow-wrap", "anywhere"),
+            ),
         CssRule::new(".tag-link")
             .property("text-decoration", "none")
             .property("color", "var(--bs-gray-300)")
@@ -195,6 +277,13 @@ fn warehouse_css_rules() -> Vec<CssRule> {
             .property("font-size", "0.85rem")
             .property("color", "var(--bs-gray-300)")
             .property("padding", "0.1rem 0"),
+        CssRule::new(".readme")
+            .property("margin", "0")
+            .property("font-size", "0.85rem")
+            .property("white-space", "pre-wrap")
+            .property("max-height", "24rem")
+            .property("overflow", "auto")
+            .property("color", "var(--bs-gray-300)"),
         // Home / service index
         CssRule::new(".home-layout")
             .property("display", "flex")
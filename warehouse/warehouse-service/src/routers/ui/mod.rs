@@ -10,10 +10,14 @@ pub use common::assets;
 
 #[derive(Deserialize)]
 pub(super) struct PageQuery {
-    /// Selected crate name (or docker repository)
+    /// Selected crate name (or docker repository, or Helm chart)
     pub(super) repo: Option<String>,
-    /// Selected version (or docker tag)
+    /// Selected version (or docker tag, or chart version)
     pub(super) tag: Option<String>,
+    /// Selected file or directory inside a files repository
+    pub(super) path: Option<String>,
+    /// Selected platform manifest digest of a multi-platform image
+    pub(super) platform: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -90,6 +94,56 @@ async fn crates_root_slash(
         .finish()
 }
 
+// Files redirects
+
+#[get("/files")]
+async fn files_root(req: actix_web::HttpRequest, config: web::Data<JwtConfig>) -> impl Responder {
+    if !common::is_ui_authenticated(&req, &config) {
+        return common::ui_login_redirect();
+    }
+    HttpResponse::PermanentRedirect()
+        .append_header(("Location", "/ui/files/catalog"))
+        .finish()
+}
+
+#[get("/files/")]
+async fn files_root_slash(
+    req: actix_web::HttpRequest,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !common::is_ui_authenticated(&req, &config) {
+        return common::ui_login_redirect();
+    }
+    HttpResponse::PermanentRedirect()
+        .append_header(("Location", "/ui/files/catalog"))
+        .finish()
+}
+
+// Helm redirects
+
+#[get("/helm")]
+async fn helm_root(req: actix_web::HttpRequest, config: web::Data<JwtConfig>) -> impl Responder {
+    if !common::is_ui_authenticated(&req, &config) {
+        return common::ui_login_redirect();
+    }
+    HttpResponse::PermanentRedirect()
+        .append_header(("Location", "/ui/helm/catalog"))
+        .finish()
+}
+
+#[get("/helm/")]
+async fn helm_root_slash(
+    req: actix_web::HttpRequest,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !common::is_ui_authenticated(&req, &config) {
+        return common::ui_login_redirect();
+    }
+    HttpResponse::PermanentRedirect()
+        .append_header(("Location", "/ui/helm/catalog"))
+        .finish()
+}
+
 // ---------------------------------------------------------------------------
 // Scope
 // ---------------------------------------------------------------------------
@@ -106,6 +160,12 @@ pub fn scope() -> impl HttpServiceFactory {
         // Crates redirects
         .service(crates_root)
         .service(crates_root_slash)
+        // Files redirects
+        .service(files_root)
+        .service(files_root_slash)
+        // Helm redirects
+        .service(helm_root)
+        .service(helm_root_slash)
         // Auth
         .service(pages::auth::login)
         .service(pages::auth::login_slash)
@@ -118,7 +178,14 @@ pub fn scope() -> impl HttpServiceFactory {
         .service(pages::docker::catalog::docker_catalog)
         .service(pages::docker::catalog::docker_catalog_slash)
         .service(pages::docker::tags::docker_tags)
+        .service(pages::docker::image::docker_image)
         // Crates pages
         .service(pages::crates::catalog::crates_index)
         .service(pages::crates::catalog::crates_index_slash)
+        // Files pages
+        .service(pages::files::catalog::files_catalog)
+        .service(pages::files::catalog::files_catalog_slash)
+        // Helm pages
+        .service(pages::helm::catalog::helm_catalog)
+        .service(pages::helm::catalog::helm_catalog_slash)
 }
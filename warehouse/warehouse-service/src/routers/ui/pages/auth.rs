@@ -1,3 +1,4 @@
+use crate::domain::auth_store::AUTH_STORE;
 use crate::domain::jwt::JwtConfig;
 use crate::routers::ui::common::{UI_SESSION_COOKIE, UiPageKind, render_page};
 use actix_web::cookie::{Cookie, SameSite};
@@ -38,18 +39,16 @@ pub(super) async fn login_submit(
             .finish();
     }
 
-    let Some(expected_user) = config.username.as_deref() else {
-        return HttpResponse::Found()
-            .append_header(("Location", "/ui/login?err=1"))
-            .finish();
-    };
-    let Some(expected_pass) = config.password.as_deref() else {
-        return HttpResponse::Found()
-            .append_header(("Location", "/ui/login?err=1"))
-            .finish();
-    };
-
-    if form.username != expected_user || form.password != expected_pass {
+    let form = form.into_inner();
+    let (verify_config, username, password) =
+        (config.clone(), form.username.clone(), form.password.clone());
+    let verified = web::block(move || {
+        AUTH_STORE
+            .authenticate_password(&verify_config, &username, &password)
+            .is_some()
+    })
+    .await;
+    if !matches!(verified, Ok(true)) {
         return HttpResponse::Found()
             .append_header(("Location", "/ui/login?err=1"))
             .finish();
@@ -1,5 +1,7 @@
-use super::storage::{IndexDep, IndexRecord, list_crates, list_versions};
+use super::storage::{IndexDep, IndexRecord, list_crates, list_versions, storage_usage};
 use crate::domain::jwt::JwtConfig;
+use crate::routers::crates::metadata::{CrateMetadata, load_metadata, load_readme};
+use crate::routers::quotas::{self, QuotaUsage};
 use crate::routers::ui::PageQuery;
 use crate::routers::ui::common::{UiPageKind, is_ui_authenticated, render_page, ui_login_redirect};
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
@@ -14,7 +16,7 @@ pub(super) async fn crates_index(
     if !is_ui_authenticated(&req, &config) {
         return ui_login_redirect();
     }
-    render_crates_page(query.repo.clone(), query.tag.clone())
+    render_crates_page(query.repo.clone(), query.tag.clone()).await
 }
 
 #[get("/crates/catalog/")]
@@ -26,7 +28,7 @@ pub(super) async fn crates_index_slash(
     if !is_ui_authenticated(&req, &config) {
         return ui_login_redirect();
     }
-    render_crates_page(query.repo.clone(), query.tag.clone())
+    render_crates_page(query.repo.clone(), query.tag.clone()).await
 }
 
 // ---------------------------------------------------------------------------
@@ -37,11 +39,11 @@ pub(super) async fn crates_index_slash(
 //   repo  → selected crate name
 //   tag   → selected version string
 
-fn render_crates_page(
+async fn render_crates_page(
     selected_crate: Option<String>,
     selected_version: Option<String>,
 ) -> HttpResponse {
-    let all_crates = list_crates();
+    let all_crates = list_crates().await;
 
     let krate = selected_crate
         .as_ref()
@@ -49,7 +51,7 @@ fn render_crates_page(
         .cloned();
 
     let versions: Vec<IndexRecord> = match krate.as_deref() {
-        Some(name) => list_versions(name),
+        Some(name) => list_versions(name).await,
         None => Vec::new(),
     };
 
@@ -70,6 +72,17 @@ fn render_crates_page(
     let selected_record = active_version
         .as_ref()
         .and_then(|v| versions.iter().find(|r| &r.vers == v));
+    let usage = match krate.as_deref() {
+        Some(name) => storage_usage(name).await,
+        None => None,
+    };
+    let (metadata, readme) = match selected_record {
+        Some(r) => (
+            load_metadata(&r.name, &r.vers).await,
+            load_readme(&r.name, &r.vers).await,
+        ),
+        None => (None, None),
+    };
 
     let left = div()
         .class("split-left panel")
@@ -87,11 +100,13 @@ fn render_crates_page(
             &versions,
             active_version.as_deref(),
         )))
-        .child(
-            div()
-                .class("right-bottom")
-                .child(render_details_panel(krate.as_deref(), selected_record)),
-        );
+        .child(div().class("right-bottom").child(render_details_panel(
+            krate.as_deref(),
+            selected_record,
+            metadata.as_ref(),
+            readme.as_deref(),
+            usage.as_ref(),
+        )));
 
     render_page(
         HttpResponse::Ok(),
@@ -239,7 +254,13 @@ fn render_versions_panel(
 // Right-bottom panel – version details
 // ---------------------------------------------------------------------------
 
-fn render_details_panel(krate: Option<&str>, record: Option<&IndexRecord>) -> Element {
+fn render_details_panel(
+    krate: Option<&str>,
+    record: Option<&IndexRecord>,
+    metadata: Option<&CrateMetadata>,
+    readme: Option<&str>,
+    usage: Option<&QuotaUsage>,
+) -> Element {
     let title = match (krate, record) {
         (Some(_), Some(r)) => div()
             .class("panel-title")
@@ -255,6 +276,10 @@ fn render_details_panel(krate: Option<&str>, record: Option<&IndexRecord>) -> El
         Some(r) => {
             let mut list = div().class("meta-list");
 
+            if let Some(description) = metadata.and_then(|m| m.description.as_deref()) {
+                list = list.child(meta_row("ui_meta_description", description));
+            }
+
             list = list
                 .child(meta_row("ui_meta_version", &r.vers))
                 .child(meta_row(
@@ -263,6 +288,10 @@ fn render_details_panel(krate: Option<&str>, record: Option<&IndexRecord>) -> El
                 ))
                 .child(meta_row("ui_meta_checksum", &r.cksum));
 
+            if let Some(usage) = usage {
+                list = list.child(meta_row("ui_meta_storage", &quotas::describe(usage)));
+            }
+
             if let Some(rv) = &r.rust_version {
                 list = list.child(meta_row("ui_meta_rust_version", rv));
             }
@@ -285,11 +314,28 @@ fn render_details_panel(krate: Option<&str>, record: Option<&IndexRecord>) -> El
                 list = list.child(meta_row("ui_meta_features", &all_features.join(", ")));
             }
 
+            if let Some(m) = metadata {
+                list = render_publish_metadata(list, m);
+            }
+
             // Dependencies — grouped by kind
             if !r.deps.is_empty() {
                 list = list.child(render_deps_section(r));
             }
 
+            if let Some(readme) = readme {
+                list = list.child(
+                    div()
+                        .class("meta-row")
+                        .child(
+                            div()
+                                .class("meta-label")
+                                .attr("data-i18n", "ui_meta_readme"),
+                        )
+                        .child(element("pre").class("readme mono").text(readme)),
+                );
+            }
+
             list
         }
     };
@@ -297,6 +343,37 @@ fn render_details_panel(krate: Option<&str>, record: Option<&IndexRecord>) -> El
     div().class("panel").child(title).child(body)
 }
 
+fn render_publish_metadata(mut list: Element, m: &CrateMetadata) -> Element {
+    if !m.authors.is_empty() {
+        list = list.child(meta_row("ui_meta_authors", &m.authors.join(", ")));
+    }
+    if let Some(license) = &m.license {
+        list = list.child(meta_row("ui_meta_license", license));
+    }
+    for (label_key, url) in [
+        ("ui_meta_homepage", &m.homepage),
+        ("ui_meta_documentation", &m.documentation),
+        ("ui_meta_repository", &m.repository),
+    ] {
+        if let Some(url) = url {
+            list = list.child(meta_link_row(label_key, url));
+        }
+    }
+    if !m.keywords.is_empty() {
+        list = list.child(meta_row("ui_meta_keywords", &m.keywords.join(", ")));
+    }
+    if !m.categories.is_empty() {
+        list = list.child(meta_row("ui_meta_categories", &m.categories.join(", ")));
+    }
+    if let Some(login) = &m.published_by {
+        list = list.child(meta_row("ui_meta_published_by", login));
+    }
+    if let Some(created_at) = &m.created_at {
+        list = list.child(meta_row("ui_meta_published_at", created_at));
+    }
+    list
+}
+
 fn render_deps_section(record: &IndexRecord) -> Element {
     let mut normal: Vec<&IndexDep> = Vec::new();
     let mut dev: Vec<&IndexDep> = Vec::new();
@@ -362,6 +439,29 @@ fn meta_row(label_key: &str, value: &str) -> Element {
         .child(div().class("meta-value mono").text(value))
 }
 
+/// Renders a link for plain http(s) URLs; anything else is shown as text
+/// because attribute values are emitted verbatim.
+fn meta_link_row(label_key: &str, url: &str) -> Element {
+    let safe = (url.starts_with("https://") || url.starts_with("http://"))
+        && !url.contains(['"', '<', '>', '\'', ' ']);
+    if !safe {
+        return meta_row(label_key, url);
+    }
+
+    div()
+        .class("meta-row")
+        .child(div().class("meta-label").attr("data-i18n", label_key))
+        .child(
+            div().class("meta-value mono").child(
+                a().attr("href", url)
+                    .attr("target", "_blank")
+                    .attr("rel", "noopener noreferrer")
+                    .class("tag-link")
+                    .text(url),
+            ),
+        )
+}
+
 fn short_hex(hex: &str) -> String {
     if hex.len() <= 16 {
         return hex.to_string();
@@ -1,8 +1,8 @@
-use crate::routers::CRATES_STORAGE_ROOT;
+use crate::routers::CRATES_STORE;
 use crate::routers::crates::validate_crate_name;
+use crate::routers::quotas::{self, QuotaUsage};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
 // ---------------------------------------------------------------------------
 // Public data types
 // ---------------------------------------------------------------------------
@@ -50,45 +50,44 @@ pub struct IndexDep {
 // ---------------------------------------------------------------------------
 
 /// Returns a sorted list of all crate names that have an index file.
-pub fn list_crates() -> Vec<String> {
-    let root = PathBuf::from(CRATES_STORAGE_ROOT.as_str()).join("index");
-    let mut names: Vec<String> = Vec::new();
-
-    // The index tree is <root>/index/<prefix>/<name> — we walk recursively and
-    // collect leaf files (which are the index files, one per crate name).
-    collect_index_names(&root, &mut names);
+pub async fn list_crates() -> Vec<String> {
+    // Index keys are `index/<prefix>/<name>`, one per crate name.
+    let mut names: Vec<String> = CRATES_STORE
+        .list("index/")
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|key| key.rsplit_once('/').map(|(_, name)| name.to_string()))
+        .filter(|name| validate_crate_name(name))
+        .collect();
     names.sort();
     names
 }
 
-fn collect_index_names(dir: &std::path::Path, out: &mut Vec<String>) {
-    let Ok(entries) = std::fs::read_dir(dir) else {
-        return;
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            collect_index_names(&path, out);
-        } else if let Some(name) = path.file_name().and_then(|n| n.to_str())
-            && validate_crate_name(name)
-        {
-            out.push(name.to_string());
-        }
-    }
-}
-
 /// Reads all version records for a crate from its index file.
 /// Returns them in published order (oldest first, as written to the file).
-pub fn list_versions(crate_name: &str) -> Vec<IndexRecord> {
-    let Some(path) = crate::routers::crates::index_file_path(crate_name) else {
+pub async fn list_versions(crate_name: &str) -> Vec<IndexRecord> {
+    let Some(key) = crate::routers::crates::index_file_key(crate_name) else {
         return Vec::new();
     };
-    let Ok(content) = std::fs::read_to_string(&path) else {
+    let Ok(Some(content)) = CRATES_STORE.get(&key).await else {
         return Vec::new();
     };
-    content
+    String::from_utf8_lossy(&content)
         .lines()
         .filter(|l| !l.trim().is_empty())
         .filter_map(|l| serde_json::from_str(l).ok())
         .collect()
 }
+
+/// Storage used by a crate, or by every crate sharing its quota, against
+/// that quota.
+pub async fn storage_usage(crate_name: &str) -> Option<QuotaUsage> {
+    match quotas::crate_usage(crate_name).await {
+        Ok(usage) => Some(usage),
+        Err(e) => {
+            tracing::error!("failed to compute storage usage for {crate_name}: {e}");
+            None
+        }
+    }
+}
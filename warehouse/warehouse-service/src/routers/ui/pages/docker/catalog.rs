@@ -2,6 +2,7 @@ use crate::domain::jwt::JwtConfig;
 use crate::routers::docker::registry::storage::{
     TagListError, TagMetadata, list_repositories, list_tag_metadata_for_repository,
 };
+use crate::routers::quotas::{self, QuotaUsage};
 use crate::routers::ui::PageQuery;
 use crate::routers::ui::common::{UiPageKind, is_ui_authenticated, render_page, ui_login_redirect};
 use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
@@ -23,7 +24,7 @@ pub(in crate::routers::ui::pages) async fn docker_catalog(
     if !is_ui_authenticated(&req, &config) {
         return ui_login_redirect();
     }
-    render_catalog_page(query.repo.clone(), query.tag.clone())
+    render_catalog_page(query.repo.clone(), query.tag.clone()).await
 }
 
 #[get("/docker/catalog/")]
@@ -35,14 +36,14 @@ pub(in crate::routers::ui::pages) async fn docker_catalog_slash(
     if !is_ui_authenticated(&req, &config) {
         return ui_login_redirect();
     }
-    render_catalog_page(query.repo.clone(), query.tag.clone())
+    render_catalog_page(query.repo.clone(), query.tag.clone()).await
 }
 
-fn render_catalog_page(
+async fn render_catalog_page(
     selected_repo: Option<String>,
     selected_tag: Option<String>,
 ) -> HttpResponse {
-    let repositories = list_repositories();
+    let repositories = list_repositories().await;
     let tree = build_repo_tree(&repositories);
 
     let repo = selected_repo
@@ -51,13 +52,24 @@ fn render_catalog_page(
         .cloned();
 
     let tags_meta = match repo.as_deref() {
-        Some(repo) => match list_tag_metadata_for_repository(repo) {
+        Some(repo) => match list_tag_metadata_for_repository(repo).await {
             Ok(v) => v,
             Err(TagListError::InvalidName) | Err(TagListError::NotFound) => Vec::new(),
         },
         None => Vec::new(),
     };
 
+    let usage = match repo.as_deref() {
+        Some(repo) => match quotas::docker_repository_usage(repo).await {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                tracing::error!("failed to compute storage usage for {repo}: {e}");
+                None
+            }
+        },
+        None => None,
+    };
+
     let active_tag = selected_tag
         .as_ref()
         .filter(|tag| tags_meta.iter().any(|meta| &meta.tag == *tag))
@@ -88,11 +100,11 @@ fn render_catalog_page(
             &tags_meta,
             active_tag.as_deref(),
         )))
-        .child(
-            div()
-                .class("right-bottom")
-                .child(render_metadata_panel(repo.as_deref(), selected_meta)),
-        );
+        .child(div().class("right-bottom").child(render_metadata_panel(
+            repo.as_deref(),
+            selected_meta,
+            usage.as_ref(),
+        )));
 
     render_page(
         HttpResponse::Ok(),
@@ -179,7 +191,11 @@ fn render_tags_panel(
         .child(body)
 }
 
-fn render_metadata_panel(repo: Option<&str>, selected_meta: Option<&TagMetadata>) -> Element {
+fn render_metadata_panel(
+    repo: Option<&str>,
+    selected_meta: Option<&TagMetadata>,
+    usage: Option<&QuotaUsage>,
+) -> Element {
     let title = match (repo, selected_meta) {
         (Some(_), Some(meta)) => div()
             .class("panel-title")
@@ -203,7 +219,20 @@ fn render_metadata_panel(repo: Option<&str>, selected_meta: Option<&TagMetadata>
                     .size_bytes
                     .map(|v| format!("{v} bytes"))
                     .unwrap_or_else(|| "unknown".to_string()),
-            )),
+            ))
+            .child_opt(usage.map(|usage| meta_row("ui_meta_storage", &quotas::describe(usage))))
+            .child_opt(repo.map(|repo| {
+                div().class("meta-row").child(div()).child(
+                    div().child(
+                        a().class("button")
+                            .attr(
+                                "href",
+                                &format!("/ui/docker/image?repo={repo}&tag={}", meta.tag),
+                            )
+                            .attr("data-i18n", "ui_inspect"),
+                    ),
+                )
+            })),
         None => div()
             .class("empty")
             .attr("data-i18n", "ui_empty_select_tag"),
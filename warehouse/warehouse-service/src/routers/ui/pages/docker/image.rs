@@ -0,0 +1,326 @@
+use crate::domain::jwt::JwtConfig;
+use crate::routers::docker::registry::inspect::{
+    ImageConfig, ImageInspection, ImagePlatform, InspectError, inspect_image,
+};
+use crate::routers::quotas::format_bytes;
+use crate::routers::ui::PageQuery;
+use crate::routers::ui::common::{UiPageKind, is_ui_authenticated, render_page, ui_login_redirect};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use quench::prelude::*;
+
+#[get("/docker/image")]
+pub(in crate::routers::ui::pages) async fn docker_image(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !is_ui_authenticated(&req, &config) {
+        return ui_login_redirect();
+    }
+    let query = query.into_inner();
+    let (Some(repo), Some(tag)) = (query.repo, query.tag) else {
+        return catalog_redirect("/ui/docker/catalog");
+    };
+    render_image_page(repo, tag, query.platform).await
+}
+
+fn catalog_redirect(location: &str) -> HttpResponse {
+    HttpResponse::Found()
+        .append_header(("Location", location))
+        .finish()
+}
+
+// ---------------------------------------------------------------------------
+// Page renderer
+// ---------------------------------------------------------------------------
+
+// `PageQuery` fields used here:
+//   repo      → repository
+//   tag       → tag (or digest)
+//   platform  → selected platform manifest digest of a multi-platform image
+
+async fn render_image_page(repo: String, tag: String, platform: Option<String>) -> HttpResponse {
+    let image = match inspect_image(&repo, &tag).await {
+        Ok(image) => image,
+        Err(InspectError::Storage) => return HttpResponse::InternalServerError().finish(),
+        // Deleted or mistyped tags go back to the repository.
+        Err(_) => return catalog_redirect(&format!("/ui/docker/catalog?repo={repo}")),
+    };
+
+    // Default to the first platform
+    let selected = platform
+        .and_then(|digest| image.platforms.iter().find(|p| p.digest == digest))
+        .or_else(|| image.platforms.first());
+
+    let left = div()
+        .class("split-left panel")
+        .child(
+            div()
+                .class("panel-title")
+                .child(span().attr("data-i18n", "ui_platforms"))
+                .child(span().text(&format!(" {repo}:{tag}"))),
+        )
+        .child(
+            div()
+                .class("tree-scroll")
+                .child(render_platform_list(&image, selected))
+                .child(render_platform_details(&image, selected)),
+        );
+
+    let right = div()
+        .class("split-right")
+        .child(
+            div()
+                .class("right-top")
+                .child(render_layers_panel(selected)),
+        )
+        .child(div().class("right-bottom").child(render_history_panel(
+            selected.and_then(|p| p.config.as_ref()),
+        )));
+
+    render_page(
+        HttpResponse::Ok(),
+        content()
+            .class("container-fluid py-4")
+            .child(div().class("split-view").child(left).child(right)),
+        UiPageKind::Docker,
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Left panel – platforms and image configuration
+// ---------------------------------------------------------------------------
+
+fn render_platform_list(image: &ImageInspection, selected: Option<&ImagePlatform>) -> Element {
+    if image.platforms.is_empty() {
+        return div()
+            .class("empty")
+            .attr("data-i18n", "ui_empty_no_platforms");
+    }
+
+    let mut list = ul().class("repo-tree");
+    for platform in &image.platforms {
+        let href = format!(
+            "/ui/docker/image?repo={}&tag={}&platform={}",
+            image.name, image.reference, platform.digest
+        );
+        let class = if selected.is_some_and(|s| s.digest == platform.digest) {
+            "repo-link active"
+        } else {
+            "repo-link"
+        };
+        list = list.child(
+            li().child(
+                a().attr("href", &href)
+                    .class(class)
+                    .text(&platform_label(platform)),
+            ),
+        );
+    }
+    list
+}
+
+fn render_platform_details(image: &ImageInspection, selected: Option<&ImagePlatform>) -> Element {
+    let mut list = div()
+        .class("meta-list")
+        .child(meta_row("ui_meta_digest", &image.digest))
+        .child(meta_row(
+            "ui_meta_media_type",
+            image.media_type.as_deref().unwrap_or("unknown"),
+        ));
+
+    let Some(platform) = selected else {
+        return list;
+    };
+    if image.platforms.len() > 1 {
+        list = list.child(meta_row("ui_meta_platform_digest", &platform.digest));
+    }
+    if !platform.stored {
+        return list.child(
+            div()
+                .class("empty")
+                .attr("data-i18n", "ui_empty_platform_not_stored"),
+        );
+    }
+
+    list = list
+        .child(meta_row("ui_meta_platform", &platform_label(platform)))
+        .child(meta_row(
+            "ui_meta_compressed_size",
+            &format_bytes(platform.compressed_size),
+        ));
+
+    let Some(config) = &platform.config else {
+        return list;
+    };
+    list.child(meta_row(
+        "ui_meta_created",
+        config.created.as_deref().unwrap_or("-"),
+    ))
+    .child_opt(
+        config
+            .author
+            .as_deref()
+            .map(|author| meta_row("ui_meta_author", author)),
+    )
+    .child(meta_row(
+        "ui_meta_entrypoint",
+        &join_or_dash(&config.entrypoint),
+    ))
+    .child(meta_row("ui_meta_cmd", &join_or_dash(&config.cmd)))
+    .child(meta_row(
+        "ui_meta_working_dir",
+        config.working_dir.as_deref().unwrap_or("-"),
+    ))
+    .child(meta_row(
+        "ui_meta_user",
+        config.user.as_deref().unwrap_or("-"),
+    ))
+    .child(meta_lines("ui_meta_env", &config.env))
+    .child(meta_lines("ui_meta_exposed_ports", &config.exposed_ports))
+    .child(meta_lines(
+        "ui_meta_labels",
+        &config
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>(),
+    ))
+    .child(meta_row("ui_meta_config_digest", &config.digest))
+}
+
+// ---------------------------------------------------------------------------
+// Right-top panel – layers
+// ---------------------------------------------------------------------------
+
+fn render_layers_panel(platform: Option<&ImagePlatform>) -> Element {
+    let header = div()
+        .class("header")
+        .child(div().class("cell").text("#"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_digest"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_media_type"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_size"));
+
+    let mut body = div().class("body");
+    match platform {
+        Some(platform) if !platform.layers.is_empty() => {
+            for (index, layer) in platform.layers.iter().enumerate() {
+                body = body.child(
+                    div()
+                        .class("row")
+                        .child(div().class("cell mono").text(&(index + 1).to_string()))
+                        .child(div().class("cell mono").text(&layer.digest))
+                        .child(
+                            div()
+                                .class("cell mono")
+                                .text(layer.media_type.as_deref().unwrap_or("-")),
+                        )
+                        .child(div().class("cell mono").text(&format_bytes(layer.size))),
+                );
+            }
+        }
+        _ => {
+            body = body.child(div().class("empty").attr("data-i18n", "ui_empty_no_layers"));
+        }
+    }
+
+    div()
+        .class("panel table layers-grid")
+        .child(div().class("panel-title").attr("data-i18n", "ui_layers"))
+        .child(header)
+        .child(body)
+}
+
+// ---------------------------------------------------------------------------
+// Right-bottom panel – build history
+// ---------------------------------------------------------------------------
+
+fn render_history_panel(config: Option<&ImageConfig>) -> Element {
+    let header = div()
+        .class("header")
+        .child(div().class("cell").attr("data-i18n", "ui_col_created"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_created_by"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_layer"));
+
+    let mut body = div().class("body");
+    match config {
+        Some(config) if !config.history.is_empty() => {
+            for step in &config.history {
+                let created = step.created.as_deref().unwrap_or("-");
+                body = body.child(
+                    div()
+                        .class("row")
+                        .child(
+                            div()
+                                .class("cell mono")
+                                .text(created.get(..19).unwrap_or(created)),
+                        )
+                        .child(
+                            div()
+                                .class("cell mono")
+                                .text(step.created_by.as_deref().unwrap_or("-")),
+                        )
+                        .child(div().class("cell").text(if step.empty_layer {
+                            "-"
+                        } else {
+                            "✓"
+                        })),
+                );
+            }
+        }
+        _ => {
+            body = body.child(
+                div()
+                    .class("empty")
+                    .attr("data-i18n", "ui_empty_no_history"),
+            );
+        }
+    }
+
+    div()
+        .class("panel table history-grid")
+        .child(div().class("panel-title").attr("data-i18n", "ui_history"))
+        .child(header)
+        .child(body)
+}
+
+fn platform_label(platform: &ImagePlatform) -> String {
+    let mut label = format!(
+        "{}/{}",
+        platform.os.as_deref().unwrap_or("unknown"),
+        platform.architecture.as_deref().unwrap_or("unknown")
+    );
+    if let Some(variant) = &platform.variant {
+        label = format!("{label}/{variant}");
+    }
+    label
+}
+
+fn join_or_dash(parts: &[String]) -> String {
+    if parts.is_empty() {
+        return "-".to_string();
+    }
+    parts.join(" ")
+}
+
+fn meta_row(label_key: &str, value: &str) -> Element {
+    div()
+        .class("meta-row")
+        .child(div().class("meta-label").attr("data-i18n", label_key))
+        .child(div().class("meta-value mono").text(value))
+}
+
+/// A row listing one value per line, e.g. environment variables.
+fn meta_lines(label_key: &str, values: &[String]) -> Element {
+    if values.is_empty() {
+        return meta_row(label_key, "-");
+    }
+    let mut lines = div().class("meta-value mono");
+    for value in values {
+        lines = lines.child(div().text(value));
+    }
+    div()
+        .class("meta-row")
+        .child(div().class("meta-label").attr("data-i18n", label_key))
+        .child(lines)
+}
@@ -0,0 +1,353 @@
+use crate::domain::jwt::JwtConfig;
+use crate::routers::files::{
+    DirectoryListing, EntryKind, FileMetadata, RepositorySummary, list_directory,
+    list_repositories, load_metadata,
+};
+use crate::routers::quotas::format_bytes;
+use crate::routers::ui::PageQuery;
+use crate::routers::ui::common::{UiPageKind, is_ui_authenticated, render_page, ui_login_redirect};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use quench::prelude::*;
+
+#[get("/files/catalog")]
+pub(in crate::routers::ui::pages) async fn files_catalog(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !is_ui_authenticated(&req, &config) {
+        return ui_login_redirect();
+    }
+    render_files_page(query.repo.clone(), query.path.clone()).await
+}
+
+#[get("/files/catalog/")]
+pub(in crate::routers::ui::pages) async fn files_catalog_slash(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !is_ui_authenticated(&req, &config) {
+        return ui_login_redirect();
+    }
+    render_files_page(query.repo.clone(), query.path.clone()).await
+}
+
+// ---------------------------------------------------------------------------
+// Page renderer
+// ---------------------------------------------------------------------------
+
+// `PageQuery` fields used here:
+//   repo  → selected repository
+//   path  → selected directory or file inside it
+
+async fn render_files_page(
+    selected_repo: Option<String>,
+    selected_path: Option<String>,
+) -> HttpResponse {
+    let repositories = list_repositories().await.unwrap_or_else(|e| {
+        tracing::error!("failed to list file repositories: {e}");
+        Vec::new()
+    });
+
+    let repo = selected_repo
+        .as_ref()
+        .filter(|r| repositories.iter().any(|x| &x.name == *r))
+        .cloned();
+    let path = selected_path
+        .as_deref()
+        .unwrap_or_default()
+        .trim_matches('/')
+        .to_string();
+
+    // A selected file shows its directory above and its metadata below.
+    let file = match repo.as_deref() {
+        Some(repo) if !path.is_empty() => load_metadata(repo, &path).await,
+        _ => None,
+    };
+    let dir = match &file {
+        Some(_) => path.rsplit_once('/').map_or("", |(dir, _)| dir),
+        None => path.as_str(),
+    };
+    let listing = match repo.as_deref() {
+        Some(repo) => list_directory(repo, dir, false).await.unwrap_or_else(|e| {
+            tracing::error!("failed to list {repo}/{dir}: {e}");
+            None
+        }),
+        None => None,
+    };
+
+    let left = div()
+        .class("split-left panel")
+        .child(
+            div()
+                .class("panel-title")
+                .attr("data-i18n", "ui_repositories"),
+        )
+        .child(
+            div()
+                .class("tree-scroll")
+                .child(render_repository_list(&repositories, repo.as_deref())),
+        );
+
+    let right = div()
+        .class("split-right")
+        .child(div().class("right-top").child(render_listing_panel(
+            repo.as_deref(),
+            dir,
+            listing.as_ref(),
+            file.as_ref().map(|f| f.path.as_str()),
+        )))
+        .child(
+            div()
+                .class("right-bottom")
+                .child(render_details_panel(file.as_ref())),
+        );
+
+    render_page(
+        HttpResponse::Ok(),
+        content()
+            .class("container-fluid py-4")
+            .child(div().class("split-view").child(left).child(right)),
+        UiPageKind::Files,
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Left panel – repository list
+// ---------------------------------------------------------------------------
+
+fn render_repository_list(repositories: &[RepositorySummary], selected: Option<&str>) -> Element {
+    if repositories.is_empty() {
+        return div().class("empty").attr("data-i18n", "ui_files_empty");
+    }
+
+    let mut list = ul().class("repo-tree");
+    for repository in repositories {
+        let href = format!("/ui/files/catalog?repo={}", repository.name);
+        let class = if Some(repository.name.as_str()) == selected {
+            "repo-link active"
+        } else {
+            "repo-link"
+        };
+        list = list.child(li().child(a().attr("href", &href).class(class).text(&repository.name)));
+    }
+    list
+}
+
+// ---------------------------------------------------------------------------
+// Right-top panel – directory listing
+// ---------------------------------------------------------------------------
+
+fn render_listing_panel(
+    repo: Option<&str>,
+    dir: &str,
+    listing: Option<&DirectoryListing>,
+    active_file: Option<&str>,
+) -> Element {
+    let title = match repo {
+        Some(repo) => render_breadcrumbs(repo, dir),
+        None => div().class("panel-title").attr("data-i18n", "ui_files"),
+    };
+
+    let header = div()
+        .class("header")
+        .child(div().class("cell").attr("data-i18n", "ui_col_name"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_size"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_modified"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_checksum"));
+
+    let mut body = div().class("body");
+    match (repo, listing) {
+        (None, _) => {
+            body = body.child(
+                div()
+                    .class("empty")
+                    .attr("data-i18n", "ui_empty_select_repo"),
+            );
+        }
+        (Some(_), None) => {
+            body = body.child(div().class("empty").attr("data-i18n", "ui_empty_no_files"));
+        }
+        (Some(repo), Some(listing)) => {
+            for entry in &listing.entries {
+                let href = page_href(repo, &entry.path);
+                let row_class = if Some(entry.path.as_str()) == active_file {
+                    "row active"
+                } else {
+                    "row"
+                };
+                let (icon, label) = match entry.kind {
+                    EntryKind::Directory => ("fas fa-folder", format!("{}/", entry.name)),
+                    EntryKind::File => ("fas fa-file", entry.name.clone()),
+                };
+
+                let mut row = div()
+                    .class(row_class)
+                    .child(
+                        div().class("cell").child(
+                            a().attr("href", &href)
+                                .class("tag-link")
+                                .child(i().class(icon).attr("aria-hidden", "true"))
+                                .child(span().text(&format!(" {label}"))),
+                        ),
+                    )
+                    .child(div().class("cell").text(&format_bytes(entry.size_bytes)))
+                    .child(
+                        div()
+                            .class("cell mono")
+                            .text(entry.modified_at.as_deref().unwrap_or("-")),
+                    )
+                    .child(
+                        div()
+                            .class("cell mono")
+                            .text(&entry.sha256.as_deref().map(short_hex).unwrap_or("-".into())),
+                    );
+
+                if entry.kind == EntryKind::File {
+                    row = row.child(
+                        div().class("cell actions").child(
+                            i().class("fas fa-trash")
+                                .attr("aria-hidden", "true")
+                                .attr("data-action", "delete-file")
+                                .attr("data-repository", repo)
+                                .attr("data-path", &escape_attr(&entry.path))
+                                .attr("title", "Delete file")
+                                .attr("role", "button")
+                                .attr("aria-label", "Delete file")
+                                .on_click("handleFileDeleteClick(event)"),
+                        ),
+                    );
+                }
+
+                body = body.child(row);
+            }
+        }
+    }
+
+    div()
+        .class("panel table files-grid")
+        .child(title)
+        .child(header)
+        .child(body)
+}
+
+/// `repo / dir / subdir` with every level linking to its listing.
+fn render_breadcrumbs(repo: &str, dir: &str) -> Element {
+    let mut title = div().class("panel-title").child(
+        a().attr("href", &page_href(repo, ""))
+            .class("tag-link")
+            .text(repo),
+    );
+
+    let mut current = String::new();
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        if !current.is_empty() {
+            current.push('/');
+        }
+        current.push_str(segment);
+        title = title.child(span().text(" / ")).child(
+            a().attr("href", &page_href(repo, &current))
+                .class("tag-link")
+                .text(segment),
+        );
+    }
+    title
+}
+
+// ---------------------------------------------------------------------------
+// Right-bottom panel – file details
+// ---------------------------------------------------------------------------
+
+fn render_details_panel(file: Option<&FileMetadata>) -> Element {
+    let title = match file {
+        Some(f) => div()
+            .class("panel-title")
+            .child(span().attr("data-i18n", "ui_metadata_for"))
+            .child(span().text(&format!(" {}", f.path))),
+        None => div().class("panel-title").attr("data-i18n", "ui_metadata"),
+    };
+
+    let body = match file {
+        None => div()
+            .class("empty")
+            .attr("data-i18n", "ui_empty_select_file"),
+        Some(f) => {
+            let mut list = div()
+                .class("meta-list")
+                .child(meta_row("ui_meta_path", &f.path))
+                .child(meta_row("ui_meta_size", &format_bytes(f.size_bytes)))
+                .child(meta_row("ui_meta_checksum", &f.sha256))
+                .child(meta_row("ui_meta_content_type", &f.content_type))
+                .child(meta_row(
+                    "ui_meta_uploaded_by",
+                    f.uploaded_by.as_deref().unwrap_or("-"),
+                ))
+                .child(meta_row("ui_meta_uploaded_at", &f.created_at));
+
+            for (key, value) in &f.metadata {
+                list = list.child(
+                    div()
+                        .class("meta-row")
+                        .child(div().class("meta-label").text(key))
+                        .child(div().class("meta-value mono").text(value)),
+                );
+            }
+
+            list.child(
+                div().class("meta-row").child(div()).child(
+                    div().child(
+                        button()
+                            .class("button")
+                            .attr("data-repository", &f.repository)
+                            .attr("data-path", &escape_attr(&f.path))
+                            .attr("data-i18n", "ui_download")
+                            .on_click("handleFileDownloadClick(event)"),
+                    ),
+                ),
+            )
+        }
+    };
+
+    div().class("panel").child(title).child(body)
+}
+
+fn meta_row(label_key: &str, value: &str) -> Element {
+    div()
+        .class("meta-row")
+        .child(div().class("meta-label").attr("data-i18n", label_key))
+        .child(div().class("meta-value mono").text(value))
+}
+
+fn short_hex(hex: &str) -> String {
+    if hex.len() <= 16 {
+        return hex.to_string();
+    }
+    format!("{}…{}", &hex[..8], &hex[hex.len() - 8..])
+}
+
+/// Link to this page for a repository path. Repository names are URL-safe;
+/// paths are percent-encoded because attribute values are emitted verbatim.
+fn page_href(repo: &str, path: &str) -> String {
+    if path.is_empty() {
+        return format!("/ui/files/catalog?repo={repo}");
+    }
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~' | b'/') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    format!("/ui/files/catalog?repo={repo}&path={encoded}")
+}
+
+/// Escapes a value for use inside a double-quoted attribute.
+fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
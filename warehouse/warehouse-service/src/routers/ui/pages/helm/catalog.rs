@@ -0,0 +1,314 @@
+use crate::domain::jwt::JwtConfig;
+use crate::routers::helm::{ChartVersion, list_charts};
+use crate::routers::quotas::format_bytes;
+use crate::routers::ui::PageQuery;
+use crate::routers::ui::common::{UiPageKind, is_ui_authenticated, render_page, ui_login_redirect};
+use actix_web::{HttpRequest, HttpResponse, Responder, get, web};
+use quench::prelude::*;
+use std::sync::LazyLock;
+
+static REGISTRY_BASE_URL: LazyLock<String> =
+    LazyLock::new(|| envmnt::get_or("REGISTRY_BASE_URL", "https://localhost"));
+
+#[get("/helm/catalog")]
+pub(in crate::routers::ui::pages) async fn helm_catalog(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !is_ui_authenticated(&req, &config) {
+        return ui_login_redirect();
+    }
+    render_helm_page(query.repo.clone(), query.tag.clone()).await
+}
+
+#[get("/helm/catalog/")]
+pub(in crate::routers::ui::pages) async fn helm_catalog_slash(
+    req: HttpRequest,
+    query: web::Query<PageQuery>,
+    config: web::Data<JwtConfig>,
+) -> impl Responder {
+    if !is_ui_authenticated(&req, &config) {
+        return ui_login_redirect();
+    }
+    render_helm_page(query.repo.clone(), query.tag.clone()).await
+}
+
+// ---------------------------------------------------------------------------
+// Page renderer
+// ---------------------------------------------------------------------------
+
+// `PageQuery` fields used here:
+//   repo  → selected chart name
+//   tag   → selected chart version
+
+async fn render_helm_page(
+    selected_chart: Option<String>,
+    selected_version: Option<String>,
+) -> HttpResponse {
+    let mut charts = list_charts().await.unwrap_or_else(|e| {
+        tracing::error!("failed to list charts: {e}");
+        Default::default()
+    });
+    let names: Vec<String> = charts.keys().cloned().collect();
+
+    let chart = selected_chart.filter(|name| charts.contains_key(name));
+    let versions = chart
+        .as_ref()
+        .and_then(|name| charts.remove(name))
+        .unwrap_or_default();
+
+    // Default to the newest version
+    let selected = selected_version
+        .and_then(|v| versions.iter().find(|r| r.version == v))
+        .or_else(|| versions.first());
+
+    let left = div()
+        .class("split-left panel")
+        .child(div().class("panel-title").attr("data-i18n", "ui_charts"))
+        .child(
+            div()
+                .class("tree-scroll")
+                .child(render_chart_list(&names, chart.as_deref())),
+        );
+
+    let right = div()
+        .class("split-right")
+        .child(div().class("right-top").child(render_versions_panel(
+            chart.as_deref(),
+            &versions,
+            selected.map(|v| v.version.as_str()),
+        )))
+        .child(
+            div()
+                .class("right-bottom")
+                .child(render_details_panel(selected)),
+        );
+
+    render_page(
+        HttpResponse::Ok(),
+        content()
+            .class("container-fluid py-4")
+            .child(div().class("split-view").child(left).child(right)),
+        UiPageKind::Helm,
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Left panel – chart list
+// ---------------------------------------------------------------------------
+
+fn render_chart_list(charts: &[String], selected: Option<&str>) -> Element {
+    if charts.is_empty() {
+        return div().class("empty").attr("data-i18n", "ui_charts_empty");
+    }
+
+    let mut list = ul().class("repo-tree");
+    for name in charts {
+        let href = format!("/ui/helm/catalog?repo={name}");
+        let class = if Some(name.as_str()) == selected {
+            "repo-link active"
+        } else {
+            "repo-link"
+        };
+        list = list.child(li().child(a().attr("href", &href).class(class).text(name)));
+    }
+    list
+}
+
+// ---------------------------------------------------------------------------
+// Right-top panel – versions table
+// ---------------------------------------------------------------------------
+
+fn render_versions_panel(
+    chart: Option<&str>,
+    versions: &[ChartVersion],
+    active_version: Option<&str>,
+) -> Element {
+    let title = match chart {
+        Some(n) => div()
+            .class("panel-title")
+            .child(span().attr("data-i18n", "ui_versions_for"))
+            .child(span().text(&format!(" {n}"))),
+        None => div().class("panel-title").attr("data-i18n", "ui_versions"),
+    };
+
+    let header = div()
+        .class("header")
+        .child(div().class("cell").attr("data-i18n", "ui_col_version"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_app_version"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_created"))
+        .child(div().class("cell").attr("data-i18n", "ui_col_digest"));
+
+    let mut body = div().class("body");
+    match chart {
+        None => {
+            body = body.child(
+                div()
+                    .class("empty")
+                    .attr("data-i18n", "ui_empty_select_chart"),
+            );
+        }
+        Some(_) if versions.is_empty() => {
+            body = body.child(
+                div()
+                    .class("empty")
+                    .attr("data-i18n", "ui_empty_no_versions"),
+            );
+        }
+        Some(chart) => {
+            for version in versions {
+                // `+` (build metadata) would decode to a space in the query.
+                let href = format!(
+                    "/ui/helm/catalog?repo={chart}&tag={}",
+                    version.version.replace('+', "%2B")
+                );
+                let row_class = if Some(version.version.as_str()) == active_version {
+                    "row active"
+                } else {
+                    "row"
+                };
+
+                let row = div()
+                    .class(row_class)
+                    .child(
+                        div().class("cell").child(
+                            a().attr("href", &href)
+                                .class("tag-link")
+                                .text(&version.version),
+                        ),
+                    )
+                    .child(
+                        div()
+                            .class("cell")
+                            .text(version.app_version.as_deref().unwrap_or("-")),
+                    )
+                    .child(
+                        div()
+                            .class("cell mono")
+                            .text(version.created.get(..19).unwrap_or(&version.created)),
+                    )
+                    .child(div().class("cell mono").text(&short_hex(&version.digest)))
+                    .child(
+                        div().class("cell actions").child(
+                            i().class("fas fa-trash")
+                                .attr("aria-hidden", "true")
+                                .attr("data-action", "delete-chart")
+                                .attr("data-chart", chart)
+                                .attr("data-version", &version.version)
+                                .attr("title", "Delete version")
+                                .attr("role", "button")
+                                .attr("aria-label", "Delete version")
+                                .on_click("handleChartDeleteClick(event)"),
+                        ),
+                    );
+
+                body = body.child(row);
+            }
+        }
+    }
+
+    div()
+        .class("panel table charts-grid")
+        .child(title)
+        .child(header)
+        .child(body)
+}
+
+// ---------------------------------------------------------------------------
+// Right-bottom panel – version details
+// ---------------------------------------------------------------------------
+
+fn render_details_panel(version: Option<&ChartVersion>) -> Element {
+    let title = match version {
+        Some(v) => div()
+            .class("panel-title")
+            .child(span().attr("data-i18n", "ui_metadata_for"))
+            .child(span().text(&format!(" {} {}", v.name, v.version))),
+        None => div().class("panel-title").attr("data-i18n", "ui_metadata"),
+    };
+
+    let Some(v) = version else {
+        return div().class("panel").child(title).child(
+            div()
+                .class("empty")
+                .attr("data-i18n", "ui_empty_select_version"),
+        );
+    };
+
+    let mut list = div().class("meta-list");
+    if let Some(description) = &v.description {
+        list = list.child(meta_row("ui_meta_description", description));
+    }
+    list = list
+        .child(meta_row("ui_meta_version", &v.version))
+        .child(meta_row(
+            "ui_meta_app_version",
+            v.app_version.as_deref().unwrap_or("-"),
+        ))
+        .child(meta_row("ui_meta_api_version", &v.api_version))
+        .child(meta_row(
+            "ui_meta_chart_type",
+            v.chart_type.as_deref().unwrap_or("application"),
+        ));
+    if v.deprecated {
+        list = list.child(meta_row("ui_meta_deprecated", "true"));
+    }
+
+    let keywords: Vec<&str> = v
+        .chart
+        .get("keywords")
+        .and_then(serde_json::Value::as_array)
+        .map(|k| k.iter().filter_map(serde_json::Value::as_str).collect())
+        .unwrap_or_default();
+    if !keywords.is_empty() {
+        list = list.child(meta_row("ui_meta_keywords", &keywords.join(", ")));
+    }
+    if let Some(home) = v.chart.get("home").and_then(serde_json::Value::as_str) {
+        list = list.child(meta_row("ui_meta_homepage", home));
+    }
+
+    list = list
+        .child(meta_row("ui_meta_digest", &v.digest))
+        .child(meta_row("ui_meta_size", &format_bytes(v.size_bytes)))
+        .child(meta_row(
+            "ui_meta_uploaded_by",
+            v.uploaded_by.as_deref().unwrap_or("-"),
+        ))
+        .child(meta_row("ui_meta_uploaded_at", &v.created))
+        .child(meta_row(
+            "ui_meta_repo_add",
+            &format!(
+                "helm repo add warehouse {}/helm",
+                REGISTRY_BASE_URL.trim_end_matches('/')
+            ),
+        ))
+        .child(
+            div().class("meta-row").child(div()).child(
+                div().child(
+                    button()
+                        .class("button")
+                        .attr("data-chart", &v.name)
+                        .attr("data-version", &v.version)
+                        .attr("data-i18n", "ui_download")
+                        .on_click("handleChartDownloadClick(event)"),
+                ),
+            ),
+        );
+
+    div().class("panel").child(title).child(list)
+}
+
+fn meta_row(label_key: &str, value: &str) -> Element {
+    div()
+        .class("meta-row")
+        .child(div().class("meta-label").attr("data-i18n", label_key))
+        .child(div().class("meta-value mono").text(value))
+}
+
+fn short_hex(hex: &str) -> String {
+    if hex.len() <= 16 {
+        return hex.to_string();
+    }
+    format!("{}…{}", &hex[..8], &hex[hex.len() - 8..])
+}
@@ -1,5 +1,5 @@
 use crate::routers::ui::common::{UiPageKind, render_page};
-use crate::routers::{crates_enabled, docker_enabled};
+use crate::routers::{crates_enabled, docker_enabled, files_enabled, helm_enabled};
 use actix_web::{HttpResponse, Responder, get};
 use quench::prelude::*;
 
@@ -34,8 +34,26 @@ fn render_home_page() -> HttpResponse {
         ));
     }
 
-    // If somehow neither feature is on, show a placeholder
-    if !docker_enabled() && !crates_enabled() {
+    if files_enabled() {
+        cards = cards.child(service_card(
+            "/ui/files/catalog",
+            "ui_service_files_title",
+            "ui_service_files_desc",
+            "home-card-files",
+        ));
+    }
+
+    if helm_enabled() {
+        cards = cards.child(service_card(
+            "/ui/helm/catalog",
+            "ui_service_helm_title",
+            "ui_service_helm_desc",
+            "home-card-helm",
+        ));
+    }
+
+    // If somehow no feature is on, show a placeholder
+    if !docker_enabled() && !crates_enabled() && !files_enabled() && !helm_enabled() {
         cards = cards.child(
             div()
                 .class("empty")
@@ -1,4 +1,6 @@
 pub mod auth;
 pub mod crates;
 pub mod docker;
+pub mod files;
+pub mod helm;
 pub mod home;
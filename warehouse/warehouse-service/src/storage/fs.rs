@@ -0,0 +1,196 @@
+use super::{ObjectStore, invalid_key, valid_key};
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+/// Staging directory for atomic writes, skipped when listing.
+const TMP_DIR: &str = ".tmp";
+
+/// Objects as plain files below a root directory.
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        Ok(self.root.join(key))
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        self.root
+            .join(TMP_DIR)
+            .join(uuid::Uuid::new_v4().simple().to_string())
+    }
+
+    /// Renames a file staged by [`tmp_path`](Self::tmp_path) to `key`.
+    async fn move_into(&self, key: &str, staged: &Path) -> io::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(staged, &path).await
+    }
+
+    /// Removes directories left empty by a delete, up to the root.
+    async fn prune_empty_parents(&self, path: &Path) {
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if current == self.root || !current.starts_with(&self.root) {
+                break;
+            }
+            if tokio::fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+fn not_found_as_none<T>(result: io::Result<T>) -> io::Result<Option<T>> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for FsStore {
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        not_found_as_none(tokio::fs::read(self.path(key)?).await)
+    }
+
+    async fn get_range(&self, key: &str, start: u64, end: u64) -> io::Result<Option<Vec<u8>>> {
+        let Some(mut file) = not_found_as_none(tokio::fs::File::open(self.path(key)?).await)?
+        else {
+            return Ok(None);
+        };
+        file.seek(SeekFrom::Start(start)).await?;
+        let mut buffer = vec![0u8; (end - start + 1) as usize];
+        file.read_exact(&mut buffer).await?;
+        Ok(Some(buffer))
+    }
+
+    async fn size(&self, key: &str) -> io::Result<Option<u64>> {
+        let metadata = not_found_as_none(tokio::fs::metadata(self.path(key)?).await)?;
+        Ok(metadata.filter(|m| m.is_file()).map(|m| m.len()))
+    }
+
+    async fn modified(&self, key: &str) -> io::Result<Option<SystemTime>> {
+        let metadata = not_found_as_none(tokio::fs::metadata(self.path(key)?).await)?;
+        metadata
+            .filter(|m| m.is_file())
+            .map(|m| m.modified())
+            .transpose()
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> io::Result<()> {
+        self.path(key)?;
+        let tmp = self.tmp_path();
+        if let Some(parent) = tmp.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&tmp, data).await?;
+
+        let result = self.move_into(key, &tmp).await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result
+    }
+
+    async fn compose(&self, key: &str, parts: &[String]) -> io::Result<()> {
+        self.path(key)?;
+        let tmp = self.tmp_path();
+        if let Some(parent) = tmp.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let written = async {
+            let mut file = tokio::fs::File::create(&tmp).await?;
+            for part in parts {
+                let mut source = tokio::fs::File::open(self.path(part)?).await?;
+                tokio::io::copy(&mut source, &mut file).await?;
+            }
+            file.flush().await
+        }
+        .await;
+        let result = match written {
+            Ok(()) => self.move_into(key, &tmp).await,
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        result
+    }
+
+    async fn touch(&self, key: &str) -> io::Result<()> {
+        let path = self.path(key)?;
+        let touched = tokio::task::spawn_blocking(move || {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .set_modified(SystemTime::now())
+        })
+        .await
+        .map_err(io::Error::other)?;
+        not_found_as_none(touched).map(|_| ())
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {
+                self.prune_empty_parents(&path).await;
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn list_sizes(&self, prefix: &str) -> io::Result<Vec<(String, u64)>> {
+        let dir = match prefix.strip_suffix('/') {
+            Some(p) => self.path(p)?,
+            None if prefix.is_empty() => self.root.clone(),
+            None => return Err(invalid_key(prefix)),
+        };
+
+        let mut keys = Vec::new();
+        let mut stack = vec![(dir, prefix.to_string())];
+        while let Some((dir, key_prefix)) = stack.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if key_prefix.is_empty() && name == TMP_DIR {
+                    continue;
+                }
+                let key = format!("{key_prefix}{name}");
+                let metadata = entry.metadata().await?;
+                if metadata.is_dir() {
+                    stack.push((entry.path(), format!("{key}/")));
+                } else {
+                    keys.push((key, metadata.len()));
+                }
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}
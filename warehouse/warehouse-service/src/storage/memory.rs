@@ -0,0 +1,101 @@
+use super::{ObjectStore, invalid_key, valid_key};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// Objects held in process memory; everything is lost on restart.
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: RwLock<BTreeMap<String, (Vec<u8>, SystemTime)>>,
+}
+
+impl MemoryStore {
+    fn read<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&(Vec<u8>, SystemTime)) -> T,
+    ) -> io::Result<Option<T>> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        let objects = self.objects.read().map_err(|_| poisoned())?;
+        Ok(objects.get(key).map(f))
+    }
+}
+
+fn poisoned() -> io::Error {
+    io::Error::other("memory store lock poisoned")
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for MemoryStore {
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        self.read(key, |(data, _)| data.clone())
+    }
+
+    async fn get_range(&self, key: &str, start: u64, end: u64) -> io::Result<Option<Vec<u8>>> {
+        self.read(key, |(data, _)| {
+            data.get(start as usize..=end as usize).map(<[u8]>::to_vec)
+        })?
+        .map(|range| range.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof)))
+        .transpose()
+    }
+
+    async fn size(&self, key: &str) -> io::Result<Option<u64>> {
+        self.read(key, |(data, _)| data.len() as u64)
+    }
+
+    async fn modified(&self, key: &str) -> io::Result<Option<SystemTime>> {
+        self.read(key, |(_, modified)| *modified)
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> io::Result<()> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        let mut objects = self.objects.write().map_err(|_| poisoned())?;
+        objects.insert(key.to_string(), (data, SystemTime::now()));
+        Ok(())
+    }
+
+    async fn compose(&self, key: &str, parts: &[String]) -> io::Result<()> {
+        let mut data = Vec::new();
+        for part in parts {
+            let content = self
+                .read(part, |(content, _)| content.clone())?
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+            data.extend(content);
+        }
+        self.put(key, data).await
+    }
+
+    async fn touch(&self, key: &str) -> io::Result<()> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        let mut objects = self.objects.write().map_err(|_| poisoned())?;
+        if let Some((_, modified)) = objects.get_mut(key) {
+            *modified = SystemTime::now();
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        let mut objects = self.objects.write().map_err(|_| poisoned())?;
+        objects.remove(key);
+        Ok(())
+    }
+
+    async fn list_sizes(&self, prefix: &str) -> io::Result<Vec<(String, u64)>> {
+        let objects = self.objects.read().map_err(|_| poisoned())?;
+        Ok(objects
+            .range(prefix.to_string()..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, (data, _))| (key.clone(), data.len() as u64))
+            .collect())
+    }
+}
@@ -0,0 +1,242 @@
+//! Object storage behind the registries.
+//!
+//! Everything the registries persist — blobs, manifests, tags, crate files,
+//! the sparse index, generic files, Helm charts — is addressed by a `/`-separated key
+//! relative to a store. The filesystem backend maps keys straight onto the
+//! historical on-disk layout, so existing storage directories keep working.
+//!
+//! `STORAGE_BACKEND` selects the implementation:
+//! - `fs` (default): files under `STORAGE_PATH` / `CRATES_STORAGE_PATH` /
+//!   `FILES_STORAGE_PATH` / `HELM_STORAGE_PATH`
+//! - `s3`: an S3-compatible bucket (AWS S3, MinIO, ...), see [`s3`]
+//! - `memory`: process memory only, for tests and throwaway instances
+//!
+//! In-progress Docker upload sessions are kept in the Docker store too,
+//! under `_uploads/` (see `routers::docker::uploads`).
+//!
+//! ## Running several replicas
+//!
+//! The store holds the registry content and the upload sessions, so any
+//! replica can serve any pull or push. The remaining state is not covered
+//! by the storage backends: moving it into a store would need change
+//! notifications between replicas (accounts) or leases on jobs (queues),
+//! which object storage does not offer. It lives on local disk whatever the
+//! backend:
+//!
+//! - Replicas behind one service must share a volume (`ReadWriteMany` in
+//!   Kubernetes) for accounts and tokens (`AUTH_STORAGE_PATH`) and the GC
+//!   history (`GC_HISTORY_PATH`).
+//! - The webhook and replication queues (`WEBHOOK_QUEUE_PATH`,
+//!   `REPLICATION_QUEUE_PATH`) and the audit log (`AUDIT_LOG_PATH`) need a
+//!   persistent path per replica: queue workers deliver every job in their
+//!   directory, so a shared queue would be delivered twice. `GET /admin/audit`
+//!   shows the log of the replica serving it.
+//!
+//! Accounts and tokens are read once at startup, so restart the other
+//! replicas after changing them. Locks are per process: crate index
+//! updates, tag writes, retention and GC are only serialised within one
+//! instance, and Docker quota usage is cached per instance (see
+//! `routers::quotas`), so send crate publishes and admin operations to a
+//! single replica.
+
+use std::io;
+use std::path::{Component, Path};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+pub mod fs;
+pub mod memory;
+pub mod s3;
+
+#[async_trait::async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Reads a whole object; `Ok(None)` when it does not exist.
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Reads the inclusive byte range `start..=end` of an object.
+    async fn get_range(&self, key: &str, start: u64, end: u64) -> io::Result<Option<Vec<u8>>>;
+
+    /// Size in bytes; `Ok(None)` when the object does not exist.
+    async fn size(&self, key: &str) -> io::Result<Option<u64>>;
+
+    /// Last modification time; `Ok(None)` when the object does not exist.
+    async fn modified(&self, key: &str) -> io::Result<Option<SystemTime>>;
+
+    /// Writes an object, replacing any previous content. Readers never see a
+    /// partially written object.
+    async fn put(&self, key: &str, data: Vec<u8>) -> io::Result<()>;
+
+    /// Writes the concatenation of the objects `parts`, in order, to `key`;
+    /// the parts are left in place. Fails with `NotFound` when a part is
+    /// missing.
+    async fn compose(&self, key: &str, parts: &[String]) -> io::Result<()>;
+
+    /// Sets the modification time of an object to now, e.g. so GC treats a
+    /// reused blob as new. Touching a missing object is not an error.
+    async fn touch(&self, key: &str) -> io::Result<()>;
+
+    /// Deletes an object. Deleting a missing object is not an error.
+    async fn delete(&self, key: &str) -> io::Result<()>;
+
+    /// Lists every key below `prefix`, which must be empty or end with `/`,
+    /// together with its size in bytes.
+    async fn list_sizes(&self, prefix: &str) -> io::Result<Vec<(String, u64)>>;
+
+    /// Lists every key below `prefix`, which must be empty or end with `/`.
+    async fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        Ok(self
+            .list_sizes(prefix)
+            .await?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+}
+
+/// Opens the configured backend for one registry. `namespace` separates the
+/// registries inside a shared bucket; `fs_root` is the directory used by the
+/// filesystem backend.
+pub fn open(namespace: &str, fs_root: &str) -> Arc<dyn ObjectStore> {
+    match envmnt::get_or("STORAGE_BACKEND", "fs")
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "fs" => Arc::new(fs::FsStore::new(fs_root)),
+        "memory" => Arc::new(memory::MemoryStore::default()),
+        "s3" => Arc::new(
+            s3::S3Store::from_env(namespace)
+                .unwrap_or_else(|e| panic!("config error: invalid s3 storage settings: {e}")),
+        ),
+        other => panic!("config error: unsupported STORAGE_BACKEND `{other}`"),
+    }
+}
+
+/// A key is a relative `/`-separated path without `.`/`..` segments.
+pub fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.contains('\\')
+        && !key.starts_with('/')
+        && !key.ends_with('/')
+        && Path::new(key)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn invalid_key(key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid storage key `{key}`"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObjectStore;
+    use super::fs::FsStore;
+    use super::memory::MemoryStore;
+    use std::io;
+    use std::time::{Duration, SystemTime};
+
+    fn keys(listed: &[(String, u64)]) -> Vec<&str> {
+        listed.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    /// Runs the behaviour every backend must share against `store`.
+    async fn exercise(store: &dyn ObjectStore) {
+        assert_eq!(store.get("a/missing").await.unwrap(), None);
+        assert_eq!(store.size("a/missing").await.unwrap(), None);
+        assert_eq!(store.modified("a/missing").await.unwrap(), None);
+
+        store.put("a/b/one", b"hello world".to_vec()).await.unwrap();
+        store.put("a/two", b"second".to_vec()).await.unwrap();
+        store.put("c/three", b"3".to_vec()).await.unwrap();
+        assert_eq!(store.get("a/b/one").await.unwrap().unwrap(), b"hello world");
+        assert_eq!(store.size("a/b/one").await.unwrap(), Some(11));
+        assert!(store.modified("a/b/one").await.unwrap().is_some());
+
+        store.put("a/two", b"replaced".to_vec()).await.unwrap();
+        assert_eq!(store.get("a/two").await.unwrap().unwrap(), b"replaced");
+
+        // Ranges are inclusive; reading past the end is an error.
+        assert_eq!(
+            store.get_range("a/b/one", 6, 10).await.unwrap().unwrap(),
+            b"world"
+        );
+        assert_eq!(
+            store.get_range("a/b/one", 0, 0).await.unwrap().unwrap(),
+            b"h"
+        );
+        assert_eq!(store.get_range("a/missing", 0, 1).await.unwrap(), None);
+        assert!(store.get_range("a/b/one", 5, 20).await.is_err());
+
+        assert_eq!(
+            store.list_sizes("a/").await.unwrap(),
+            [("a/b/one".to_string(), 11), ("a/two".to_string(), 8)]
+        );
+        assert_eq!(
+            store.list("").await.unwrap(),
+            ["a/b/one", "a/two", "c/three"]
+        );
+        assert!(store.list("x/").await.unwrap().is_empty());
+
+        for invalid in ["", "/abs", "a/../b", "a/", "a\\b"] {
+            assert!(store.put(invalid, Vec::new()).await.is_err(), "{invalid}");
+            assert!(store.get(invalid).await.is_err(), "{invalid}");
+        }
+
+        store
+            .compose("c/joined", &["a/two".to_string(), "a/b/one".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get("c/joined").await.unwrap().unwrap(),
+            b"replacedhello world"
+        );
+        assert_eq!(store.get("a/two").await.unwrap().unwrap(), b"replaced");
+        let missing = store
+            .compose("c/broken", &["a/two".to_string(), "a/missing".to_string()])
+            .await
+            .unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert_eq!(store.get("c/broken").await.unwrap(), None);
+
+        store.delete("a/b/one").await.unwrap();
+        store.delete("a/b/one").await.unwrap();
+        assert_eq!(store.get("a/b/one").await.unwrap(), None);
+        assert_eq!(store.list("a/").await.unwrap(), ["a/two"]);
+        assert_eq!(
+            keys(&store.list_sizes("").await.unwrap()),
+            ["a/two", "c/joined", "c/three"]
+        );
+    }
+
+    /// Touching moves an object's modification time forward.
+    async fn exercise_touch(store: &dyn ObjectStore) {
+        store.put("t/object", b"x".to_vec()).await.unwrap();
+        let written = store.modified("t/object").await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        store.touch("t/object").await.unwrap();
+        let touched = store.modified("t/object").await.unwrap().unwrap();
+        assert!(touched > written);
+        assert!(touched <= SystemTime::now());
+        store.touch("t/missing").await.unwrap();
+        assert_eq!(store.get("t/missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store() {
+        exercise(&MemoryStore::default()).await;
+        exercise_touch(&MemoryStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn fs_store() {
+        let root = std::env::temp_dir().join(format!("store-test-{}", uuid::Uuid::new_v4()));
+        exercise(&FsStore::new(&root)).await;
+        exercise_touch(&FsStore::new(&root)).await;
+        // Deletes prune the directories they empty.
+        assert!(!root.join("a/b").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
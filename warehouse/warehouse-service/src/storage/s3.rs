@@ -0,0 +1,624 @@
+//! S3-compatible object storage, signed with AWS Signature Version 4.
+//!
+//! Configuration:
+//! - `S3_ENDPOINT`: e.g. `https://s3.eu-west-1.amazonaws.com` or
+//!   `http://minio:9000` (path-style addressing is always used)
+//! - `S3_BUCKET`, `S3_ACCESS_KEY_ID`, `S3_SECRET_ACCESS_KEY`
+//! - `S3_REGION` (default `us-east-1`)
+//! - `S3_PREFIX` (optional): key prefix inside the bucket
+//!
+//! Objects of each registry live under `<S3_PREFIX><namespace>/`.
+//!
+//! Composed objects larger than one part are written with a multipart
+//! upload that copies their large parts within the bucket, so a blob is
+//! never held in memory as a whole.
+
+use super::{ObjectStore, invalid_key, valid_key};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::time::{Duration, SystemTime};
+
+/// Composed objects up to this size are assembled in memory and take one PUT.
+const PART_BYTES: usize = 8 * 1024 * 1024;
+
+/// S3 rejects multipart uploads with a part below 5 MiB other than the last.
+const MIN_PART_BYTES: u64 = 5 * 1024 * 1024;
+
+pub struct S3Store {
+    client: reqwest::Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    prefix: String,
+}
+
+impl S3Store {
+    pub fn from_env(namespace: &str) -> Result<Self, String> {
+        let required = |name: &str| {
+            let value = envmnt::get_or(name, "");
+            if value.trim().is_empty() {
+                Err(format!("{name} is not set"))
+            } else {
+                Ok(value.trim().to_string())
+            }
+        };
+
+        let endpoint = Url::parse(&required("S3_ENDPOINT")?)
+            .map_err(|e| format!("S3_ENDPOINT is not a valid url: {e}"))?;
+        let mut prefix = envmnt::get_or("S3_PREFIX", "")
+            .trim()
+            .trim_matches('/')
+            .to_string();
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        prefix.push_str(namespace);
+        prefix.push('/');
+
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .connect_timeout(Duration::from_secs(30))
+                .build()
+                .map_err(|e| e.to_string())?,
+            endpoint,
+            bucket: required("S3_BUCKET")?,
+            region: envmnt::get_or("S3_REGION", "us-east-1"),
+            access_key: required("S3_ACCESS_KEY_ID")?,
+            secret_key: required("S3_SECRET_ACCESS_KEY")?,
+            prefix,
+        })
+    }
+
+    fn object_path(&self, key: &str) -> io::Result<String> {
+        if !valid_key(key) {
+            return Err(invalid_key(key));
+        }
+        Ok(format!("/{}/{}{key}", self.bucket, self.prefix))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        range: Option<(u64, u64)>,
+        headers: &[(&str, &str)],
+    ) -> io::Result<reqwest::Response> {
+        let canonical_uri = uri_encode(path, false);
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&canonical_uri);
+        url.set_query((!canonical_query.is_empty()).then_some(canonical_query.as_str()));
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = format!("{:x}", Sha256::digest(&body));
+
+        // Every header is signed; SigV4 wants them sorted by name.
+        let mut signed: Vec<(&str, &str)> = vec![
+            ("host", &host),
+            ("x-amz-content-sha256", &payload_hash),
+            ("x-amz-date", &amz_date),
+        ];
+        signed.extend_from_slice(headers);
+        signed.sort();
+        let canonical_headers: String = signed
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let signed_headers = signed
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let canonical_request = format!(
+            "{method}\n{canonical_uri}\n{canonical_query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{:x}",
+            Sha256::digest(canonical_request.as_bytes())
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.secret_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hmac_sha256(&signing_key, string_to_sign.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                    self.access_key
+                ),
+            );
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some((start, end)) = range {
+            request = request.header("range", format!("bytes={start}-{end}"));
+        }
+        if !body.is_empty() {
+            request = request.body(body);
+        }
+
+        request.send().await.map_err(io::Error::other)
+    }
+
+    async fn begin_multipart<'a>(&'a self, key: &'a str) -> io::Result<Multipart<'a>> {
+        let path = self.object_path(key)?;
+        let response = self
+            .send(
+                Method::POST,
+                &path,
+                &[("uploads", "")],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(status_error(
+                "create multipart upload",
+                key,
+                response.status(),
+            ));
+        }
+        let body = response.text().await.map_err(io::Error::other)?;
+        let upload_id = xml_values(&body, "UploadId")
+            .into_iter()
+            .next()
+            .ok_or_else(|| io::Error::other(format!("s3 returned no upload id for `{key}`")))?;
+        Ok(Multipart {
+            store: self,
+            key,
+            path,
+            upload_id,
+            etags: Vec::new(),
+        })
+    }
+}
+
+/// A multipart upload in progress; parts are numbered in the order they are
+/// added.
+struct Multipart<'a> {
+    store: &'a S3Store,
+    key: &'a str,
+    path: String,
+    upload_id: String,
+    etags: Vec<String>,
+}
+
+impl Multipart<'_> {
+    /// Uploads the next part.
+    async fn upload(&mut self, data: Vec<u8>) -> io::Result<()> {
+        let number = (self.etags.len() + 1).to_string();
+        let query = [
+            ("partNumber", number.as_str()),
+            ("uploadId", &self.upload_id),
+        ];
+        let response = self
+            .store
+            .send(Method::PUT, &self.path, &query, data, None, &[])
+            .await?;
+        if !response.status().is_success() {
+            return Err(status_error("upload part", self.key, response.status()));
+        }
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| io::Error::other(format!("s3 returned no etag for `{}`", self.key)))?;
+        self.etags.push(etag.to_string());
+        Ok(())
+    }
+
+    /// Copies object `source` of the same store as the next part.
+    async fn copy(&mut self, source: &str) -> io::Result<()> {
+        let number = (self.etags.len() + 1).to_string();
+        let query = [
+            ("partNumber", number.as_str()),
+            ("uploadId", &self.upload_id),
+        ];
+        let copy_source = uri_encode(&self.store.object_path(source)?, false);
+        let response = self
+            .store
+            .send(
+                Method::PUT,
+                &self.path,
+                &query,
+                Vec::new(),
+                None,
+                &[("x-amz-copy-source", &copy_source)],
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(status_error("copy part", source, response.status()));
+        }
+        let body = response.text().await.map_err(io::Error::other)?;
+        let etag = xml_values(&body, "ETag")
+            .into_iter()
+            .next()
+            .ok_or_else(|| io::Error::other(format!("s3 returned no etag for `{source}`")))?;
+        self.etags.push(etag);
+        Ok(())
+    }
+
+    /// Completes the upload when every part was added, aborts it otherwise.
+    async fn finish(self, parts: io::Result<()>) -> io::Result<()> {
+        let result = match parts {
+            Ok(()) => self.complete().await,
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            self.abort().await;
+        }
+        result
+    }
+
+    async fn complete(&self) -> io::Result<()> {
+        let parts: String = self
+            .etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{etag}</ETag></Part>",
+                    i + 1
+                )
+            })
+            .collect();
+        let body = format!("<CompleteMultipartUpload>{parts}</CompleteMultipartUpload>");
+        let response = self
+            .store
+            .send(
+                Method::POST,
+                &self.path,
+                &[("uploadId", &self.upload_id)],
+                body.into_bytes(),
+                None,
+                &[],
+            )
+            .await?;
+        let status = response.status();
+        // S3 may report a failed completion in the body of a 200.
+        let body = response.text().await.map_err(io::Error::other)?;
+        if !status.is_success() || body.contains("<Error>") {
+            return Err(io::Error::other(format!(
+                "s3 complete multipart upload `{}` failed with {status}",
+                self.key
+            )));
+        }
+        Ok(())
+    }
+
+    async fn abort(&self) {
+        let aborted = self
+            .store
+            .send(
+                Method::DELETE,
+                &self.path,
+                &[("uploadId", &self.upload_id)],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await;
+        if let Err(e) = aborted {
+            tracing::warn!("failed to abort multipart upload of `{}`: {e}", self.key);
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 URI encoding; `/` is kept in paths and encoded in query values.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn status_error(op: &str, key: &str, status: StatusCode) -> io::Error {
+    io::Error::other(format!("s3 {op} `{key}` failed with {status}"))
+}
+
+/// Extracts the text of every `<tag>` element; enough for ListObjectsV2.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+#[async_trait::async_trait]
+impl ObjectStore for S3Store {
+    async fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.object_path(key)?,
+                &[],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(Some(
+                response.bytes().await.map_err(io::Error::other)?.to_vec(),
+            )),
+            s => Err(status_error("get", key, s)),
+        }
+    }
+
+    async fn get_range(&self, key: &str, start: u64, end: u64) -> io::Result<Option<Vec<u8>>> {
+        let response = self
+            .send(
+                Method::GET,
+                &self.object_path(key)?,
+                &[],
+                Vec::new(),
+                Some((start, end)),
+                &[],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(Some(
+                response.bytes().await.map_err(io::Error::other)?.to_vec(),
+            )),
+            s => Err(status_error("get", key, s)),
+        }
+    }
+
+    async fn size(&self, key: &str) -> io::Result<Option<u64>> {
+        let response = self
+            .send(
+                Method::HEAD,
+                &self.object_path(key)?,
+                &[],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(response
+                .headers()
+                .get("content-length")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())),
+            s => Err(status_error("head", key, s)),
+        }
+    }
+
+    async fn modified(&self, key: &str) -> io::Result<Option<SystemTime>> {
+        let response = self
+            .send(
+                Method::HEAD,
+                &self.object_path(key)?,
+                &[],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(response
+                .headers()
+                .get("last-modified")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+                .map(SystemTime::from)),
+            s => Err(status_error("head", key, s)),
+        }
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> io::Result<()> {
+        let response = self
+            .send(Method::PUT, &self.object_path(key)?, &[], data, None, &[])
+            .await?;
+        if !response.status().is_success() {
+            return Err(status_error("put", key, response.status()));
+        }
+        Ok(())
+    }
+
+    async fn compose(&self, key: &str, parts: &[String]) -> io::Result<()> {
+        let missing = |part: &str| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("s3 object `{part}` does not exist"),
+            )
+        };
+        let mut sizes = Vec::with_capacity(parts.len());
+        for part in parts {
+            sizes.push(self.size(part).await?.ok_or_else(|| missing(part))?);
+        }
+
+        if sizes.iter().sum::<u64>() <= PART_BYTES as u64 {
+            let mut data = Vec::new();
+            for part in parts {
+                data.extend(self.get(part).await?.ok_or_else(|| missing(part))?);
+            }
+            return self.put(key, data).await;
+        }
+
+        let mut upload = self.begin_multipart(key).await?;
+        let copied = async {
+            // Large parts are copied within the bucket; smaller ones are
+            // gathered until they make a valid part.
+            let mut pending = Vec::new();
+            for (part, size) in parts.iter().zip(sizes) {
+                if pending.is_empty() && size >= MIN_PART_BYTES {
+                    upload.copy(part).await?;
+                    continue;
+                }
+                pending.extend(self.get(part).await?.ok_or_else(|| missing(part))?);
+                if pending.len() as u64 >= MIN_PART_BYTES {
+                    upload.upload(std::mem::take(&mut pending)).await?;
+                }
+            }
+            if !pending.is_empty() {
+                upload.upload(pending).await?;
+            }
+            Ok(())
+        }
+        .await;
+        upload.finish(copied).await
+    }
+
+    async fn touch(&self, key: &str) -> io::Result<()> {
+        // Objects cannot be touched in place: copy the object onto itself,
+        // which S3 only allows when the metadata is replaced.
+        let path = self.object_path(key)?;
+        let response = self
+            .send(
+                Method::PUT,
+                &path,
+                &[],
+                Vec::new(),
+                None,
+                &[
+                    ("x-amz-copy-source", &uri_encode(&path, false)),
+                    ("x-amz-metadata-directive", "REPLACE"),
+                ],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(()),
+            s if s.is_success() => Ok(()),
+            s => Err(status_error("copy", key, s)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        let response = self
+            .send(
+                Method::DELETE,
+                &self.object_path(key)?,
+                &[],
+                Vec::new(),
+                None,
+                &[],
+            )
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(()),
+            s if s.is_success() => Ok(()),
+            s => Err(status_error("delete", key, s)),
+        }
+    }
+
+    async fn list_sizes(&self, prefix: &str) -> io::Result<Vec<(String, u64)>> {
+        let full_prefix = format!("{}{prefix}", self.prefix);
+        let bucket_path = format!("/{}", self.bucket);
+        let mut keys = Vec::new();
+        let mut continuation: Option<String> = None;
+
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", full_prefix.as_str())];
+            if let Some(token) = continuation.as_deref() {
+                query.push(("continuation-token", token));
+            }
+            let response = self
+                .send(Method::GET, &bucket_path, &query, Vec::new(), None, &[])
+                .await?;
+            if !response.status().is_success() {
+                return Err(status_error("list", prefix, response.status()));
+            }
+            let body = response.text().await.map_err(io::Error::other)?;
+
+            // Every `<Contents>` carries exactly one `<Key>` and one `<Size>`.
+            let sizes = xml_values(&body, "Size");
+            keys.extend(
+                xml_values(&body, "Key")
+                    .into_iter()
+                    .zip(sizes)
+                    .filter_map(|(k, size)| {
+                        let key = k.strip_prefix(&self.prefix)?.to_string();
+                        Some((key, size.parse().unwrap_or_default()))
+                    }),
+            );
+
+            continuation = xml_values(&body, "NextContinuationToken")
+                .into_iter()
+                .next();
+            let truncated = xml_values(&body, "IsTruncated")
+                .first()
+                .is_some_and(|v| v == "true");
+            if !truncated || continuation.is_none() {
+                break;
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}